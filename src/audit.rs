@@ -0,0 +1,171 @@
+//! Audit records of verifications, for append-only logs (e.g. one JSON object per line)
+
+use std::time::{Duration, SystemTime};
+
+use crate::errors::{Error, Kind};
+use crate::types::block::commit::SignedHeader;
+use crate::types::block::traits::commit::ProvableCommit;
+use crate::types::block::traits::header::{Header, Height};
+use crate::types::chain;
+use crate::types::time::Time;
+use crate::types::traits::trusted::TrustThreshold;
+use crate::types::traits::validator::Validator;
+use crate::types::traits::validator_set::ValidatorSet;
+use crate::types::trusted::TrustedState;
+use crate::verification::verify_single;
+
+/// Whether the untrusted header of an audited verification was accepted.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Verified,
+    Failed,
+}
+
+/// What happened during a single verification, as produced by `verify_single_audited`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// The `now` the verification was done at.
+    pub timestamp: Time,
+    /// Chain of the untrusted header.
+    pub chain_id: chain::Id,
+    pub trusted_height: Height,
+    pub untrusted_height: Height,
+    pub outcome: AuditOutcome,
+    /// Voting power which signed the untrusted header, and the total it is measured
+    /// against: the untrusted validators once verified, the trusted validators for a
+    /// skip without enough trusted power. Unknown for failures before the signatures.
+    pub signed_power: Option<u64>,
+    pub total_power: Option<u64>,
+    /// Category of the error (see `Kind::category`), if the verification failed.
+    pub error_kind: Option<String>,
+    /// The error message, if the verification failed.
+    pub error: Option<String>,
+}
+
+/// Same as `verify_single`, also returning an audit record of the verification, whatever
+/// its outcome. Recording the signed power of a verified header means verifying the
+/// signatures of its commit a second time.
+pub fn verify_single_audited<H, C, L, V>(
+    trusted_state: TrustedState<C, H, V>,
+    untrusted_sh: &SignedHeader<C, H>,
+    untrusted_vals: &C::ValidatorSet,
+    untrusted_next_vals: &C::ValidatorSet,
+    trust_threshold: L,
+    trusting_period: Duration,
+    now: SystemTime,
+) -> (Result<TrustedState<C, H, V>, Error>, AuditRecord)
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    L: TrustThreshold,
+    V: Validator,
+{
+    let untrusted_header = untrusted_sh.header();
+    let mut record = AuditRecord {
+        timestamp: now.into(),
+        chain_id: untrusted_header.chain_id(),
+        trusted_height: trusted_state.last_header().header().height(),
+        untrusted_height: untrusted_header.height(),
+        outcome: AuditOutcome::Verified,
+        signed_power: None,
+        total_power: None,
+        error_kind: None,
+        error: None,
+    };
+
+    let result = verify_single(
+        trusted_state,
+        untrusted_sh,
+        untrusted_vals,
+        untrusted_next_vals,
+        trust_threshold,
+        trusting_period,
+        now,
+    );
+    match &result {
+        Ok(_) => {
+            record.signed_power = untrusted_sh
+                .commit()
+                .voting_power_in(untrusted_header.chain_id(), untrusted_vals)
+                .ok();
+            record.total_power = Some(untrusted_vals.total_power());
+        }
+        Err(e) => {
+            record.outcome = AuditOutcome::Failed;
+            if let Kind::InvalidCommit { total, signed }
+            | Kind::InsufficientSignedVotingPower { total, signed, .. } = e.kind()
+            {
+                record.signed_power = Some(*signed);
+                record.total_power = Some(*total);
+            }
+            record.error_kind = Some(e.kind().category().to_string());
+            record.error = Some(e.to_string());
+        }
+    }
+    (result, record)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::mocks::{
+        generate_keypairs, light_header, signed_commit, trusted_state_at, validator_set,
+    };
+    use crate::{LightSignedHeader, TrustThresholdFraction};
+
+    #[test]
+    fn test_verify_single_audited() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 10);
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let header = light_header(2, time, vals.hash(), vals.hash());
+        let now = SystemTime::UNIX_EPOCH + Duration::new(20, 0);
+        let audit = |signers: &[usize]| {
+            let commit = signed_commit(&header, &keypairs, &vals, signers);
+            verify_single_audited(
+                trusted_state.clone(),
+                &LightSignedHeader::new(commit, header.clone()),
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                now,
+            )
+        };
+
+        let (result, record) = audit(&[0, 1, 2]);
+        assert!(result.is_ok());
+        assert_eq!(
+            record,
+            AuditRecord {
+                timestamp: now.into(),
+                chain_id: header.chain_id,
+                trusted_height: 1,
+                untrusted_height: 2,
+                outcome: AuditOutcome::Verified,
+                signed_power: Some(30),
+                total_power: Some(40),
+                error_kind: None,
+                error: None,
+            }
+        );
+
+        let (result, record) = audit(&[0, 1]);
+        assert!(result.is_err());
+        assert_eq!(record.outcome, AuditOutcome::Failed);
+        assert_eq!(record.signed_power, Some(20));
+        assert_eq!(record.total_power, Some(40));
+        assert_eq!(record.error_kind.as_deref(), Some("invalid commit"));
+        assert_eq!(record.error, Some(result.err().unwrap().to_string()));
+
+        // one JSON object per record
+        let line = serde_json::to_string(&record).unwrap();
+        assert!(!line.contains('\n'));
+        assert!(line.contains(r#""outcome":"failed""#));
+        let parsed: AuditRecord = serde_json::from_str(&line).unwrap();
+        assert_eq!(parsed, record);
+    }
+}
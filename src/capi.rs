@@ -0,0 +1,249 @@
+//! C ABI around `verify_from_json`, enabled by the `capi` feature, for embedding the
+//! verifier in non-Rust hosts. Build a shared library with e.g.
+//! `cargo rustc --release --features capi --crate-type cdylib`.
+//!
+//! Every input is a JSON document passed as a byte buffer. The result is returned as a
+//! status code along with an output buffer allocated by this library, holding the new
+//! trusted state (JSON) on success and the error message otherwise, which the host
+//! must release with `light_client_free_buffer`.
+
+use std::panic;
+use std::ptr;
+use std::slice;
+use std::str;
+
+use crate::errors::{Error, Kind};
+use crate::ffi::verify_from_json;
+
+/// The header was verified, the output buffer holds the new trusted state.
+pub const LIGHT_CLIENT_OK: i32 = 0;
+/// A required pointer was null.
+pub const LIGHT_CLIENT_NULL_POINTER: i32 = 1;
+/// An input buffer isn't valid UTF-8.
+pub const LIGHT_CLIENT_INVALID_UTF8: i32 = 2;
+/// An input couldn't be parsed, the output buffer holds the error message.
+pub const LIGHT_CLIENT_PARSE_ERROR: i32 = 3;
+/// The header couldn't be verified, the output buffer holds the error message.
+pub const LIGHT_CLIENT_VERIFICATION_FAILED: i32 = 4;
+/// The verifier panicked; this is a bug.
+pub const LIGHT_CLIENT_PANIC: i32 = 5;
+
+/// A byte buffer borrowed from the host.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct LightClientInput {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+/// A byte buffer allocated by this library, to release with `light_client_free_buffer`.
+#[repr(C)]
+pub struct LightClientOutput {
+    pub ptr: *mut u8,
+    pub len: usize,
+}
+
+/// Verify `signed_header` against `trusted_state`, see `verify_from_json`.
+///
+/// On return `*output` is set to a buffer allocated by this library (or to a null, empty
+/// buffer if nothing was produced), except when `output` itself is null.
+///
+/// # Safety
+///
+/// Each input must point to `len` readable bytes, which aren't modified during the call,
+/// or be null (only valid with a zero `len`). `output` must be null or point to writable
+/// memory for a `LightClientOutput`.
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn light_client_verify_json(
+    trusted_state: LightClientInput,
+    signed_header: LightClientInput,
+    validators: LightClientInput,
+    next_validators: LightClientInput,
+    trust_threshold: LightClientInput,
+    trusting_period_secs: u64,
+    now_unix_secs: u64,
+    output: *mut LightClientOutput,
+) -> i32 {
+    if output.is_null() {
+        return LIGHT_CLIENT_NULL_POINTER;
+    }
+    *output = LightClientOutput {
+        ptr: ptr::null_mut(),
+        len: 0,
+    };
+
+    let inputs = [
+        trusted_state,
+        signed_header,
+        validators,
+        next_validators,
+        trust_threshold,
+    ];
+    let mut strs = Vec::with_capacity(inputs.len());
+    for input in inputs.iter() {
+        let bytes = if input.ptr.is_null() {
+            if input.len != 0 {
+                return LIGHT_CLIENT_NULL_POINTER;
+            }
+            &[][..]
+        } else {
+            slice::from_raw_parts(input.ptr, input.len)
+        };
+        match str::from_utf8(bytes) {
+            Ok(s) => strs.push(s),
+            Err(_) => return LIGHT_CLIENT_INVALID_UTF8,
+        }
+    }
+
+    let result = panic::catch_unwind(|| {
+        verify_from_json(
+            strs[0],
+            strs[1],
+            strs[2],
+            strs[3],
+            strs[4],
+            trusting_period_secs,
+            now_unix_secs,
+        )
+    });
+    let (status, message) = match result {
+        Ok(Ok(new_state)) => (LIGHT_CLIENT_OK, new_state),
+        Ok(Err(e)) => (status_of(&e), e.to_string()),
+        Err(_) => return LIGHT_CLIENT_PANIC,
+    };
+    *output = into_output(message.into_bytes());
+    status
+}
+
+/// Release a buffer returned by this library. Null buffers are ignored.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by this library and not released before.
+#[no_mangle]
+pub unsafe extern "C" fn light_client_free_buffer(buffer: LightClientOutput) {
+    if !buffer.ptr.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buffer.ptr, buffer.len,
+        )));
+    }
+}
+
+fn status_of(e: &Error) -> i32 {
+    match e.kind() {
+        Kind::Parse => LIGHT_CLIENT_PARSE_ERROR,
+        _ => LIGHT_CLIENT_VERIFICATION_FAILED,
+    }
+}
+
+fn into_output(bytes: Vec<u8>) -> LightClientOutput {
+    let bytes = bytes.into_boxed_slice();
+    let len = bytes.len();
+    LightClientOutput {
+        ptr: Box::into_raw(bytes) as *mut u8,
+        len,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::mocks::{
+        generate_keypairs, signed_header_at, trusted_state_at, validator_set,
+    };
+    use crate::types::time::Time;
+    use crate::types::trusted::LightTrustedState;
+    use std::time::Duration;
+
+    fn input(bytes: &[u8]) -> LightClientInput {
+        LightClientInput {
+            ptr: bytes.as_ptr(),
+            len: bytes.len(),
+        }
+    }
+
+    fn read(output: &LightClientOutput) -> String {
+        let bytes = unsafe { slice::from_raw_parts(output.ptr, output.len) };
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_light_client_verify_json() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let sh = signed_header_at(2, time, &keypairs, &vals, &[0, 1, 2, 3]);
+
+        let trusted_state_json = serde_json::to_vec(&trusted_state).unwrap();
+        let sh_json = serde_json::to_vec(&sh).unwrap();
+        let vals_json = serde_json::to_vec(&vals).unwrap();
+        let threshold_json = br#"{"numerator":"2","denominator":"3"}"#;
+
+        let verify = |sh: LightClientInput, now: u64, output: *mut LightClientOutput| unsafe {
+            light_client_verify_json(
+                input(&trusted_state_json),
+                sh,
+                input(&vals_json),
+                input(&vals_json),
+                input(threshold_json),
+                100,
+                now,
+                output,
+            )
+        };
+        let mut output = LightClientOutput {
+            ptr: ptr::null_mut(),
+            len: 0,
+        };
+
+        assert_eq!(verify(input(&sh_json), 20, &mut output), LIGHT_CLIENT_OK);
+        let new_state: LightTrustedState = serde_json::from_str(&read(&output)).unwrap();
+        assert_eq!(new_state, LightTrustedState::new(sh, vals));
+        unsafe { light_client_free_buffer(output) };
+
+        let mut output = LightClientOutput {
+            ptr: ptr::null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            verify(input(&sh_json), 200, &mut output),
+            LIGHT_CLIENT_VERIFICATION_FAILED
+        );
+        assert!(read(&output).starts_with("old header has expired"));
+        unsafe { light_client_free_buffer(output) };
+
+        let mut output = LightClientOutput {
+            ptr: ptr::null_mut(),
+            len: 0,
+        };
+        assert_eq!(
+            verify(input(&sh_json[1..]), 20, &mut output),
+            LIGHT_CLIENT_PARSE_ERROR
+        );
+        unsafe { light_client_free_buffer(output) };
+
+        // invalid buffers don't produce any output
+        let mut output = LightClientOutput {
+            ptr: ptr::null_mut(),
+            len: 0,
+        };
+        let dangling = LightClientInput {
+            ptr: ptr::null(),
+            len: 3,
+        };
+        assert_eq!(verify(dangling, 20, &mut output), LIGHT_CLIENT_NULL_POINTER);
+        assert!(output.ptr.is_null());
+        assert_eq!(
+            verify(input(&[0xff, 0xfe]), 20, &mut output),
+            LIGHT_CLIENT_INVALID_UTF8
+        );
+        assert!(output.ptr.is_null());
+        assert_eq!(
+            verify(input(&sh_json), 20, ptr::null_mut()),
+            LIGHT_CLIENT_NULL_POINTER
+        );
+    }
+}
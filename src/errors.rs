@@ -1,8 +1,9 @@
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use anomaly::{BoxError, Context};
 use thiserror::Error;
 
+use crate::types::chain;
 use crate::types::hash::Hash;
 
 /// The main error type verification methods will return.
@@ -12,18 +13,53 @@ pub type Error = anomaly::Error<Kind>;
 /// All error kinds related to the light client.
 #[derive(Clone, Debug, Error)]
 pub enum Kind {
-    /// The provided header expired.
+    /// The provided header expired. The client has to be re-initialized from a new
+    /// trusted source, see `LightClient::reset`.
     #[error("old header has expired at {at:?} (now: {now:?})")]
     Expired { at: SystemTime, now: SystemTime },
 
     /// Trusted header is from the future.
+    /// Superseded by [`Kind::TrustedHeaderFromFuture`], no longer returned.
     #[error("trusted header time is too far in the future")]
     DurationOutOfRange,
 
+    /// Trusted header is from the future: the local clock or the trusted state is off.
+    #[error("trusted header time {header_time:?} is after now {now:?}")]
+    TrustedHeaderFromFuture {
+        header_time: SystemTime,
+        now: SystemTime,
+    },
+
+    /// Untrusted header is from the future, even accounting for clock drift.
+    #[error("untrusted header time {header_time:?} is after now {now:?} + max clock drift {max_clock_drift:?}")]
+    UntrustedHeaderFromFuture {
+        header_time: SystemTime,
+        now: SystemTime,
+        max_clock_drift: Duration,
+    },
+
+    /// Untrusted header is older than the client accepts, even though the trusted state
+    /// hasn't expired.
+    #[error(
+        "untrusted header time {header_time:?} is more than {max_header_age:?} before now {now:?}"
+    )]
+    StaleHeader {
+        header_time: SystemTime,
+        now: SystemTime,
+        max_header_age: Duration,
+    },
+
     /// Header height smaller than expected.
     #[error("expected height >= {expected} (got: {got})")]
     NonIncreasingHeight { got: u64, expected: u64 },
 
+    /// Skipping to the header would go further than the client allows in one step.
+    #[error("header is {distance} heights ahead of the trusted header (max skip distance: {max_skip_distance})")]
+    SkipTooFar {
+        distance: u64,
+        max_skip_distance: u64,
+    },
+
     /// Header time is in the past compared to already trusted header.
     #[error("untrusted header time <= trusted header time")]
     NonIncreasingTime,
@@ -51,6 +87,13 @@ pub enum Kind {
         commit_hash: Hash,
     },
 
+    /// Commit is for another height than its header.
+    #[error("commit is for height {commit_height}, header is at height {header_height}")]
+    HeightMismatch {
+        header_height: u64,
+        commit_height: u64,
+    },
+
     /// Signed power does not account for +2/3 of total voting power.
     #[error("signed voting power ({signed}) do not account for +2/3 of the total voting power: ({total})")]
     InvalidCommit { total: u64, signed: u64 },
@@ -63,12 +106,82 @@ pub enum Kind {
         total: u64,
         signed: u64,
         trust_threshold: String,
+        /// Additional signed power that would have been needed to meet the threshold
+        power_gap: u64,
+    },
+
+    /// Too little of a validator set's voting power carried over to the next set.
+    #[error("validators carried over hold {overlap} of the previous total voting power ({total}), threshold: {trust_threshold}")]
+    InsufficientValidatorSetOverlap {
+        overlap: u64,
+        total: u64,
+        trust_threshold: String,
     },
 
     /// This is returned if an invalid TrustThreshold is created.
     #[error("A valid threshold is `1/3 <= threshold <= 1`, got: {got}")]
     InvalidTrustThreshold { got: String },
 
+    /// Header is from another chain than the one the client is locked to.
+    #[error("header's chain id does not match the expected chain id ({got}!={expected})")]
+    ChainIdMismatch { got: chain::Id, expected: chain::Id },
+
+    /// Header carries an all-zero consensus hash.
+    #[error("header's consensus hash is zero")]
+    ZeroConsensusHash,
+
+    /// Header isn't shaped like the first block of a chain.
+    #[error("invalid genesis header")]
+    InvalidGenesisHeader,
+
+    /// Adjacent header doesn't follow the trusted block.
+    #[error("header's last block id does not match the trusted block ({header_last_block_hash:?}!={trusted_block_hash:?})")]
+    InvalidLastBlockId {
+        header_last_block_hash: Hash,
+        trusted_block_hash: Hash,
+    },
+
+    /// Header is not the one a trusted source told us to expect.
+    #[error("header hash does not match the expected hash ({header_hash:?}!={expected_hash:?})")]
+    UnexpectedHeaderHash {
+        header_hash: Hash,
+        expected_hash: Hash,
+    },
+
+    /// Header doesn't commit to the application state we expect (hex encoded hashes).
+    #[error("header's app hash does not match the expected app hash ({header_app_hash}!={expected_app_hash})")]
+    InvalidAppHash {
+        header_app_hash: String,
+        expected_app_hash: String,
+    },
+
+    /// Validator set response doesn't contain the whole set (e.g. a truncated page).
+    #[error("incomplete validator set (expected {expected} validators, got: {got})")]
+    IncompleteValidatorSet { expected: u64, got: u64 },
+
+    /// Merkle proof doesn't show the validator at the given index of the header's validator set.
+    #[error("invalid inclusion proof for validator {index} of {total}")]
+    InvalidValidatorProof { index: usize, total: usize },
+
+    /// Reported total voting power of a validator set differs from the sum of its validators.
+    #[error("total voting power mismatch (reported: {reported}, computed: {computed})")]
+    TotalVotingPowerMismatch { reported: u64, computed: u64 },
+
+    /// Validator set larger than the client accepts (see `VerificationOptions::max_validators`).
+    #[error("validator set has {count} validators (max: {max_validators})")]
+    TooManyValidators { count: usize, max_validators: usize },
+
+    /// Header block version differs from the consensus version we expect.
+    #[error("unexpected block version (expected: {expected}, got: {got})")]
+    UnexpectedBlockVersion { expected: u64, got: u64 },
+
+    /// Header's data hash isn't the merkle root of the supplied transactions.
+    #[error("header's data hash does not match the transactions ({header_data_hash:?}!={computed_data_hash:?})")]
+    InvalidDataHash {
+        header_data_hash: Option<Hash>,
+        computed_data_hash: Option<Hash>,
+    },
+
     /// Use the [`Kind::context`] method to wrap the underlying error of
     /// the implementation, if any.
     #[error("Implementation specific error")]
@@ -96,4 +209,314 @@ impl Kind {
     pub fn context(self, source: impl Into<BoxError>) -> Context<Kind> {
         Context::new(self, Some(source.into()))
     }
+
+    /// Short, user-facing category of this error, e.g. for grouping errors in dashboards.
+    /// One of "expired", "bad time", "bad header", "bad validators", "invalid commit",
+    /// "insufficient power", "invalid input" or "implementation specific".
+    pub fn category(&self) -> &'static str {
+        match self {
+            Kind::Expired { .. } => "expired",
+            Kind::DurationOutOfRange
+            | Kind::TrustedHeaderFromFuture { .. }
+            | Kind::UntrustedHeaderFromFuture { .. }
+            | Kind::StaleHeader { .. }
+            | Kind::NonIncreasingTime => "bad time",
+            Kind::NonIncreasingHeight { .. }
+            | Kind::SkipTooFar { .. }
+            | Kind::ChainIdMismatch { .. }
+            | Kind::ZeroConsensusHash
+            | Kind::InvalidGenesisHeader
+            | Kind::InvalidLastBlockId { .. }
+            | Kind::UnexpectedHeaderHash { .. }
+            | Kind::InvalidAppHash { .. }
+            | Kind::UnexpectedBlockVersion { .. }
+            | Kind::InvalidDataHash { .. } => "bad header",
+            Kind::InvalidValidatorSet { .. }
+            | Kind::InvalidNextValidatorSet { .. }
+            | Kind::InsufficientValidatorSetOverlap { .. }
+            | Kind::IncompleteValidatorSet { .. }
+            | Kind::InvalidValidatorProof { .. }
+            | Kind::TotalVotingPowerMismatch { .. }
+            | Kind::TooManyValidators { .. } => "bad validators",
+            Kind::InvalidCommitValue { .. }
+            | Kind::HeightMismatch { .. }
+            | Kind::InvalidCommit { .. } => "invalid commit",
+            Kind::InsufficientSignedVotingPower { .. } => "insufficient power",
+            Kind::InvalidTrustThreshold { .. }
+            | Kind::OutOfRange
+            | Kind::Parse
+            | Kind::InvalidKey
+            | Kind::Length => "invalid input",
+            Kind::ImplementationSpecific => "implementation specific",
+        }
+    }
+
+    /// Explanation of this error for operators: its likely cause and what to do next.
+    pub fn explain(&self) -> String {
+        match self {
+            Kind::Expired { at, .. } => format!(
+                "The trusted state expired at {:?}: it is older than the trusting period, so its validators may no longer be bonded and can't be relied upon. Re-initialize the client from a header and validators obtained from a trusted source (see LightClient::reset).",
+                at
+            ),
+            Kind::DurationOutOfRange => "The trusted header is dated too far in the future. Check the local clock, then the trusted state.".to_string(),
+            Kind::TrustedHeaderFromFuture { .. } => "The trusted header is dated after the current time. Either the local clock is behind or the trusted state is corrupted: check the clock first, then reload the trusted state from a trusted source.".to_string(),
+            Kind::UntrustedHeaderFromFuture { max_clock_drift, .. } => format!(
+                "The header is dated after the current time, beyond the tolerated clock drift of {:?}. Check the local clock; if it is right, the provider is serving a bogus header.",
+                max_clock_drift
+            ),
+            Kind::StaleHeader { max_header_age, .. } => format!(
+                "The header is older than the accepted age of {:?}. The provider is likely lagging behind the chain: fetch a recent header, or from another provider.",
+                max_header_age
+            ),
+            Kind::NonIncreasingHeight { expected, .. } => format!(
+                "The header isn't after the trusted one. Fetch a header at height {} or above.",
+                expected
+            ),
+            Kind::SkipTooFar { max_skip_distance, .. } => format!(
+                "The header is too far ahead of the trusted one to be verified in one step. Verify intermediate headers first, at most {} heights apart.",
+                max_skip_distance
+            ),
+            Kind::NonIncreasingTime => "The header isn't dated after the trusted one, which a valid chain never does. The provider is likely serving a bogus or forked header: try another provider.".to_string(),
+            Kind::InvalidValidatorSet { .. } => "The validator set doesn't hash to the header's validators hash. Check it was fetched at the header's height h, e.g. /validators?height=h rather than h+1, and that it is complete.".to_string(),
+            Kind::InvalidNextValidatorSet { .. } => "The next validator set doesn't hash to the header's next validators hash. Check it was fetched at height h+1 for a header at height h, and that it is complete.".to_string(),
+            Kind::InvalidCommitValue { .. } => "The commit is for another block than the header. Check both were fetched at the same height from the same provider.".to_string(),
+            Kind::HeightMismatch { header_height, .. } => format!(
+                "The commit is for another height than the header. Fetch the commit at height {}.",
+                header_height
+            ),
+            Kind::InvalidCommit { .. } => "The commit isn't signed by more than 2/3 of the voting power of its validators, or carries invalid signatures. The header can't be final: fetch it again, or from another provider.".to_string(),
+            Kind::InsufficientSignedVotingPower { power_gap, .. } => format!(
+                "Too few of the trusted validators signed the header to skip to it ({} more voting power was needed). Verify an intermediate header first, closer to the trusted one.",
+                power_gap
+            ),
+            Kind::InsufficientValidatorSetOverlap { .. } => "Too little of the voting power carried over between the validator sets. Verify the headers in between one at a time.".to_string(),
+            Kind::InvalidTrustThreshold { .. } => "The trust threshold is invalid. Use a fraction between 1/3 and 1, e.g. 1/3 or 2/3.".to_string(),
+            Kind::ChainIdMismatch { expected, .. } => format!(
+                "The header is from another chain than {}. Check the provider is configured for the right chain.",
+                expected
+            ),
+            Kind::ZeroConsensusHash => "The header's consensus hash is zero, which tendermint never produces. The provider is likely serving a bogus header: try another provider.".to_string(),
+            Kind::InvalidGenesisHeader => "The header isn't the first block of a chain: it refers to a previous block. Check it was fetched at height 1.".to_string(),
+            Kind::InvalidLastBlockId { .. } => "The header doesn't follow the trusted block: the provider is on a fork, or the trusted state is for another chain. Compare the trusted block hash with a trusted source.".to_string(),
+            Kind::UnexpectedHeaderHash { .. } => "The header isn't the one the trusted source expects. Either the provider is on a fork or the expected hash is for another height: compare both with a trusted source.".to_string(),
+            Kind::InvalidAppHash { .. } => "The header commits to another application state than the expected one. Check the expected app hash is for this height; app hashes are those of the state after the previous block.".to_string(),
+            Kind::IncompleteValidatorSet { .. } => "The validator set response is missing validators. Fetch all of its pages, e.g. with a larger per_page.".to_string(),
+            Kind::InvalidValidatorProof { .. } => "The inclusion proof doesn't show the validator in the header's validator set. Fetch the proof again, at the header's height.".to_string(),
+            Kind::TotalVotingPowerMismatch { .. } => "The reported total voting power doesn't add up. The validator set response is likely corrupted or truncated: fetch it again.".to_string(),
+            Kind::TooManyValidators { max_validators, .. } => format!(
+                "The validator set is larger than the {} validators accepted, which is suspicious. Check the provider, or raise the limit if the chain does have that many validators.",
+                max_validators
+            ),
+            Kind::UnexpectedBlockVersion { .. } => "The header was produced by another block protocol version than expected. The chain likely upgraded: update the client before following it further.".to_string(),
+            Kind::InvalidDataHash { .. } => "The transactions don't match the header's data hash. Check they are the transactions of this block, in order.".to_string(),
+            Kind::ImplementationSpecific => "The implementation of a header, commit or validator type failed. See the underlying error for details.".to_string(),
+            Kind::OutOfRange => "A value is out of its allowed range. Check the input is well-formed.".to_string(),
+            Kind::Parse => "The input couldn't be parsed. Check its encoding and format.".to_string(),
+            Kind::InvalidKey => "A key is malformed or of an unsupported type. Check the key type is supported, or register a signature scheme for it.".to_string(),
+            Kind::Length => "A value has an incorrect length. Check the input isn't truncated or padded.".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Kind;
+    use crate::types::mocks::fixed_hash;
+    use std::time::{Duration, SystemTime};
+
+    // every kind, with its category
+    fn kinds() -> Vec<(Kind, &'static str)> {
+        let now = SystemTime::UNIX_EPOCH;
+        let hash = fixed_hash();
+        vec![
+            (Kind::Expired { at: now, now }, "expired"),
+            (Kind::DurationOutOfRange, "bad time"),
+            (
+                Kind::TrustedHeaderFromFuture {
+                    header_time: now,
+                    now,
+                },
+                "bad time",
+            ),
+            (
+                Kind::UntrustedHeaderFromFuture {
+                    header_time: now,
+                    now,
+                    max_clock_drift: Duration::new(0, 0),
+                },
+                "bad time",
+            ),
+            (
+                Kind::StaleHeader {
+                    header_time: now,
+                    now,
+                    max_header_age: Duration::new(0, 0),
+                },
+                "bad time",
+            ),
+            (Kind::NonIncreasingTime, "bad time"),
+            (
+                Kind::NonIncreasingHeight {
+                    got: 1,
+                    expected: 2,
+                },
+                "bad header",
+            ),
+            (
+                Kind::ChainIdMismatch {
+                    got: "other-chain".into(),
+                    expected: "test-chain".into(),
+                },
+                "bad header",
+            ),
+            (
+                Kind::SkipTooFar {
+                    distance: 10,
+                    max_skip_distance: 5,
+                },
+                "bad header",
+            ),
+            (Kind::ZeroConsensusHash, "bad header"),
+            (Kind::InvalidGenesisHeader, "bad header"),
+            (
+                Kind::InvalidLastBlockId {
+                    header_last_block_hash: hash,
+                    trusted_block_hash: hash,
+                },
+                "bad header",
+            ),
+            (
+                Kind::UnexpectedHeaderHash {
+                    header_hash: hash,
+                    expected_hash: hash,
+                },
+                "bad header",
+            ),
+            (
+                Kind::InvalidAppHash {
+                    header_app_hash: "AB".to_string(),
+                    expected_app_hash: "CD".to_string(),
+                },
+                "bad header",
+            ),
+            (
+                Kind::UnexpectedBlockVersion {
+                    expected: 10,
+                    got: 11,
+                },
+                "bad header",
+            ),
+            (
+                Kind::InvalidDataHash {
+                    header_data_hash: None,
+                    computed_data_hash: None,
+                },
+                "bad header",
+            ),
+            (
+                Kind::InvalidValidatorSet {
+                    header_val_hash: hash,
+                    expected_val_hash: hash,
+                },
+                "bad validators",
+            ),
+            (
+                Kind::InvalidNextValidatorSet {
+                    header_next_val_hash: hash,
+                    expected_next_val_hash: hash,
+                },
+                "bad validators",
+            ),
+            (
+                Kind::InsufficientValidatorSetOverlap {
+                    overlap: 1,
+                    total: 3,
+                    trust_threshold: "1/3".to_string(),
+                },
+                "bad validators",
+            ),
+            (
+                Kind::IncompleteValidatorSet {
+                    expected: 2,
+                    got: 1,
+                },
+                "bad validators",
+            ),
+            (
+                Kind::InvalidValidatorProof { index: 1, total: 4 },
+                "bad validators",
+            ),
+            (
+                Kind::TotalVotingPowerMismatch {
+                    reported: 2,
+                    computed: 1,
+                },
+                "bad validators",
+            ),
+            (
+                Kind::TooManyValidators {
+                    count: 2,
+                    max_validators: 1,
+                },
+                "bad validators",
+            ),
+            (
+                Kind::InvalidCommitValue {
+                    header_hash: hash,
+                    commit_hash: hash,
+                },
+                "invalid commit",
+            ),
+            (
+                Kind::HeightMismatch {
+                    header_height: 10,
+                    commit_height: 11,
+                },
+                "invalid commit",
+            ),
+            (
+                Kind::InvalidCommit {
+                    total: 3,
+                    signed: 1,
+                },
+                "invalid commit",
+            ),
+            (
+                Kind::InsufficientSignedVotingPower {
+                    total: 3,
+                    signed: 1,
+                    trust_threshold: "1/3".to_string(),
+                    power_gap: 1,
+                },
+                "insufficient power",
+            ),
+            (
+                Kind::InvalidTrustThreshold {
+                    got: "1/4".to_string(),
+                },
+                "invalid input",
+            ),
+            (Kind::OutOfRange, "invalid input"),
+            (Kind::Parse, "invalid input"),
+            (Kind::InvalidKey, "invalid input"),
+            (Kind::Length, "invalid input"),
+            (Kind::ImplementationSpecific, "implementation specific"),
+        ]
+    }
+
+    #[test]
+    fn test_category() {
+        for (kind, category) in kinds() {
+            assert_eq!(kind.category(), category, "category of {:?}", kind);
+        }
+    }
+
+    #[test]
+    fn test_explain() {
+        let explanations: Vec<String> = kinds().iter().map(|(kind, _)| kind.explain()).collect();
+        for (i, explanation) in explanations.iter().enumerate() {
+            assert!(!explanation.is_empty());
+            assert!(!explanations[..i].contains(explanation), "{}", explanation);
+        }
+    }
 }
@@ -0,0 +1,350 @@
+//! Detection of misbehaving validators from conflicting commits
+
+use anomaly::fail;
+
+use crate::errors::{Error, Kind};
+use crate::types::account;
+use crate::types::block::commit::Commit;
+use crate::types::block::commit_sigs::CommitSig;
+use crate::types::chain;
+use crate::types::traits::validator::Validator;
+use crate::types::validator::Set;
+use std::collections::HashSet;
+
+/// Find the validators that signed both of the given commits, which are for
+/// different blocks at the same height and round. Only validators whose signatures
+/// verify on *both* commits are returned, so a forged vote can't frame a validator.
+///
+/// Conflicting votes across different rounds are not equivocation, so commits
+/// from different rounds are rejected.
+pub fn find_equivocators<V>(
+    commit_a: &Commit,
+    commit_b: &Commit,
+    chain_id: chain::Id,
+    vals: &Set<V>,
+) -> Result<Vec<account::Id>, Error>
+where
+    V: Validator,
+{
+    if commit_a.height != commit_b.height || commit_a.round != commit_b.round {
+        fail!(
+            Kind::ImplementationSpecific,
+            "commits are not for the same height and round ({}/{} != {}/{})",
+            commit_a.height,
+            commit_a.round,
+            commit_b.height,
+            commit_b.round
+        );
+    }
+    common_signers(commit_a, commit_b, chain_id, vals, true)
+}
+
+/// Find the validators that signed both of the given commits, which are for
+/// different blocks at the same height but in different rounds (amnesia attack).
+/// As with `find_equivocators`, only signatures which verify on both commits count.
+///
+/// Only votes for the blocks count: precommitting nil in a round and the block in a
+/// later one is what honest validators do.
+///
+/// Commits from the same round are rejected, use `find_equivocators` for those.
+pub fn detect_amnesia<V>(
+    commit_a: &Commit,
+    commit_b: &Commit,
+    chain_id: chain::Id,
+    vals: &Set<V>,
+) -> Result<Vec<account::Id>, Error>
+where
+    V: Validator,
+{
+    if commit_a.height != commit_b.height {
+        fail!(
+            Kind::ImplementationSpecific,
+            "commits are not for the same height ({} != {})",
+            commit_a.height,
+            commit_b.height
+        );
+    }
+    if commit_a.round == commit_b.round {
+        fail!(
+            Kind::ImplementationSpecific,
+            "commits are for the same round {}",
+            commit_a.round
+        );
+    }
+    common_signers(commit_a, commit_b, chain_id, vals, false)
+}
+
+/// Compare the signers of two commits at the same height, e.g. for a block re-proposed
+/// in a later round. Returns the validators which only signed the first commit and those
+/// which only signed the second one, each sorted by address. Only signatures which
+/// verify are taken into account.
+pub fn commit_signer_diff<V>(
+    commit_a: &Commit,
+    commit_b: &Commit,
+    chain_id: chain::Id,
+    vals: &Set<V>,
+) -> Result<(Vec<account::Id>, Vec<account::Id>), Error>
+where
+    V: Validator,
+{
+    if commit_a.height != commit_b.height {
+        fail!(
+            Kind::ImplementationSpecific,
+            "commits are not for the same height ({} != {})",
+            commit_a.height,
+            commit_b.height
+        );
+    }
+
+    let signers_a = commit_a.verified_signers(chain_id, vals)?;
+    let signers_b = commit_b.verified_signers(chain_id, vals)?;
+
+    let mut only_in_a: Vec<account::Id> = signers_a.difference(&signers_b).cloned().collect();
+    let mut only_in_b: Vec<account::Id> = signers_b.difference(&signers_a).cloned().collect();
+    only_in_a.sort();
+    only_in_b.sort();
+    Ok((only_in_a, only_in_b))
+}
+
+// Validators which correctly signed both commits, sorted by address. Without
+// nil_votes, only their votes for the blocks of the commits count.
+// Fails if the commits are for the same block, as that is no misbehaviour.
+fn common_signers<V>(
+    commit_a: &Commit,
+    commit_b: &Commit,
+    chain_id: chain::Id,
+    vals: &Set<V>,
+    nil_votes: bool,
+) -> Result<Vec<account::Id>, Error>
+where
+    V: Validator,
+{
+    if commit_a.block_id == commit_b.block_id {
+        fail!(
+            Kind::ImplementationSpecific,
+            "commits are for the same block {}",
+            commit_a.block_id
+        );
+    }
+
+    let mut signers_a = commit_a.verified_signers(chain_id, vals)?;
+    let mut signers_b = commit_b.verified_signers(chain_id, vals)?;
+    if !nil_votes {
+        let block_voters_a = block_voters(commit_a);
+        let block_voters_b = block_voters(commit_b);
+        signers_a.retain(|signer| block_voters_a.contains(signer));
+        signers_b.retain(|signer| block_voters_b.contains(signer));
+    }
+
+    let mut signers: Vec<account::Id> = signers_a.intersection(&signers_b).cloned().collect();
+    signers.sort();
+    Ok(signers)
+}
+
+// Validators which voted for the block of the commit, rather than nil.
+fn block_voters(commit: &Commit) -> HashSet<account::Id> {
+    commit
+        .signatures
+        .iter()
+        .filter_map(|commit_sig| match commit_sig {
+            CommitSig::BlockIDFlagCommit {
+                validator_address, ..
+            } => Some(*validator_address),
+            CommitSig::BlockIDFlagAbsent | CommitSig::BlockIDFlagNil { .. } => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evidence::{commit_signer_diff, detect_amnesia, find_equivocators};
+    use crate::types::block::commit::{Commit, CommitSigs};
+    use crate::types::block::commit_sigs::CommitSig;
+    use crate::types::chain;
+    use crate::types::mocks::{
+        generate_keypairs, light_header, signed_commit, signed_commit_at_round, validator_set,
+    };
+    use crate::types::signature::Signature;
+    use crate::types::traits::validator::Validator;
+    use crate::types::traits::validator_set::ValidatorSet;
+    use crate::Time;
+    use ed25519_dalek::{Keypair, Signer};
+    use std::time::Duration;
+
+    // turn the vote of the validator at the given index into a correctly signed
+    // precommit for nil.
+    fn precommit_nil(commit: &mut Commit, chain_id: chain::Id, keypair: &Keypair, index: usize) {
+        let mut sigs = commit.signatures.clone().into_vec();
+        let (validator_address, timestamp) = match &sigs[index] {
+            CommitSig::BlockIDFlagCommit {
+                validator_address,
+                timestamp,
+                ..
+            } => (*validator_address, *timestamp),
+            _ => panic!("expected a vote for the block"),
+        };
+        sigs[index] = CommitSig::BlockIDFlagNil {
+            validator_address,
+            timestamp,
+            signature: Signature::new(vec![]),
+        };
+        commit.signatures = CommitSigs::new(sigs.clone());
+
+        let vote = commit
+            .signed_votes(chain_id)
+            .into_iter()
+            .map(|vote| vote.unwrap())
+            .find(|vote| vote.validator_id() == validator_address)
+            .unwrap();
+        sigs[index] = CommitSig::BlockIDFlagNil {
+            validator_address,
+            timestamp,
+            signature: Signature::new(keypair.sign(&vote.sign_bytes()).to_bytes().to_vec()),
+        };
+        commit.signatures = CommitSigs::new(sigs);
+    }
+
+    #[test]
+    fn test_find_equivocators() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header_a = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let mut header_b = header_a.clone();
+        header_b.time = header_a.time + Duration::new(1, 0);
+
+        // validator 2 signed both blocks
+        let commit_a = signed_commit(&header_a, &keypairs, &vals, &[0, 1, 2]);
+        let mut commit_b = signed_commit(&header_b, &keypairs, &vals, &[2, 3]);
+
+        // validator 0 appears in the second commit too, but with its signature
+        // for the first block, which doesn't verify for the second one
+        let mut sigs_b = commit_b.signatures.clone().into_vec();
+        sigs_b[0] = commit_a.signatures[0].clone();
+        commit_b.signatures = CommitSigs::new(sigs_b);
+        match &commit_b.signatures[0] {
+            CommitSig::BlockIDFlagCommit { .. } => (),
+            _ => panic!("expected a forged vote"),
+        }
+
+        let equivocators =
+            find_equivocators(&commit_a, &commit_b, header_a.chain_id, &vals).unwrap();
+        assert_eq!(equivocators, vec![vals.validators()[2].address()]);
+
+        // the same block can't be used as evidence
+        let res = find_equivocators(&commit_a, &commit_a, header_a.chain_id, &vals);
+        assert!(res.is_err());
+
+        // neither can commits at different heights
+        let header_c = light_header(11, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit_c = signed_commit(&header_c, &keypairs, &vals, &[2]);
+        let res = find_equivocators(&commit_a, &commit_c, header_a.chain_id, &vals);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_detect_amnesia() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header_a = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let mut header_b = header_a.clone();
+        header_b.time = header_a.time + Duration::new(1, 0);
+
+        // validators 1 and 2 signed the first block in round 0,
+        // and the conflicting one in round 1
+        let commit_a = signed_commit_at_round(&header_a, &keypairs, &vals, &[0, 1, 2], 0);
+        let commit_b = signed_commit_at_round(&header_b, &keypairs, &vals, &[1, 2, 3], 1);
+
+        let signers = detect_amnesia(&commit_a, &commit_b, header_a.chain_id, &vals).unwrap();
+        let mut expected = vec![
+            vals.validators()[1].address(),
+            vals.validators()[2].address(),
+        ];
+        expected.sort();
+        assert_eq!(signers, expected);
+
+        // cross round votes are not equivocation
+        let res = find_equivocators(&commit_a, &commit_b, header_a.chain_id, &vals);
+        assert!(res.is_err());
+
+        // and same round votes are not amnesia
+        let commit_c = signed_commit_at_round(&header_b, &keypairs, &vals, &[1, 2, 3], 0);
+        let res = detect_amnesia(&commit_a, &commit_c, header_a.chain_id, &vals);
+        assert!(res.is_err());
+
+        // committing the same block in another round is fine
+        let commit_d = signed_commit_at_round(&header_a, &keypairs, &vals, &[1, 2, 3], 1);
+        let res = detect_amnesia(&commit_a, &commit_d, header_a.chain_id, &vals);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_commit_signer_diff() {
+        let keypairs = generate_keypairs(5);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+
+        // the same block, committed in two rounds by overlapping signers
+        let commit_a = signed_commit_at_round(&header, &keypairs, &vals, &[0, 1, 2, 3], 0);
+        let commit_b = signed_commit_at_round(&header, &keypairs, &vals, &[2, 3, 4], 1);
+
+        let (only_in_a, only_in_b) =
+            commit_signer_diff(&commit_a, &commit_b, header.chain_id, &vals).unwrap();
+        let mut expected_a = vec![
+            vals.validators()[0].address(),
+            vals.validators()[1].address(),
+        ];
+        expected_a.sort();
+        assert_eq!(only_in_a, expected_a);
+        assert_eq!(only_in_b, vec![vals.validators()[4].address()]);
+
+        // swapping the commits swaps the result
+        let (only_in_b_swapped, only_in_a_swapped) =
+            commit_signer_diff(&commit_b, &commit_a, header.chain_id, &vals).unwrap();
+        assert_eq!(only_in_a_swapped, only_in_a);
+        assert_eq!(only_in_b_swapped, only_in_b);
+
+        // a commit doesn't differ from itself
+        let (only_in_a, only_in_b) =
+            commit_signer_diff(&commit_a, &commit_a, header.chain_id, &vals).unwrap();
+        assert!(only_in_a.is_empty());
+        assert!(only_in_b.is_empty());
+
+        // commits at different heights can't be compared
+        let header_c = light_header(11, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit_c = signed_commit(&header_c, &keypairs, &vals, &[2]);
+        let res = commit_signer_diff(&commit_a, &commit_c, header.chain_id, &vals);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_nil_precommits() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let chain_id = "test-chain".parse().unwrap();
+        let header_a = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let mut header_b = header_a.clone();
+        header_b.time = header_a.time + Duration::new(1, 0);
+
+        // validator 1 precommitted nil in round 0, then the block in round 1, validator 2
+        // precommitted nil in both rounds: they behaved honestly
+        let mut commit_a = signed_commit_at_round(&header_a, &keypairs, &vals, &[0, 1, 2, 3], 0);
+        precommit_nil(&mut commit_a, chain_id, &keypairs[1], 1);
+        precommit_nil(&mut commit_a, chain_id, &keypairs[2], 2);
+        let mut commit_b = signed_commit_at_round(&header_b, &keypairs, &vals, &[1, 2, 3], 1);
+        precommit_nil(&mut commit_b, chain_id, &keypairs[2], 2);
+        assert_eq!(commit_a.verified_signers(chain_id, &vals).unwrap().len(), 4);
+
+        let signers = detect_amnesia(&commit_a, &commit_b, chain_id, &vals).unwrap();
+        assert_eq!(signers, vec![vals.validators()[3].address()]);
+
+        // in the same round, a nil precommit and a vote for the block are a double vote
+        let commit_c = signed_commit_at_round(&header_b, &keypairs, &vals, &[1, 3], 0);
+        let equivocators = find_equivocators(&commit_a, &commit_c, chain_id, &vals).unwrap();
+        let mut expected = vec![
+            vals.validators()[1].address(),
+            vals.validators()[3].address(),
+        ];
+        expected.sort();
+        assert_eq!(equivocators, expected);
+    }
+}
@@ -0,0 +1,99 @@
+//! Entry points for callers across a wasm or FFI boundary, exchanging JSON strings
+
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::errors::{Error, Kind};
+use crate::types::block::commit::LightSignedHeader;
+use crate::types::trusted::{LightTrustedState, TrustThresholdFraction};
+use crate::types::validator::{Info, Set};
+use crate::verification::verify_single;
+
+/// Same as `verify_single` for the concrete types, with every input and the output
+/// serialized as JSON: the trusted state, the untrusted signed header, its validators
+/// and next validators (JSON arrays of validators) and the trust threshold (e.g.
+/// `{"numerator":"2","denominator":"3"}`). The trusting period and the current time are
+/// whole seconds, the latter since the unix epoch. Returns the new trusted state.
+///
+/// Malformed inputs are reported as `Kind::Parse` errors, times beyond what the platform
+/// can represent as `Kind::OutOfRange` errors.
+pub fn verify_from_json(
+    trusted_state_json: &str,
+    signed_header_json: &str,
+    validators_json: &str,
+    next_validators_json: &str,
+    trust_threshold_json: &str,
+    trusting_period_secs: u64,
+    now_unix_secs: u64,
+) -> Result<String, Error> {
+    let trusted_state: LightTrustedState = parse_json(trusted_state_json)?;
+    let signed_header: LightSignedHeader = parse_json(signed_header_json)?;
+    let validators: Set<Info> = parse_json(validators_json)?;
+    let next_validators: Set<Info> = parse_json(next_validators_json)?;
+    let trust_threshold: TrustThresholdFraction = parse_json(trust_threshold_json)?;
+    let now = UNIX_EPOCH
+        .checked_add(Duration::from_secs(now_unix_secs))
+        .ok_or_else(|| Error::from(Kind::OutOfRange))?;
+
+    let new_state = verify_single(
+        trusted_state,
+        &signed_header,
+        &validators,
+        &next_validators,
+        trust_threshold,
+        Duration::from_secs(trusting_period_secs),
+        now,
+    )?;
+    serde_json::to_string(&new_state).map_err(|e| Kind::ImplementationSpecific.context(e).into())
+}
+
+fn parse_json<T>(json: &str) -> Result<T, Error>
+where
+    T: serde::de::DeserializeOwned,
+{
+    serde_json::from_str(json).map_err(|e| Kind::Parse.context(e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // what the host on the other side of the boundary holds: a state trusting height 1
+    // (at 1s) and the header at height 2 (at 2s), signed by all of the 4 validators
+    const TRUSTED_STATE_JSON: &str = include_str!("../tests/support/ffi/trusted_state.json");
+    const SIGNED_HEADER_JSON: &str = include_str!("../tests/support/ffi/signed_header.json");
+    const VALIDATORS_JSON: &str = include_str!("../tests/support/ffi/validators.json");
+    const THRESHOLD_JSON: &str = r#"{"numerator":"2","denominator":"3"}"#;
+
+    fn verify(sh_json: &str, trusting_period_secs: u64, now: u64) -> Result<String, Error> {
+        verify_from_json(
+            TRUSTED_STATE_JSON,
+            sh_json,
+            VALIDATORS_JSON,
+            VALIDATORS_JSON,
+            THRESHOLD_JSON,
+            trusting_period_secs,
+            now,
+        )
+    }
+
+    #[test]
+    fn test_verify_from_json() {
+        let new_state_json = verify(SIGNED_HEADER_JSON, 100, 20).unwrap();
+        let new_state: LightTrustedState = serde_json::from_str(&new_state_json).unwrap();
+        let sh: LightSignedHeader = serde_json::from_str(SIGNED_HEADER_JSON).unwrap();
+        let vals: Set<Info> = serde_json::from_str(VALIDATORS_JSON).unwrap();
+        assert_eq!(new_state, LightTrustedState::new(sh, vals));
+
+        // verification errors are passed through
+        let res = verify(SIGNED_HEADER_JSON, 100, 200);
+        assert!(matches!(res.err().unwrap().kind(), Kind::Expired { .. }));
+        // malformed input is a parse error
+        let res = verify(&SIGNED_HEADER_JSON[1..], 100, 20);
+        assert!(matches!(res.err().unwrap().kind(), Kind::Parse));
+        // times which can't be represented are rejected rather than overflowing
+        let res = verify(SIGNED_HEADER_JSON, 100, u64::MAX);
+        assert!(matches!(res.err().unwrap().kind(), Kind::OutOfRange));
+        let res = verify(SIGNED_HEADER_JSON, u64::MAX, 20);
+        assert!(matches!(res.err().unwrap().kind(), Kind::OutOfRange));
+    }
+}
@@ -1,9 +1,17 @@
+mod audit;
+#[cfg(feature = "capi")]
+pub mod capi;
 mod errors;
+mod evidence;
+mod ffi;
+mod light_client;
 mod merkle_tree;
 mod serialization;
+mod telemetry;
 mod types;
 mod utils;
 mod verification;
+mod warnings;
 
 #[macro_use]
 extern crate serde_derive;
@@ -11,20 +19,55 @@ extern crate serde_derive;
 /// Types required to construct call to verification functionality
 // Concrete header
 pub use types::block::header::Header as LightHeader;
+// Tendermint version whose header layout LightHeader::try_hash_with_version follows
+pub use types::block::header::HeaderVersion;
 // Concrete signed header
 pub use types::block::commit::LightSignedHeader;
 // Generic signed header
 pub use types::block::commit::SignedHeader;
+// Distinct chain ids of a batch of signed headers
+pub use types::block::commit::chain_ids_of;
 // Commit type which implements ProvableCommit
 pub use types::block::commit::Commit;
+// Outcome of Commit::voting_power_in_report, for debugging partially bad commits
+pub use types::block::commit::VotingPowerReport;
+// Compact commit carrying a bitmap of its signers
+pub use types::block::bitmap_commit::{BitmapCommit, BitmapSignature};
+// Signed header bundled with its validators and next validators
+pub use types::block::light_block::LightBlock;
+// What ProvableCommit::voting_power_in_with_policy does with duplicate votes
+pub use types::block::traits::commit::DuplicatePolicy;
+// Signature of a single validator within a commit, and its block_id_flag
+pub use serialization::raw_commit_sigs::BlockIDFlag as BlockIdFlag;
+pub use types::block::commit_sigs::CommitSig;
+// Serializable record of a single vote of a commit
+pub use types::vote::vote::VoteRecord;
+// Length of the bytes signed for a vote, e.g. for gas estimation
+pub use types::vote::vote::canonical_vote_sign_bytes_len;
 // Trusted state data types
 pub use types::trusted::TrustThresholdFraction;
 pub use types::trusted::TrustedState;
+// Denominator of the trust thresholds read from floats, before reducing them
+pub use types::trusted::TRUST_THRESHOLD_FLOAT_DENOMINATOR;
+// Trusted state of the concrete types
+pub use types::trusted::LightTrustedState;
+// Relationship of a height to the height of a trusted state
+pub use types::trusted::Relationship;
+// Trusted state along with its chain id, for multi-chain stores
+pub use types::trusted::PersistedTrustedState;
 // Validator data types
 pub use types::validator::Info as LightValidator;
 pub use types::validator::Set as LightValidatorSet;
+pub use types::validator::ValidatorLookup;
+pub use types::validator::ValidatorSetHasher;
+// Validators added, removed and re-weighted between two validator sets
+pub use types::validator::ValSetUpdate;
+// Response of the /validators RPC endpoint
+pub use types::validator_response::ValidatorSetResponse;
 // Time data type.
 pub use types::time::Time;
+// ChainId data type.
+pub use types::chain::Id as ChainId;
 // ClientId data type.
 pub use types::client::Id as ClientId;
 // AccountId data type.
@@ -35,12 +78,88 @@ pub use types::pubkey::PublicKey;
 pub use types::vote::power::Power as VotePower;
 // Enum encapsulating ed25519 and Secp256k1 signature types
 pub use types::signature::Signature;
+// Verifiers of validator signatures by key type, see VerificationOptions::signature_schemes
+pub use types::signature_scheme::{
+    SignatureSchemeRegistry, SignatureVerifier, ED25519_KEY_TYPE, SECP256K1_KEY_TYPE,
+};
 
 // Generic Function to call to validate a header
 pub use verification::verify_single;
+// Same as verify_single, with optional behaviour
+pub use verification::{
+    verify_single_with_options, HashMismatch, MismatchedHash, VerificationOptions,
+    DEFAULT_MAX_CLOCK_DRIFT, DEFAULT_MAX_COMMIT_ROUND,
+};
+// Same as verify_single_with_options, also returning the validators the header was trusted on
+pub use verification::{verify_single_with_output, VerificationOutput};
+// Same as verify_single_with_options, also returning the decisions taken
+pub use verification::{verify_single_with_trace, Decision, DecisionTrace};
+// Same as verify_single, also checking the header against a hash from a trusted source
+pub use verification::verify_single_expecting;
+// Generic function to verify a header when the next validator set isn't available
+pub use verification::verify_single_without_next_vals;
+// Same as verify_single, taking the validators as plain slices
+pub use verification::verify_single_from_slices;
+// Same as verify_single, taking a LightBlock
+pub use verification::verify_single_light_block;
+// Same as verify_single, loading the validator set only once it is needed
+pub use verification::verify_single_lazy;
 // Generic function to validate initial signed header and validator set
 // Client must create trusted set only if this function returns Ok.
 pub use verification::validate_initial_signed_header_and_valset;
+// Same, with a configurable threshold
+pub use verification::verify_self_contained;
+// Same, also checking the header is the first block of the chain
+pub use verification::verify_genesis_header;
+// Lower-level function to verify a commit against a precomputed validator lookup
+pub use verification::verify_commit_with_lookup;
+// Function to verify many commits signed by the same validator set on a thread pool
+#[cfg(feature = "parallel")]
+pub use verification::verify_commits_parallel;
+// Function to compute how much voting power a failed skip was missing
+pub use verification::power_gap;
+// Function to report the fraction of voting power which signed a commit
+pub use verification::signed_fraction;
+// Function to tell whether a single validator holds enough power to be trusted on its own
+pub use verification::is_power_concentrated;
+// Function to check that consecutive validator sets overlap enough
+pub use verification::verify_valset_continuity;
+// Function to measure the turnover of voting power across consecutive validator sets
+pub use verification::valset_churn;
+// Function to check a fetched validator set against the trusted header's next validators hash
+pub use verification::verify_next_validators;
+// Whether a transition changes the validators, for relayers building client updates
+pub use verification::requires_valset_update;
+// Pick the validator set of a header among candidate sets
+pub use verification::match_validator_set;
+// Function to check that trusted states loaded from disk are consistent with each other
+pub use verification::verify_trusted_state_chain;
+// Function to check a header against the block protocol version the client expects
+pub use verification::verify_version;
+// Function to check a header's data hash against the block's transactions
+pub use verification::verify_data_hash;
+// Function to check that a validator is part of a header's validator set with a merkle proof
+pub use verification::verify_validator_in_set;
+// Same as verify_single, also producing an audit record of the verification
+pub use audit::{verify_single_audited, AuditOutcome, AuditRecord};
+// Stateful client keeping the trusted state, resettable after expiry, catching up with sync_to
+pub use light_client::{FetchFuture, HeaderFetcher, LightClient};
+// Same as verify_single, also returning warnings about risky but valid transitions
+pub use warnings::{verify_single_with_warnings, Warning, LARGE_VALIDATOR_SET_CHANGE};
+// JSON in, JSON out version of verify_single, for wasm and FFI callers
+pub use ffi::verify_from_json;
+// Function to find validators which signed two conflicting commits
+pub use evidence::find_equivocators;
+// Function to find validators which signed conflicting commits in different rounds
+pub use evidence::detect_amnesia;
+// Function to compare the signers of two commits at the same height
+pub use evidence::commit_signer_diff;
+// Names of the metrics emitted with the metrics feature
+#[cfg(feature = "metrics")]
+pub use telemetry::{
+    SIGNATURE_VERIFY_SECONDS, VERIFICATIONS_ATTEMPTED, VERIFICATIONS_FAILED,
+    VERIFICATIONS_SUCCEEDED,
+};
 
 /// Traits inherited by some of the exposed types
 pub mod traits {
@@ -52,6 +171,14 @@ pub mod traits {
     pub use super::types::traits::trusted::TrustThreshold;
     // Provable commit trait implemented by LightSignedHeader
     pub use super::types::block::traits::commit::ProvableCommit;
+    // Hooks for commits carrying a single aggregate signature (e.g. BLS)
+    pub use super::types::block::traits::commit::{AggregateCommit, AggregateVerifier};
     // Validator trait implemented by LightValidator
     pub use super::types::traits::validator::Validator;
 }
+
+/// Alternative serde formats for the exposed types
+pub mod serializers {
+    // Lower-case hex (de)serialization of hashes, for #[serde(with = "...")]
+    pub use super::serialization::bytes::lower_hash;
+}
@@ -0,0 +1,377 @@
+//! Minimal stateful light client, for callers which don't keep the trusted state themselves
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, SystemTime};
+
+use anomaly::fail;
+
+use crate::errors::{Error, Kind};
+use crate::types::block::commit::SignedHeader;
+use crate::types::block::light_block::LightBlock;
+use crate::types::block::traits::commit::ProvableCommit;
+use crate::types::block::traits::header::Header;
+use crate::types::traits::trusted::TrustThreshold;
+use crate::types::traits::validator::Validator;
+use crate::types::trusted::TrustedState;
+use crate::verification::{is_within_trust_period, verify_next_validators, verify_single};
+
+/// Future returned by a [`HeaderFetcher`]. It is boxed so that fetchers can be written
+/// with any async runtime, and used as trait objects.
+pub type FetchFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, Error>> + Send + 'a>>;
+
+/// Source of the light blocks `LightClient::sync_to` verifies, e.g. an RPC client of a
+/// full node. The blocks it returns are untrusted: they are all verified.
+pub trait HeaderFetcher<C, H, V>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    /// The light block at the given height.
+    fn fetch_light_block(&self, height: u64) -> FetchFuture<'_, LightBlock<C, H, V>>;
+}
+
+/// LightClient keeps the trusted state of a chain, moving it forward with every header
+/// verified with `verify`.
+///
+/// Once the trusted state is older than the trusting period, verification fails with
+/// `Kind::Expired` and the client can't recover on its own: it has to be re-initialized
+/// subjectively, from a header and validators obtained from a new trusted source (e.g.
+/// checked by an operator against a block explorer). The recommended flow on expiry:
+///
+/// 1. stop following the chain, the headers verified so far stay trusted,
+/// 2. obtain a recent signed header and its next validators from a trusted source,
+/// 3. install them with `reset`, which rejects states that are already expired,
+/// 4. resume verifying headers from there.
+#[derive(Clone, Debug)]
+pub struct LightClient<C, H, V, L>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+    L: TrustThreshold,
+{
+    trusted_state: TrustedState<C, H, V>,
+    root_height: u64,
+    trust_threshold: L,
+    trusting_period: Duration,
+}
+
+impl<C, H, V, L> LightClient<C, H, V, L>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+    L: TrustThreshold,
+{
+    /// A client trusting the given state, as obtained from a trusted source.
+    pub fn new(
+        trusted_state: TrustedState<C, H, V>,
+        trust_threshold: L,
+        trusting_period: Duration,
+    ) -> Self {
+        Self {
+            root_height: trusted_state.last_header().header().height(),
+            trusted_state,
+            trust_threshold,
+            trusting_period,
+        }
+    }
+
+    pub fn trusted_state(&self) -> &TrustedState<C, H, V> {
+        &self.trusted_state
+    }
+
+    /// Height of the state the client was initialized with, or last reset with. The
+    /// headers verified since are trusted from there on.
+    pub fn root_height(&self) -> u64 {
+        self.root_height
+    }
+
+    /// Verify the untrusted header with `verify_single` and, if it is verified, trust it
+    /// from now on. The trusted state is left as is when verification fails.
+    pub fn verify(
+        &mut self,
+        untrusted_sh: &SignedHeader<C, H>,
+        untrusted_vals: &C::ValidatorSet,
+        untrusted_next_vals: &C::ValidatorSet,
+        now: SystemTime,
+    ) -> Result<(), Error> {
+        self.trusted_state = verify_single(
+            self.trusted_state.clone(),
+            untrusted_sh,
+            untrusted_vals,
+            untrusted_next_vals,
+            self.trust_threshold,
+            self.trusting_period,
+            now,
+        )?;
+        Ok(())
+    }
+
+    /// Catch up to the target height, verifying and trusting the light blocks pulled from
+    /// the fetcher on the way. The client first tries to skip to the target at once and,
+    /// while too few of the validators it trusts signed the block it tries, bisects: it
+    /// verifies the block halfway instead, then tries the target again from there.
+    ///
+    /// On failure, the headers verified so far stay trusted and the error of the block
+    /// which failed is returned, e.g. `Kind::Expired` or any error of the fetcher.
+    pub async fn sync_to<F>(
+        &mut self,
+        target_height: u64,
+        fetcher: &F,
+        now: SystemTime,
+    ) -> Result<(), Error>
+    where
+        F: HeaderFetcher<C, H, V>,
+    {
+        let trusted_height = self.trusted_height();
+        if target_height < trusted_height {
+            return Err(Kind::NonIncreasingHeight {
+                got: target_height,
+                expected: trusted_height,
+            }
+            .into());
+        }
+
+        while self.trusted_height() < target_height {
+            let trusted_height = self.trusted_height();
+            let mut height = target_height;
+            loop {
+                let light_block = fetcher.fetch_light_block(height).await?;
+                let fetched_height = light_block.signed_header().header().height();
+                if fetched_height != height {
+                    fail!(
+                        Kind::ImplementationSpecific,
+                        "fetched the light block at height {} instead of {}",
+                        fetched_height,
+                        height
+                    );
+                }
+
+                match self.verify(
+                    light_block.signed_header(),
+                    light_block.validators(),
+                    light_block.next_validators(),
+                    now,
+                ) {
+                    Ok(()) => break,
+                    // adjacent headers never lack signatures of trusted validators
+                    Err(e) if matches!(e.kind(), Kind::InsufficientSignedVotingPower { .. }) => {
+                        height = trusted_height + (height - trusted_height) / 2;
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn trusted_height(&self) -> u64 {
+        self.trusted_state.last_header().header().height()
+    }
+
+    /// Re-initialize the client subjectively, e.g. after its trusted state expired (see
+    /// the flow in the type's documentation), replacing the trusted state whatever it is.
+    /// Fails, leaving the client as is, if the new state is expired as well or its
+    /// validators aren't the next validators announced by its header.
+    pub fn reset(
+        &mut self,
+        new_trusted_state: TrustedState<C, H, V>,
+        now: SystemTime,
+    ) -> Result<(), Error> {
+        is_within_trust_period(
+            new_trusted_state.last_header().header(),
+            self.trusting_period,
+            now,
+        )?;
+        verify_next_validators(&new_trusted_state, new_trusted_state.validators())?;
+        self.root_height = new_trusted_state.last_header().header().height();
+        self.trusted_state = new_trusted_state;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Kind;
+    use crate::types::block::commit::Commit;
+    use crate::types::block::commit::LightSignedHeader;
+    use crate::types::block::header::Header as LightHeader;
+    use crate::types::mocks::{
+        generate_keypairs, light_header, signed_commit, signed_header_at, validator_set,
+    };
+    use crate::types::time::Time;
+    use crate::types::traits::validator_set::ValidatorSet;
+    use crate::types::validator::{Info, Set};
+    use crate::TrustThresholdFraction;
+    use ed25519_dalek::Keypair;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    fn signed_header(keypairs: &[Keypair], vals: &Set<Info>, height: u64) -> LightSignedHeader {
+        let time = Time::unix_epoch() + Duration::new(height, 0);
+        let signers: Vec<usize> = (0..vals.number_of_validators()).collect();
+        signed_header_at(height, time, keypairs, vals, &signers)
+    }
+
+    // runs the future on the current thread, for futures which never have to wait
+    fn block_on<F: Future>(future: F) -> F::Output {
+        struct NoopWaker;
+        impl Wake for NoopWaker {
+            fn wake(self: Arc<Self>) {}
+        }
+
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    // serves the light blocks of a scripted chain, recording the heights fetched
+    struct ScriptedChain {
+        light_blocks: HashMap<u64, LightBlock<Commit, LightHeader, Info>>,
+        fetched: RefCell<Vec<u64>>,
+    }
+
+    impl HeaderFetcher<Commit, LightHeader, Info> for ScriptedChain {
+        fn fetch_light_block(
+            &self,
+            height: u64,
+        ) -> FetchFuture<'_, LightBlock<Commit, LightHeader, Info>> {
+            self.fetched.borrow_mut().push(height);
+            let light_block = self.light_blocks.get(&height).cloned();
+            Box::pin(async move {
+                light_block.ok_or_else(|| {
+                    Kind::ImplementationSpecific
+                        .context("unknown height")
+                        .into()
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn test_sync_to() {
+        // 3 epochs of 4 heights each, every set keeping half of the previous one
+        let keypairs = generate_keypairs(8);
+        let sets: Vec<Set<Info>> = (0..3)
+            .map(|epoch| validator_set(&keypairs[epoch * 2..epoch * 2 + 4], 1))
+            .collect();
+        let epoch_at = |height: u64| ((height - 1) / 4) as usize;
+        let light_blocks = (1..=12)
+            .map(|height| {
+                let (vals, next_vals) =
+                    (&sets[epoch_at(height)], &sets[epoch_at(height + 1).min(2)]);
+                let time = Time::unix_epoch() + Duration::new(height, 0);
+                let header = light_header(height, time, vals.hash(), next_vals.hash());
+                let offset = epoch_at(height) * 2;
+                let commit =
+                    signed_commit(&header, &keypairs[offset..offset + 4], vals, &[0, 1, 2, 3]);
+                let sh = LightSignedHeader::new(commit, header);
+                (height, LightBlock::new(sh, vals.clone(), next_vals.clone()))
+            })
+            .collect();
+        let chain = ScriptedChain {
+            light_blocks,
+            fetched: RefCell::new(vec![]),
+        };
+        let light_block = |height| chain.light_blocks[&height].clone();
+        let now = SystemTime::UNIX_EPOCH + Duration::new(20, 0);
+
+        let first = light_block(1);
+        let mut client = LightClient::new(
+            TrustedState::new(
+                first.signed_header().clone(),
+                first.next_validators().clone(),
+            ),
+            TrustThresholdFraction::new(1, 3).unwrap(),
+            Duration::new(100, 0),
+        );
+
+        // none of the validators trusted at 1 signed 12: bisect through 6
+        block_on(client.sync_to(12, &chain, now)).unwrap();
+        assert_eq!(*chain.fetched.borrow(), vec![12, 6, 12]);
+        assert_eq!(
+            client.trusted_state().last_header(),
+            light_block(12).signed_header()
+        );
+        assert_eq!(client.root_height(), 1);
+
+        // already there
+        chain.fetched.borrow_mut().clear();
+        block_on(client.sync_to(12, &chain, now)).unwrap();
+        assert!(chain.fetched.borrow().is_empty());
+
+        // the errors of the fetcher are passed through, keeping what was verified
+        let res = block_on(client.sync_to(13, &chain, now));
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Implementation specific error: unknown height"
+        );
+        assert_eq!(client.trusted_state().last_header().header().height(), 12);
+
+        let res = block_on(client.sync_to(11, &chain, now));
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::NonIncreasingHeight { .. }
+        ));
+    }
+
+    #[test]
+    fn test_reset_after_expiry() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let at = |secs: u64| SystemTime::UNIX_EPOCH + Duration::new(secs, 0);
+
+        let mut client = LightClient::new(
+            TrustedState::new(signed_header(&keypairs, &vals, 1), vals.clone()),
+            TrustThresholdFraction::default(),
+            Duration::new(100, 0),
+        );
+        client
+            .verify(&signed_header(&keypairs, &vals, 2), &vals, &vals, at(10))
+            .unwrap();
+        assert_eq!(client.trusted_state().last_header().header().height(), 2);
+        assert_eq!(client.root_height(), 1);
+
+        // the trusted header (at 2s) expired, nothing can be verified anymore
+        let res = client.verify(&signed_header(&keypairs, &vals, 3), &vals, &vals, at(200));
+        assert!(matches!(res.err().unwrap().kind(), Kind::Expired { .. }));
+        assert_eq!(client.trusted_state().last_header().header().height(), 2);
+
+        // a state from the new trusted source which expired as well is rejected
+        let stale_state = TrustedState::new(signed_header(&keypairs, &vals, 50), vals.clone());
+        let res = client.reset(stale_state, at(200));
+        assert!(matches!(res.err().unwrap().kind(), Kind::Expired { .. }));
+
+        // so are validators which aren't the ones the header announced
+        let other_vals = validator_set(&keypairs[..3], 1);
+        let mismatched_state = TrustedState::new(signed_header(&keypairs, &vals, 150), other_vals);
+        let res = client.reset(mismatched_state, at(200));
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InvalidNextValidatorSet { .. }
+        ));
+        assert_eq!(client.trusted_state().last_header().header().height(), 2);
+        assert_eq!(client.root_height(), 1);
+
+        // a fresh state is installed and verification resumes from it
+        let fresh_state = TrustedState::new(signed_header(&keypairs, &vals, 150), vals.clone());
+        client.reset(fresh_state, at(200)).unwrap();
+        assert_eq!(client.trusted_state().last_header().header().height(), 150);
+        client
+            .verify(&signed_header(&keypairs, &vals, 160), &vals, &vals, at(200))
+            .unwrap();
+        assert_eq!(client.trusted_state().last_header().header().height(), 160);
+        assert_eq!(client.root_height(), 150);
+    }
+}
@@ -15,6 +15,12 @@ pub fn simple_hash_from_byte_vectors(byte_vecs: Vec<Vec<u8>>) -> Hash {
     simple_hash_from_byte_slices_inner(byte_vecs.as_slice())
 }
 
+/// Compute a simple Merkle root from borrowed byte vectors, as
+/// `simple_hash_from_byte_vectors` does.
+pub fn simple_hash_from_byte_slices(byte_slices: &[Vec<u8>]) -> Hash {
+    simple_hash_from_byte_slices_inner(byte_slices)
+}
+
 // recurse into subtrees
 fn simple_hash_from_byte_slices_inner(byte_slices: &[Vec<u8>]) -> Hash {
     let length = byte_slices.len();
@@ -30,6 +36,104 @@ fn simple_hash_from_byte_slices_inner(byte_slices: &[Vec<u8>]) -> Hash {
     }
 }
 
+/// Inclusion proof of the leaf at `index` in the tree of the given byte vectors: the roots
+/// of the subtrees next to the path from the leaf to the root ("aunts"), from the bottom
+/// of the tree up, as in tendermint's `SimpleProof`.
+pub fn aunts_from_byte_vectors(byte_vecs: &[Vec<u8>], index: usize) -> Vec<Hash> {
+    if byte_vecs.len() <= 1 {
+        return vec![];
+    }
+    let k = get_split_point(byte_vecs.len());
+    let (mut aunts, aunt) = if index < k {
+        (
+            aunts_from_byte_vectors(&byte_vecs[..k], index),
+            simple_hash_from_byte_slices_inner(&byte_vecs[k..]),
+        )
+    } else {
+        (
+            aunts_from_byte_vectors(&byte_vecs[k..], index - k),
+            simple_hash_from_byte_slices_inner(&byte_vecs[..k]),
+        )
+    };
+    aunts.push(aunt);
+    aunts
+}
+
+/// Root of a tree of `total` leaves, computed from the hash of the leaf at `index` and its
+/// aunts (see [`aunts_from_byte_vectors`]). Returns `None` if the number of aunts doesn't
+/// match the shape of the tree.
+pub fn compute_hash_from_aunts(
+    index: usize,
+    total: usize,
+    leaf_hash: Hash,
+    aunts: &[Hash],
+) -> Option<Hash> {
+    if index >= total {
+        return None;
+    }
+    if total == 1 {
+        return if aunts.is_empty() {
+            Some(leaf_hash)
+        } else {
+            None
+        };
+    }
+    let (aunt, aunts) = aunts.split_last()?;
+    let k = get_split_point(total);
+    if index < k {
+        let left = compute_hash_from_aunts(index, k, leaf_hash, aunts)?;
+        Some(inner_hash(&left, aunt))
+    } else {
+        let right = compute_hash_from_aunts(index - k, total - k, leaf_hash, aunts)?;
+        Some(inner_hash(aunt, &right))
+    }
+}
+
+/// Computes the same root as [`simple_hash_from_byte_vectors`], taking the leaves one at
+/// a time and keeping only O(log n) subtree roots in memory.
+///
+/// The number of leaves doesn't need to be known up front: at every split the left
+/// subtree is complete (its size is a power of two), so subtrees are merged as soon as
+/// they are complete and the ones left on the right edge are combined by `finalize`.
+#[derive(Clone, Debug, Default)]
+pub struct IncrementalHasher {
+    // roots of complete subtrees along with their number of leaves, sizes decreasing
+    subtrees: Vec<(Hash, usize)>,
+}
+
+impl IncrementalHasher {
+    /// Append the next leaf.
+    pub fn push(&mut self, leaf: &[u8]) {
+        let mut subtree = (leaf_hash(leaf), 1);
+        while let Some((left, size)) = self.subtrees.last() {
+            if *size != subtree.1 {
+                break;
+            }
+            subtree = (inner_hash(left, &subtree.0), size + subtree.1);
+            self.subtrees.pop();
+        }
+        self.subtrees.push(subtree);
+    }
+
+    /// Number of leaves pushed so far.
+    pub fn leaves(&self) -> usize {
+        self.subtrees.iter().map(|(_, size)| size).sum()
+    }
+
+    /// Merkle root of all the leaves pushed.
+    pub fn finalize(self) -> Hash {
+        self.subtrees
+            .into_iter()
+            .rev()
+            .map(|(root, _)| root)
+            .fold(None, |right: Option<Hash>, left| match right {
+                None => Some(left),
+                Some(right) => Some(inner_hash(&left, &right)),
+            })
+            .unwrap_or([0; HASH_SIZE])
+    }
+}
+
 // returns the largest power of 2 less than length
 fn get_split_point(length: usize) -> usize {
     match length {
@@ -40,8 +144,8 @@ fn get_split_point(length: usize) -> usize {
     }
 }
 
-// tmhash(0x00 || leaf)
-fn leaf_hash(bytes: &[u8]) -> Hash {
+/// Hash of a single leaf of the tree: tmhash(0x00 || leaf)
+pub fn leaf_hash(bytes: &[u8]) -> Hash {
     // make a new array starting with 0 and copy in the bytes
     let mut leaf_bytes = Vec::with_capacity(bytes.len() + 1);
     leaf_bytes.push(0x00);
@@ -24,3 +24,29 @@ pub(crate) mod hexstring {
         serializer.serialize_str(&hex_string)
     }
 }
+
+/// Serialize a `Hash` as lower-case hex, as block explorers and most Cosmos tooling do,
+/// instead of the upper-case hex of its own `Serialize`. Deserializing accepts both cases.
+pub mod lower_hash {
+    use crate::types::hash::{Algorithm, Hash};
+    use serde::{Deserialize, Deserializer, Serializer};
+    use subtle_encoding::hex;
+
+    /// Deserialize a `Hash` from hex in either case
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Hash, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let string = String::deserialize(deserializer)?;
+        let bytes = hex::decode(string.to_ascii_lowercase()).map_err(serde::de::Error::custom)?;
+        Hash::new(Algorithm::Sha256, &bytes).map_err(serde::de::Error::custom)
+    }
+
+    /// Serialize a `Hash` as lower-case hex
+    pub fn serialize<S>(hash: &Hash, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&hash.to_hex_lower())
+    }
+}
@@ -1,5 +1,6 @@
 //! Custom, legacy serializers
 
+use crate::errors::Kind;
 use crate::types::block;
 use crate::types::block::parts;
 use crate::types::hash::Hash;
@@ -32,6 +33,7 @@ where
 {
     #[derive(Deserialize)]
     struct Parts {
+        #[serde(deserialize_with = "parse_u64_string_or_int")]
         total: u64,
         hash: String,
     }
@@ -62,3 +64,67 @@ where
         Ok(None)
     }
 }
+
+/// Parse a commit round. Tendermint encodes rounds as int32 on the wire, so anything outside
+/// of `0..=i32::MAX` comes from a malformed source and is rejected rather than cast.
+pub(crate) fn parse_round<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let round = i64::deserialize(deserializer)?;
+    if round < 0 || round > i64::from(i32::MAX) {
+        return Err(D::Error::custom(format!(
+            "{}: round {}",
+            Kind::OutOfRange,
+            round
+        )));
+    }
+    Ok(round as u64)
+}
+
+/// Parse a u64 encoded either as a JSON number or as a string, as tendermint versions
+/// differ on how they encode e.g. the `total` of a parts header.
+pub(crate) fn parse_u64_string_or_int<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrInt {
+        Int(u64),
+        String(String),
+    }
+
+    match StringOrInt::deserialize(deserializer)? {
+        StringOrInt::Int(value) => Ok(value),
+        StringOrInt::String(s) => {
+            u64::from_str(&s).map_err(|err| D::Error::custom(format!("{}", err)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_non_empty_block_id;
+
+    const HASH: &str = "E4E7D9A8C2A8A6B7E0F5D3C1B9A7F5E3D1C9B7A5F3E1D9C7B5A3F1E9D7C5B3A1";
+
+    fn parse(total: &str) -> Result<Option<crate::types::block::id::Id>, serde_json::Error> {
+        let json = format!(
+            r#"{{"hash":"{}","part_set_header":{{"total":{},"hash":"{}"}}}}"#,
+            HASH, total, HASH
+        );
+        parse_non_empty_block_id(&mut serde_json::Deserializer::from_str(&json))
+    }
+
+    #[test]
+    fn test_parse_block_id_parts_total() {
+        let from_int = parse("3").unwrap().unwrap();
+        let from_string = parse(r#""3""#).unwrap().unwrap();
+        assert_eq!(from_int, from_string);
+        assert_eq!(from_int.part_set_header.unwrap().total, 3);
+
+        assert!(parse(r#""three""#).is_err());
+        assert!(parse("-3").is_err());
+    }
+}
@@ -23,3 +23,36 @@ where
 {
     format!("{}", value).serialize(serializer)
 }
+
+/// Same as above, for optional values: `null` is `None`.
+pub(crate) mod option {
+    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Deserialize an optional string into T
+    pub(crate) fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: std::str::FromStr,
+        <T as std::str::FromStr>::Err: std::fmt::Display,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(s) => s
+                .parse::<T>()
+                .map(Some)
+                .map_err(|e| D::Error::custom(format!("{}", e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Serialize from an optional T into an optional string
+    pub(crate) fn serialize<S, T>(value: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        T: std::fmt::Display,
+    {
+        value
+            .as_ref()
+            .map(|value| format!("{}", value))
+            .serialize(serializer)
+    }
+}
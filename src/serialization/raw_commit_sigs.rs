@@ -10,7 +10,7 @@ use std::str::FromStr;
 // Implements decision: https://github.com/tendermint/tendermint/blob/master/docs/architecture/adr-025-commit.md#decision
 
 /// indicate which BlockID the signature is for
-#[derive(Serialize_repr, Deserialize_repr, PartialEq, Debug)]
+#[derive(Serialize_repr, Deserialize_repr, Clone, Copy, PartialEq, Eq, Debug)]
 #[repr(u8)]
 pub enum BlockIDFlag {
     /// vote is not included in the Commit.Precommits
@@ -41,11 +41,13 @@ pub struct RawCommitSig {
     pub signature: Option<Signature>,
 }
 
+// Absent votes carry either a null or an empty signature, both are parsed as None.
 fn option_signature<'de, D>(deserializer: D) -> Result<Option<Signature>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    Deserialize::deserialize(deserializer).map(|x: Option<_>| x.unwrap_or(None))
+    Deserialize::deserialize(deserializer)
+        .map(|x: Option<Signature>| x.filter(|signature| !signature.raw().is_empty()))
 }
 
 // Todo: https://github.com/informalsystems/tendermint-rs/issues/260 - CommitSig validator address missing in Absent vote
@@ -0,0 +1,163 @@
+//! Verification metrics, emitted through the `metrics` facade when the `metrics` feature is
+//! enabled. Install a recorder (e.g. `metrics-exporter-prometheus`) to collect them.
+//! Without the feature every function here compiles to nothing.
+
+use crate::errors::Error;
+
+/// Counter of calls to `verify_single` (and `verify_single_with_options`).
+#[cfg(feature = "metrics")]
+pub const VERIFICATIONS_ATTEMPTED: &str = "light_client_verifications_attempted_total";
+/// Counter of verifications which returned a new trusted state.
+#[cfg(feature = "metrics")]
+pub const VERIFICATIONS_SUCCEEDED: &str = "light_client_verifications_succeeded_total";
+/// Counter of verifications which failed, labelled with the error's `kind` category.
+#[cfg(feature = "metrics")]
+pub const VERIFICATIONS_FAILED: &str = "light_client_verifications_failed_total";
+/// Histogram of the time spent verifying a single vote signature, in seconds.
+#[cfg(feature = "metrics")]
+pub const SIGNATURE_VERIFY_SECONDS: &str = "light_client_signature_verify_seconds";
+
+pub(crate) fn verification_attempted() {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(VERIFICATIONS_ATTEMPTED).increment(1);
+}
+
+pub(crate) fn verification_finished<T>(result: &Result<T, Error>) {
+    #[cfg(feature = "metrics")]
+    match result {
+        Ok(_) => metrics::counter!(VERIFICATIONS_SUCCEEDED).increment(1),
+        Err(e) => {
+            metrics::counter!(VERIFICATIONS_FAILED, "kind" => e.kind().category()).increment(1)
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = result;
+}
+
+// NOTE std::time::Instant isn't available on wasm32-unknown-unknown,
+// so the metrics feature is meant for native relayers only.
+pub(crate) fn time_signature_verification<T>(verify: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "metrics")]
+    {
+        let start = std::time::Instant::now();
+        let verified = verify();
+        metrics::histogram!(SIGNATURE_VERIFY_SECONDS).record(start.elapsed().as_secs_f64());
+        verified
+    }
+    #[cfg(not(feature = "metrics"))]
+    verify()
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use super::*;
+    use crate::types::mocks::{
+        generate_keypairs, signed_header_at, trusted_state_at, validator_set,
+    };
+    use crate::{verify_single, Time, TrustThresholdFraction};
+    use metrics::{
+        Counter, CounterFn, Gauge, Histogram, HistogramFn, Key, KeyName, Metadata, Recorder,
+        SharedString, Unit,
+    };
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
+
+    // Counts increments and histogram samples, keyed by metric name and labels.
+    #[derive(Default)]
+    struct Recording(Mutex<HashMap<String, Arc<Cell>>>);
+
+    #[derive(Default)]
+    struct Cell(AtomicU64);
+
+    impl CounterFn for Cell {
+        fn increment(&self, value: u64) {
+            self.0.fetch_add(value, Ordering::SeqCst);
+        }
+
+        fn absolute(&self, value: u64) {
+            self.0.store(value, Ordering::SeqCst);
+        }
+    }
+
+    impl HistogramFn for Cell {
+        fn record(&self, _value: f64) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    impl Recording {
+        fn cell(&self, key: &Key) -> Arc<Cell> {
+            let mut name = key.name().to_string();
+            for label in key.labels() {
+                name.push_str(&format!("{{{}={}}}", label.key(), label.value()));
+            }
+            self.0.lock().unwrap().entry(name).or_default().clone()
+        }
+
+        fn get(&self, name: &str) -> u64 {
+            self.0
+                .lock()
+                .unwrap()
+                .get(name)
+                .map_or(0, |cell| cell.0.load(Ordering::SeqCst))
+        }
+    }
+
+    impl Recorder for Recording {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.cell(key))
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, key: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::from_arc(self.cell(key))
+        }
+    }
+
+    #[test]
+    fn test_verification_metrics() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let untrusted_sh = signed_header_at(2, time, &keypairs, &vals, &[0, 1, 2, 3]);
+
+        let verify = |now: SystemTime| {
+            verify_single(
+                trusted_state.clone(),
+                &untrusted_sh,
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                now,
+            )
+        };
+
+        let recording = Recording::default();
+        metrics::with_local_recorder(&recording, || {
+            assert!(verify(SystemTime::UNIX_EPOCH + Duration::new(20, 0)).is_ok());
+            // the trusted state has expired by then
+            assert!(verify(SystemTime::UNIX_EPOCH + Duration::new(200, 0)).is_err());
+        });
+
+        assert_eq!(recording.get(VERIFICATIONS_ATTEMPTED), 2);
+        assert_eq!(recording.get(VERIFICATIONS_SUCCEEDED), 1);
+        assert_eq!(
+            recording.get(&format!("{}{{kind=expired}}", VERIFICATIONS_FAILED)),
+            1
+        );
+        // only the successful verification got as far as checking the signatures
+        assert_eq!(recording.get(SIGNATURE_VERIFY_SECONDS), 4);
+    }
+}
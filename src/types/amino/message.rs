@@ -27,12 +27,16 @@ pub trait AminoMessage: prost_amino::Message {
     where
         Self: Sized,
     {
-        let len = self.encoded_len();
-        let mut res =
-            Vec::with_capacity(len + encoded_len_varint(len.try_into().expect("length overflow")));
+        let mut res = Vec::with_capacity(self.encoded_len_length_delimited());
         self.encode_length_delimited(&mut res).unwrap();
         res
     }
+
+    /// Length of the message encoded as length delimited, without encoding it.
+    fn encoded_len_length_delimited(&self) -> usize {
+        let len = self.encoded_len();
+        len + encoded_len_varint(len.try_into().expect("length overflow"))
+    }
 }
 impl<M: prost_amino::Message> AminoMessage for M {
     // blanket impl
@@ -8,8 +8,6 @@ use crate::types::{block, vote::vote};
 use crate::types::{chain, hash};
 use crate::utils::try_cast_u64_to_i64;
 use anomaly::BoxError;
-use chrono::offset::TimeZone;
-use chrono::Utc;
 use prost_amino::DecodeError;
 use prost_amino_derive::Message;
 use std::convert::TryFrom;
@@ -91,7 +89,16 @@ pub struct TimeMsg {
 
 impl ParseTimestamp for TimeMsg {
     fn parse_timestamp(&self) -> Result<Time, Error> {
-        Ok(Utc.timestamp(self.seconds, self.nanos as u32).into())
+        // negative nanos wrap around to values rejected as out of range
+        Time::from_unix(self.seconds, self.nanos as u32)
+    }
+}
+
+impl TryFrom<TimeMsg> for Time {
+    type Error = Error;
+
+    fn try_from(time: TimeMsg) -> Result<Time, Error> {
+        time.parse_timestamp()
     }
 }
 
@@ -217,6 +224,78 @@ impl block::height::ParseHeight for Vote {
     }
 }
 
+// Go's zero time.Time (0001-01-01T00:00:00Z), carried by absent votes
+const ZERO_TIME: TimeMsg = TimeMsg {
+    seconds: -62_135_596_800,
+    nanos: 0,
+};
+
+#[derive(Clone, PartialEq, Message)]
+pub struct CommitSig {
+    #[prost_amino(uint32, tag = "1")]
+    pub block_id_flag: u32,
+    #[prost_amino(bytes)]
+    pub validator_address: Vec<u8>,
+    #[prost_amino(message)]
+    pub timestamp: Option<TimeMsg>,
+    #[prost_amino(bytes)]
+    pub signature: Vec<u8>,
+}
+
+impl From<&block::commit_sigs::CommitSig> for CommitSig {
+    fn from(commit_sig: &block::commit_sigs::CommitSig) -> Self {
+        use block::commit_sigs::CommitSig::*;
+
+        let block_id_flag = commit_sig.block_id_flag() as u32;
+        match commit_sig {
+            BlockIDFlagAbsent => CommitSig {
+                block_id_flag,
+                validator_address: vec![],
+                timestamp: Some(ZERO_TIME),
+                signature: vec![],
+            },
+            BlockIDFlagCommit {
+                validator_address,
+                timestamp,
+                signature,
+            }
+            | BlockIDFlagNil {
+                validator_address,
+                timestamp,
+                signature,
+            } => CommitSig {
+                block_id_flag,
+                validator_address: validator_address.as_bytes().to_vec(),
+                timestamp: Some(TimeMsg::from(*timestamp)),
+                signature: signature.raw(),
+            },
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Commit {
+    #[prost_amino(int64, tag = "1")]
+    pub height: i64,
+    #[prost_amino(int64)]
+    pub round: i64,
+    #[prost_amino(message)]
+    pub block_id: Option<BlockId>,
+    #[prost_amino(message, repeated)]
+    pub signatures: Vec<CommitSig>,
+}
+
+impl From<&block::commit::Commit> for Commit {
+    fn from(commit: &block::commit::Commit) -> Self {
+        Commit {
+            height: commit.height.into(),
+            round: commit.round as i64,
+            block_id: Some(BlockId::from(&commit.block_id)),
+            signatures: commit.signatures.iter().map(CommitSig::from).collect(),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Message)]
 pub struct CanonicalVote {
     #[prost_amino(uint32, tag = "1")]
@@ -266,10 +345,7 @@ impl CanonicalVote {
             height: vote.height,
             round: vote.round,
             timestamp: match vote.timestamp {
-                None => Some(TimeMsg {
-                    seconds: -62_135_596_800,
-                    nanos: 0,
-                }),
+                None => Some(ZERO_TIME),
                 Some(t) => Some(t),
             },
         }
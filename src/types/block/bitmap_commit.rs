@@ -0,0 +1,386 @@
+use crate::errors::{Error, Kind};
+use crate::telemetry;
+use crate::types::block::commit::Commit;
+use crate::types::block::commit_sigs::CommitSig;
+use crate::types::block::height::Height;
+use crate::types::block::id::Id;
+use crate::types::block::traits::commit::ProvableCommit;
+use crate::types::hash::Hash;
+use crate::types::signature::Signature;
+use crate::types::time::Time;
+use crate::types::traits::validator::Validator;
+use crate::types::traits::validator_set::ValidatorSet;
+use crate::types::validator::Set;
+use crate::types::vote::vote;
+use crate::types::{account, chain};
+use anomaly::fail;
+use std::collections::HashSet;
+use std::convert::TryInto;
+
+/// Address, timestamp and signature of the vote of one of the signers of a
+/// [`BitmapCommit`].
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BitmapSignature {
+    /// Validator address, to count the vote in another validator set when skipping
+    pub validator_address: account::Id,
+    /// Timestamp of vote
+    pub timestamp: Time,
+    /// Signature of vote
+    pub signature: Signature,
+}
+
+/// BitmapCommit is a compact form of [`Commit`] for bandwidth constrained protocols:
+/// rather than one `CommitSig` per validator, it carries a bitmap of the signers over the
+/// validator set (sorted by address, as tendermint orders it) and the signature of each
+/// signer, in the same order. Only votes for the block are represented.
+///
+/// For commits carrying a single aggregate signature, see [`AggregateCommit`] instead.
+///
+/// [`AggregateCommit`]: crate::traits::AggregateCommit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BitmapCommit {
+    /// Block height
+    pub height: Height,
+
+    /// Round, in `0..=i32::MAX` as tendermint encodes it as int32
+    #[serde(deserialize_with = "crate::serialization::custom::parse_round")]
+    pub round: u64,
+
+    /// Block ID
+    pub block_id: Id,
+
+    /// The i-th entry is true iff the i-th validator of the set voted for the block
+    pub signers: Vec<bool>,
+
+    /// Signatures of the signers, in the order of `signers`
+    pub signatures: Vec<BitmapSignature>,
+}
+
+impl BitmapCommit {
+    /// Compact a commit signed by the given validator set, keeping the votes for the
+    /// block only. Fails if the commit doesn't carry one signature per validator.
+    pub fn from_commit<V>(commit: &Commit, vals: &Set<V>) -> Result<Self, Error>
+    where
+        V: Validator,
+    {
+        let pairs = commit.pair_with(vals)?;
+        let mut signers = Vec::with_capacity(pairs.len());
+        let mut signatures = vec![];
+        for (_, commit_sig) in pairs {
+            match commit_sig {
+                CommitSig::BlockIDFlagCommit {
+                    validator_address,
+                    timestamp,
+                    signature,
+                } => {
+                    signers.push(true);
+                    signatures.push(BitmapSignature {
+                        validator_address: *validator_address,
+                        timestamp: *timestamp,
+                        signature: signature.clone(),
+                    });
+                }
+                CommitSig::BlockIDFlagAbsent | CommitSig::BlockIDFlagNil { .. } => {
+                    signers.push(false)
+                }
+            }
+        }
+        Ok(BitmapCommit {
+            height: commit.height,
+            round: commit.round,
+            block_id: commit.block_id.clone(),
+            signers,
+            signatures,
+        })
+    }
+
+    // Ensure the bitmap covers the validator set, with one signature per signer.
+    fn ensure_shape(&self, number_of_validators: usize) -> Result<(), Error> {
+        if self.signers.len() != number_of_validators {
+            fail!(
+                Kind::ImplementationSpecific,
+                "signers bitmap length: {} doesn't match validators count: {}",
+                self.signers.len(),
+                number_of_validators
+            );
+        }
+        self.ensure_signatures_count()
+    }
+
+    // Ensure there is one signature per signer of the bitmap.
+    fn ensure_signatures_count(&self) -> Result<(), Error> {
+        let number_of_signers = self.signers.iter().filter(|signed| **signed).count();
+        if self.signatures.len() != number_of_signers {
+            fail!(
+                Kind::ImplementationSpecific,
+                "signatures count: {} doesn't match signers count: {}",
+                self.signatures.len(),
+                number_of_signers
+            );
+        }
+        Ok(())
+    }
+}
+
+impl<V> ProvableCommit<V> for BitmapCommit
+where
+    V: Validator,
+{
+    type ValidatorSet = Set<V>;
+
+    fn header_hash(&self) -> Hash {
+        self.block_id.hash
+    }
+
+    fn height(&self) -> Option<u64> {
+        Some(self.height.value())
+    }
+
+    fn certified_block_id(&self) -> Option<&Id> {
+        Some(&self.block_id)
+    }
+
+    fn round(&self) -> Option<u64> {
+        Some(self.round)
+    }
+
+    fn voting_power_in(
+        &self,
+        chain_id: chain::Id,
+        vals: &Self::ValidatorSet,
+    ) -> Result<u64, Error> {
+        self.ensure_signatures_count()?;
+
+        // The bitmap indexes the validators which signed the header, which may not be
+        // `vals` when skipping: only count the signers known in `vals`, by address.
+        let signer_indices = self
+            .signers
+            .iter()
+            .enumerate()
+            .filter(|(_, signed)| **signed)
+            .map(|(index, _)| index);
+        let mut seen_votes: HashSet<account::Id> = HashSet::new();
+        let mut signed_power = 0u64;
+        for (index, bitmap_sig) in signer_indices.zip(self.signatures.iter()) {
+            let val = match vals.validator(bitmap_sig.validator_address) {
+                Some(val) => val,
+                None => continue,
+            };
+            if !seen_votes.insert(bitmap_sig.validator_address) {
+                fail!(
+                    Kind::ImplementationSpecific,
+                    "Duplicate vote found by validator {:?}",
+                    bitmap_sig.validator_address,
+                );
+            }
+            let vote = vote::Vote {
+                vote_type: vote::Type::Precommit,
+                height: self.height,
+                round: self.round,
+                block_id: Some(self.block_id.clone()),
+                timestamp: bitmap_sig.timestamp,
+                validator_address: bitmap_sig.validator_address,
+                validator_index: index as u64,
+                signature: bitmap_sig.signature.clone(),
+            };
+            let sign_bytes = vote::SignedVote::new(
+                (&vote).try_into()?,
+                chain_id.as_str(),
+                vote.validator_address,
+                vote.signature,
+            )
+            .sign_bytes();
+            let verified = telemetry::time_signature_verification(|| {
+                val.verify_signature(&sign_bytes, bitmap_sig.signature.as_ref())
+            });
+            if !verified {
+                fail!(
+                    Kind::ImplementationSpecific,
+                    "Couldn't verify signature {:?} with validator {:?} on sign_bytes {:?}",
+                    bitmap_sig.signature,
+                    val,
+                    sign_bytes,
+                );
+            }
+            signed_power += val.power();
+        }
+
+        Ok(signed_power)
+    }
+
+    fn validate(&self, vals: &Self::ValidatorSet) -> Result<(), Error> {
+        if !self.signers.iter().any(|signed| *signed) {
+            fail!(Kind::ImplementationSpecific, "no signers for commit");
+        }
+        self.ensure_shape(vals.number_of_validators())?;
+
+        let signers = vals
+            .validators()
+            .iter()
+            .zip(self.signers.iter())
+            .filter(|(_, signed)| **signed)
+            .map(|(val, _)| val);
+        for (val, bitmap_sig) in signers.zip(self.signatures.iter()) {
+            if val.address() != bitmap_sig.validator_address {
+                fail!(
+                    Kind::ImplementationSpecific,
+                    "signature of validator {} doesn't match the signer {} of the bitmap",
+                    bitmap_sig.validator_address,
+                    val.address()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::block::commit::SignedHeader;
+    use crate::types::block::header::Header as LightHeader;
+    use crate::types::mocks::{generate_keypairs, light_header, signed_commit, validator_set};
+    use crate::types::trusted::TrustedState;
+    use crate::types::validator::Info;
+    use crate::{verify_single, TrustThresholdFraction};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_bitmap_commit() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(2, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 3]);
+
+        let bitmap_commit = BitmapCommit::from_commit(&commit, &vals).unwrap();
+        assert_eq!(bitmap_commit.signers, vec![true, true, false, true]);
+        assert_eq!(bitmap_commit.signatures.len(), 3);
+        assert!(bitmap_commit.validate(&vals).is_ok());
+        assert_eq!(
+            bitmap_commit
+                .voting_power_in(header.chain_id, &vals)
+                .unwrap(),
+            3
+        );
+
+        // a signature attributed to the wrong validator doesn't verify
+        let mut swapped = bitmap_commit.clone();
+        swapped.signatures[0].validator_address = bitmap_commit.signatures[1].validator_address;
+        swapped.signatures[1].validator_address = bitmap_commit.signatures[0].validator_address;
+        let res = swapped.voting_power_in(header.chain_id, &vals);
+        assert!(res
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("Couldn't verify signature"));
+
+        // nor does a bitmap pointing at other signers than the signatures
+        let mut shifted = bitmap_commit.clone();
+        shifted.signers = vec![true, true, true, false];
+        assert!(shifted
+            .validate(&vals)
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("doesn't match the signer"));
+
+        // the bitmap has to match the set and the signatures
+        let mut short = bitmap_commit.clone();
+        short.signers.pop();
+        assert!(short.validate(&vals).is_err());
+        let mut missing_signature = bitmap_commit.clone();
+        missing_signature.signatures.pop();
+        let res = missing_signature.voting_power_in(header.chain_id, &vals);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "Implementation specific error: signatures count: 2 doesn't match signers count: 3"
+        );
+    }
+
+    #[test]
+    fn test_verify_single_bitmap_commit() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let sign = |header: &LightHeader, signers: &[usize]| {
+            let commit = signed_commit(header, &keypairs, &vals, signers);
+            let bitmap_commit = BitmapCommit::from_commit(&commit, &vals).unwrap();
+            SignedHeader::new(bitmap_commit, header.clone())
+        };
+
+        let trusted_header = light_header(1, Time::unix_epoch(), vals.hash(), vals.hash());
+        let trusted_state = TrustedState::new(sign(&trusted_header, &[0, 1, 2, 3]), vals.clone());
+        let header = light_header(
+            2,
+            Time::unix_epoch() + Duration::new(10, 0),
+            vals.hash(),
+            vals.hash(),
+        );
+        let verify = |signers: &[usize]| {
+            verify_single(
+                trusted_state.clone(),
+                &sign(&header, signers),
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+            )
+        };
+
+        assert!(verify(&[0, 1, 3]).is_ok());
+        assert!(matches!(
+            verify(&[0, 1]).err().unwrap().kind(),
+            Kind::InvalidCommit { .. }
+        ));
+    }
+
+    #[test]
+    fn test_skip_bitmap_commit_across_validator_set_change() {
+        let keypairs = generate_keypairs(5);
+        let trusted_vals = validator_set(&keypairs[..4], 1);
+        let vals = validator_set(&keypairs, 1);
+
+        let trusted_header = light_header(
+            1,
+            Time::unix_epoch(),
+            trusted_vals.hash(),
+            trusted_vals.hash(),
+        );
+        let trusted_commit =
+            signed_commit(&trusted_header, &keypairs, &trusted_vals, &[0, 1, 2, 3]);
+        let trusted_state = TrustedState::new(
+            SignedHeader::new(
+                BitmapCommit::from_commit(&trusted_commit, &trusted_vals).unwrap(),
+                trusted_header,
+            ),
+            trusted_vals.clone(),
+        );
+
+        let header = light_header(
+            3,
+            Time::unix_epoch() + Duration::new(10, 0),
+            vals.hash(),
+            vals.hash(),
+        );
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2, 3, 4]);
+        let bitmap_commit = BitmapCommit::from_commit(&commit, &vals).unwrap();
+        assert_eq!(bitmap_commit.signers, vec![true; 5]);
+        assert_eq!(
+            ProvableCommit::<Info>::voting_power_in(&bitmap_commit, header.chain_id, &trusted_vals)
+                .unwrap(),
+            4
+        );
+
+        let new_state = verify_single(
+            trusted_state,
+            &SignedHeader::new(bitmap_commit, header),
+            &vals,
+            &vals,
+            TrustThresholdFraction::new(1, 3).unwrap(),
+            Duration::new(100, 0),
+            SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+        )
+        .unwrap();
+        assert_eq!(new_state.validators(), &vals);
+    }
+}
@@ -1,32 +1,62 @@
 use crate::errors::{Error, Kind};
+use crate::telemetry;
+use crate::types::amino;
+use crate::types::amino::message::AminoMessage;
 use crate::types::block::commit_sigs::CommitSig;
 use crate::types::block::header;
 use crate::types::block::height::Height;
 use crate::types::block::id::Id;
-use crate::types::block::traits::commit::ProvableCommit;
+use crate::types::block::traits::commit::{DuplicatePolicy, ProvableCommit};
+use crate::types::block::traits::header::Header as HeaderT;
+use crate::types::signature_scheme::SignatureSchemeRegistry;
 use crate::types::traits::validator::Validator;
 use crate::types::traits::validator_set::ValidatorSet as _;
-use crate::types::validator::Set;
+use crate::types::validator::{Set, ValidatorLookup};
 use crate::types::vote::vote;
 use crate::types::{account, chain, hash};
 use anomaly::fail;
+use std::borrow::Borrow;
+use std::cell::Cell;
 use std::collections::HashSet;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
 use std::ops::Deref;
 use std::slice;
 
+/// Returned by [`Commit::voting_power_in_report`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct VotingPowerReport {
+    /// Power of the validators of the set which correctly signed the commit
+    pub signed_power: u64,
+    /// Validators whose vote was rejected and why, in the order of the commit
+    pub failures: Vec<(account::Id, String)>,
+}
+
 /// Commit contains the justification (ie. a set of signatures) that a block was committed by a set
 /// of validators.
 /// TODO: Update links below!
 /// <https://github.com/tendermint/tendermint/blob/51dc810d041eaac78320adc6d53ad8b160b06601/types/block.go#L486-L502>
 /// <https://github.com/tendermint/tendermint/blob/master/docs/spec/blockchain/blockchain.md#lastcommit>
+///
+/// Signatures are always verified when counting voting power, unless the crate is built
+/// with the `dangerous-replay` feature: `voting_power_in_skipping_signatures` doesn't
+/// exist otherwise.
+///
+#[cfg_attr(not(feature = "dangerous-replay"), doc = "```compile_fail")]
+#[cfg_attr(feature = "dangerous-replay", doc = "```")]
+/// use tendermint_light_client::{ChainId, Commit, LightValidator, LightValidatorSet};
+///
+/// fn replay(commit: &Commit, chain_id: ChainId, vals: &LightValidatorSet<LightValidator>) {
+///     commit.voting_power_in_skipping_signatures(chain_id, vals).unwrap();
+/// }
+/// ```
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
 pub struct Commit {
     /// Block height
     pub height: Height,
 
-    /// Round
+    /// Round, in `0..=i32::MAX` as tendermint encodes it as int32
+    #[serde(deserialize_with = "crate::serialization::custom::parse_round")]
     pub round: u64,
 
     /// Block ID
@@ -93,16 +123,341 @@ impl Commit {
                 if possible_vote.is_err() {
                     Err(possible_vote.err().unwrap())
                 } else {
-                    Ok(vote::SignedVote::new(
+                    let signed_vote = vote::SignedVote::new(
                         possible_vote.unwrap(),
                         &chain_id.to_string(),
                         vote.validator_address,
                         vote.signature,
-                    ))
+                    );
+                    ensure_precommit(&signed_vote)?;
+                    Ok(signed_vote)
                 }
             })
             .collect()
     }
+
+    /// Every (non-absent) vote of this commit as a standalone, serializable record.
+    pub fn to_vote_records(&self, chain_id: chain::Id) -> Result<Vec<vote::VoteRecord>, Error> {
+        let chain_id = chain_id.to_string();
+        non_absent_votes(self)
+            .into_iter()
+            .map(|vote| {
+                let signed_vote = vote::SignedVote::new(
+                    (&vote).try_into()?,
+                    &chain_id,
+                    vote.validator_address,
+                    vote.signature.clone(),
+                );
+                Ok(vote::VoteRecord {
+                    height: vote.height,
+                    round: vote.round,
+                    validator: vote.validator_address,
+                    block_id: vote.block_id,
+                    signature: vote.signature,
+                    sign_bytes: signed_vote.sign_bytes(),
+                })
+            })
+            .collect()
+    }
+
+    /// Pair every validator of the given set with the commit signature at the same
+    /// index. Tendermint orders the signatures exactly like the (sorted) validator set,
+    /// so the pairs can be used to audit who signed what.
+    pub fn pair_with<'a, V>(&'a self, vals: &Set<V>) -> Result<Vec<(V, &'a CommitSig)>, Error>
+    where
+        V: Validator,
+    {
+        self.ensure_signatures_count(vals)?;
+        Ok(vals
+            .validators()
+            .iter()
+            .cloned()
+            .zip(self.signatures.iter())
+            .collect())
+    }
+
+    /// Id (hash and part set header) of the block this commit certifies, e.g. to refer
+    /// to the block in proofs. Generic code gets it from
+    /// `ProvableCommit::certified_block_id`, which is `None` for commits without one.
+    pub fn block_id(&self) -> &Id {
+        &self.block_id
+    }
+
+    /// The amino encoding tendermint uses for this commit, e.g. as the `LastCommit` of the
+    /// next block. Absent votes are encoded with Go's zero time, as tendermint does.
+    pub fn encode_canonical(&self) -> Vec<u8> {
+        amino::Commit::from(self).bytes_vec()
+    }
+
+    /// Same as `voting_power_in`, but looks the signers up in a precomputed
+    /// [`ValidatorLookup`]. Build the lookup once with `Set::lookup_index` when verifying
+    /// many commits against the same validator set.
+    pub fn voting_power_in_with<V>(
+        &self,
+        chain_id: chain::Id,
+        lookup: &ValidatorLookup<V>,
+    ) -> Result<u64, Error>
+    where
+        V: Validator,
+    {
+        self.voting_power_by::<V, _, _>(
+            chain_id,
+            |val_id| lookup.get(val_id),
+            None,
+            None,
+            false,
+            DuplicatePolicy::Reject,
+        )
+    }
+
+    /// Same as `voting_power_in`, **without verifying any signature**: sums the power of
+    /// the known validators with a non-absent vote, still rejecting duplicate votes.
+    ///
+    /// **DANGER:** anyone can forge such a commit. This is strictly for replaying data
+    /// from a fully trusted source (e.g. a local archive this client verified before),
+    /// where verifying every signature again is wasted time. Never use it on data
+    /// received from the network.
+    #[cfg(feature = "dangerous-replay")]
+    pub fn voting_power_in_skipping_signatures<V>(
+        &self,
+        chain_id: chain::Id,
+        vals: &Set<V>,
+    ) -> Result<u64, Error>
+    where
+        V: Validator,
+    {
+        self.voting_power_by::<V, _, _>(
+            chain_id,
+            |val_id| vals.validator(val_id),
+            None,
+            None,
+            true,
+            DuplicatePolicy::Reject,
+        )
+    }
+
+    /// Addresses of the validators of the given set whose votes in this commit carry a
+    /// valid signature. Votes from unknown validators and invalid signatures are skipped.
+    pub fn verified_signers<V>(
+        &self,
+        chain_id: chain::Id,
+        vals: &Set<V>,
+    ) -> Result<HashSet<account::Id>, Error>
+    where
+        V: Validator,
+    {
+        let mut signers = HashSet::new();
+        for vote in self.signed_votes(chain_id) {
+            let vote = vote?;
+            if let Some(val) = vals.validator(vote.validator_id()) {
+                if val.verify_signature(&vote.sign_bytes(), vote.signature()) {
+                    signers.insert(vote.validator_id());
+                }
+            }
+        }
+        Ok(signers)
+    }
+
+    /// Same as `voting_power_in`, for debugging a partially bad commit: rather than failing
+    /// on the first bad vote, every vote from a validator of the set is checked, and the
+    /// power of the valid ones is reported along with the rejected ones. Votes from other
+    /// validators are ignored, like in `voting_power_in`. Not meant for verification.
+    pub fn voting_power_in_report<V>(&self, chain_id: chain::Id, vals: &Set<V>) -> VotingPowerReport
+    where
+        V: Validator,
+    {
+        let mut report = VotingPowerReport {
+            signed_power: 0,
+            failures: vec![],
+        };
+        let chain_id = chain_id.to_string();
+        let mut seen_votes: HashSet<account::Id> = HashSet::new();
+        for vote in non_absent_votes(self) {
+            let val_id = vote.validator_address;
+            let val = match vals.validator(val_id) {
+                Some(val) => val,
+                None => continue,
+            };
+            if !seen_votes.insert(val_id) {
+                report.failures.push((val_id, "duplicate vote".to_string()));
+                continue;
+            }
+            let signed_vote = match (&vote).try_into() {
+                Ok(canonical_vote) => {
+                    vote::SignedVote::new(canonical_vote, &chain_id, val_id, vote.signature.clone())
+                }
+                Err(e) => {
+                    report.failures.push((val_id, e.to_string()));
+                    continue;
+                }
+            };
+            if let Err(e) = ensure_precommit(&signed_vote) {
+                report.failures.push((val_id, e.to_string()));
+                continue;
+            }
+            if !val.verify_signature(&signed_vote.sign_bytes(), signed_vote.signature()) {
+                report
+                    .failures
+                    .push((val_id, "invalid signature".to_string()));
+                continue;
+            }
+            report.signed_power += val.power();
+        }
+        report
+    }
+
+    /// Number of distinct validators with a non-absent vote (for the block or nil) in this
+    /// commit, without verifying anything. Meant for quick diagnostics.
+    pub fn signer_count(&self) -> usize {
+        self.signatures
+            .iter()
+            .filter_map(|commit_sig| match commit_sig {
+                CommitSig::BlockIDFlagAbsent => None,
+                CommitSig::BlockIDFlagCommit {
+                    validator_address, ..
+                }
+                | CommitSig::BlockIDFlagNil {
+                    validator_address, ..
+                } => Some(*validator_address),
+            })
+            .collect::<HashSet<account::Id>>()
+            .len()
+    }
+
+    /// Number of signatures `voting_power_in` will verify against the given set, i.e. the
+    /// non-absent votes from its validators, without verifying any of them. Meant for
+    /// estimating the cost of a verification (e.g. gas) up front.
+    pub fn signature_count_to_verify<V>(&self, vals: &Set<V>) -> usize
+    where
+        V: Validator,
+    {
+        self.signatures
+            .iter()
+            .filter(|commit_sig| match commit_sig {
+                CommitSig::BlockIDFlagAbsent => false,
+                CommitSig::BlockIDFlagCommit {
+                    validator_address, ..
+                }
+                | CommitSig::BlockIDFlagNil {
+                    validator_address, ..
+                } => vals.contains(*validator_address),
+            })
+            .count()
+    }
+
+    // Sum the voting power of the validators that correctly signed this commit,
+    // looking up each signer with the given function.
+    // Every signature verification is counted in signatures_verified, if given.
+    // Signatures are verified with the verifiers of schemes, if given.
+    // With skip_signatures, the signatures aren't verified at all (dangerous-replay only).
+    // Later votes of a validator which already voted are handled according to duplicates.
+    fn voting_power_by<V, B, F>(
+        &self,
+        chain_id: chain::Id,
+        find_validator: F,
+        signatures_verified: Option<&Cell<u64>>,
+        schemes: Option<&SignatureSchemeRegistry>,
+        skip_signatures: bool,
+        duplicates: DuplicatePolicy,
+    ) -> Result<u64, Error>
+    where
+        V: Validator,
+        B: Borrow<V>,
+        F: Fn(account::Id) -> Option<B>,
+    {
+        let mut seen_votes: HashSet<account::Id> = HashSet::new();
+        // NOTE we don't know the validators that committed this block,
+        // so we have to check for each vote if its validator is already known.
+        let mut signed_power = 0u64;
+        for possible_signed_vote in self.signed_votes(chain_id) {
+            if possible_signed_vote.is_err() {
+                return Err(possible_signed_vote.err().unwrap());
+            }
+            let vote = possible_signed_vote.unwrap();
+
+            // Only count if this vote is from a known validator.
+            let val_id = vote.validator_id();
+
+            let val = match find_validator(val_id) {
+                Some(v) => v,
+                None => continue,
+            };
+            let val = val.borrow();
+
+            // Fail if we have seen vote from this validator before
+            if seen_votes.contains(&val_id) {
+                if duplicates == DuplicatePolicy::IgnoreExtra {
+                    continue;
+                }
+                fail!(
+                    Kind::ImplementationSpecific,
+                    "Duplicate vote found by validator {:?}",
+                    val_id,
+                );
+            } else {
+                seen_votes.insert(val_id);
+            }
+
+            if skip_signatures {
+                signed_power += val.power();
+                continue;
+            }
+
+            // check vote is valid from validator
+            let sign_bytes = vote.sign_bytes();
+
+            if let Some(count) = signatures_verified {
+                count.set(count.get() + 1);
+            }
+            let verified = telemetry::time_signature_verification(|| match schemes {
+                Some(schemes) => val.verify_signature_with(schemes, &sign_bytes, vote.signature()),
+                None => Ok(val.verify_signature(&sign_bytes, vote.signature())),
+            })?;
+            if !verified {
+                fail!(
+                    Kind::ImplementationSpecific,
+                    "Couldn't verify signature {:?} with validator {:?} on sign_bytes {:?}",
+                    vote.signature(),
+                    val,
+                    sign_bytes,
+                );
+            }
+            signed_power += val.power();
+        }
+
+        Ok(signed_power)
+    }
+
+    // ensure there is exactly one signature for every validator in the set.
+    fn ensure_signatures_count<V>(&self, vals: &Set<V>) -> Result<(), Error>
+    where
+        V: Validator,
+    {
+        if self.signatures.len() != vals.number_of_validators() {
+            fail!(
+                Kind::ImplementationSpecific,
+                "commit signatures count: {} doesn't match validators count: {}",
+                self.signatures.len(),
+                vals.number_of_validators()
+            );
+        }
+        Ok(())
+    }
+}
+
+// Only precommits can make up a commit, a prevote among them is a protocol violation.
+// NOTE: commit signatures don't carry a vote type, so this guards against votes
+// extracted from a commit with another type than the one it implies.
+fn ensure_precommit(vote: &vote::SignedVote) -> Result<(), Error> {
+    match vote.vote_type() {
+        Some(vote::Type::Precommit) => Ok(()),
+        vote_type => fail!(
+            Kind::ImplementationSpecific,
+            "vote by validator {} is not a precommit ({:?}), only precommits can be part of a commit",
+            vote.validator_id(),
+            vote_type
+        ),
+    }
 }
 
 // this private helper function does *not* do any validation but extracts
@@ -161,68 +516,92 @@ where
     fn header_hash(&self) -> hash::Hash {
         self.block_id.hash
     }
+
+    fn height(&self) -> Option<u64> {
+        Some(self.height.value())
+    }
+
+    fn certified_block_id(&self) -> Option<&Id> {
+        Some(&self.block_id)
+    }
+
+    fn round(&self) -> Option<u64> {
+        Some(self.round)
+    }
+
     fn voting_power_in(
         &self,
         chain_id: chain::Id,
         validators: &Self::ValidatorSet,
     ) -> Result<u64, Error> {
-        let mut seen_votes: HashSet<account::Id> = HashSet::new();
-        // NOTE we don't know the validators that committed this block,
-        // so we have to check for each vote if its validator is already known.
-        let mut signed_power = 0u64;
-        for possible_signed_vote in self.signed_votes(chain_id) {
-            if possible_signed_vote.is_err() {
-                return Err(possible_signed_vote.err().unwrap());
-            }
-            let vote = possible_signed_vote.unwrap();
-
-            // Only count if this vote is from a known validator.
-            let val_id = vote.validator_id();
-
-            let val = match validators.validator(val_id) {
-                Some(v) => v,
-                None => continue,
-            };
-
-            // Fail if we have seen vote from this validator before
-            if seen_votes.contains(&val_id) {
-                fail!(
-                    Kind::ImplementationSpecific,
-                    "Duplicate vote found by validator {:?}",
-                    val_id,
-                );
-            } else {
-                seen_votes.insert(val_id);
-            }
+        self.voting_power_in_with_policy(chain_id, validators, DuplicatePolicy::Reject)
+    }
 
-            // check vote is valid from validator
-            let sign_bytes = vote.sign_bytes();
+    fn voting_power_in_counting(
+        &self,
+        chain_id: chain::Id,
+        validators: &Self::ValidatorSet,
+        signatures_verified: &Cell<u64>,
+    ) -> Result<u64, Error> {
+        self.voting_power_by::<V, _, _>(
+            chain_id,
+            |val_id| validators.validator(val_id),
+            Some(signatures_verified),
+            None,
+            false,
+            DuplicatePolicy::Reject,
+        )
+    }
 
-            if !val.verify_signature(&sign_bytes, vote.signature()) {
-                fail!(
-                    Kind::ImplementationSpecific,
-                    "Couldn't verify signature {:?} with validator {:?} on sign_bytes {:?}",
-                    vote.signature(),
-                    val,
-                    sign_bytes,
-                );
-            }
-            signed_power += val.power();
-        }
+    fn voting_power_in_with_schemes(
+        &self,
+        chain_id: chain::Id,
+        validators: &Self::ValidatorSet,
+        schemes: &SignatureSchemeRegistry,
+        signatures_verified: Option<&Cell<u64>>,
+    ) -> Result<u64, Error> {
+        self.voting_power_by::<V, _, _>(
+            chain_id,
+            |val_id| validators.validator(val_id),
+            signatures_verified,
+            Some(schemes),
+            false,
+            DuplicatePolicy::Reject,
+        )
+    }
 
-        Ok(signed_power)
+    fn voting_power_in_with_policy(
+        &self,
+        chain_id: chain::Id,
+        validators: &Self::ValidatorSet,
+        duplicates: DuplicatePolicy,
+    ) -> Result<u64, Error> {
+        self.voting_power_by::<V, _, _>(
+            chain_id,
+            |val_id| validators.validator(val_id),
+            None,
+            None,
+            false,
+            duplicates,
+        )
     }
 
     fn validate(&self, vals: &Self::ValidatorSet) -> Result<(), Error> {
+        self.validate_with(vals, true)
+    }
+
+    fn validate_with(&self, vals: &Self::ValidatorSet, strict_count: bool) -> Result<(), Error> {
         // TODO: self.block_id cannot be zero in the same way as in go
         // clarify if this another encoding related issue
         if self.signatures.len() == 0 {
             fail!(Kind::ImplementationSpecific, "no signatures for commit");
         }
-        if self.signatures.len() != vals.number_of_validators() {
+        if strict_count {
+            self.ensure_signatures_count(vals)?;
+        } else if self.signatures.len() < vals.number_of_validators() {
             fail!(
                 Kind::ImplementationSpecific,
-                "commit signatures count: {} doesn't match validators count: {}",
+                "commit signatures count: {} is less than validators count: {}",
                 self.signatures.len(),
                 vals.number_of_validators()
             );
@@ -266,10 +645,34 @@ pub struct SignedHeader<C, H> {
 }
 
 impl<C, H> SignedHeader<C, H> {
+    /// Bundle the commit with its header, without any check.
+    /// See [`SignedHeader::try_new`] for a checked alternative.
     pub fn new(commit: C, header: H) -> Self {
         Self { commit, header }
     }
 
+    /// Same as `new`, but fails if the commit is for another height than the header.
+    /// Commits which don't carry their height can't be checked and are always accepted.
+    /// As `Commit` is a `ProvableCommit` for any kind of validator, `V` has to be named
+    /// for it, e.g. `LightSignedHeader::try_new::<LightValidator>(commit, header)`.
+    pub fn try_new<V>(commit: C, header: H) -> Result<Self, Error>
+    where
+        C: ProvableCommit<V>,
+        H: HeaderT,
+        V: Validator,
+    {
+        if let Some(commit_height) = commit.height() {
+            if commit_height != header.height() {
+                return Err(Kind::HeightMismatch {
+                    header_height: header.height(),
+                    commit_height,
+                }
+                .into());
+            }
+        }
+        Ok(Self::new(commit, header))
+    }
+
     pub fn commit(&self) -> &C {
         &self.commit
     }
@@ -280,3 +683,710 @@ impl<C, H> SignedHeader<C, H> {
 }
 
 pub type LightSignedHeader = SignedHeader<Commit, header::Header>;
+
+/// The distinct chain ids of the given signed headers, e.g. to group mixed-chain data.
+pub fn chain_ids_of<C, H>(signed_headers: &[SignedHeader<C, H>]) -> HashSet<chain::Id>
+where
+    H: HeaderT,
+{
+    signed_headers
+        .iter()
+        .map(|signed_header| signed_header.header().chain_id())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::Kind;
+    use crate::types::block::commit::{Commit, CommitSigs, VotingPowerReport};
+    use crate::types::block::commit_sigs::CommitSig;
+    use crate::types::block::traits::commit::ProvableCommit;
+    use crate::types::mocks::{generate_keypairs, light_header, signed_commit, validator_set};
+    use crate::types::traits::validator::Validator;
+    use crate::types::traits::validator_set::ValidatorSet;
+    use crate::types::validator::{Info, Set};
+    use crate::types::vote::vote::VoteRecord;
+    use crate::Time;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn test_pair_with() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 2, 3]);
+
+        let pairs = commit.pair_with(&vals).unwrap();
+        assert_eq!(pairs.len(), 4);
+        for (i, (val, commit_sig)) in pairs.iter().enumerate() {
+            assert_eq!(val.address(), vals.validators()[i].address());
+            match commit_sig {
+                CommitSig::BlockIDFlagCommit {
+                    validator_address, ..
+                } => assert_eq!(*validator_address, val.address()),
+                CommitSig::BlockIDFlagAbsent => assert_eq!(i, 1),
+                CommitSig::BlockIDFlagNil { .. } => panic!("unexpected nil vote"),
+            }
+        }
+
+        // one validator short, counts don't match
+        let smaller_vals = Set::new(vals.validators()[..3].to_vec());
+        let res = commit.pair_with(&smaller_vals);
+        assert!(res.is_err());
+        assert!(res
+            .err()
+            .unwrap()
+            .to_string()
+            .ends_with("commit signatures count: 4 doesn't match validators count: 3"));
+    }
+
+    #[test]
+    fn test_voting_power_in_with() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 3);
+        let lookup = vals.lookup_index();
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 3]);
+
+        let signed_power = commit.voting_power_in(header.chain_id, &vals).unwrap();
+        assert_eq!(signed_power, 9);
+        assert_eq!(
+            commit
+                .voting_power_in_with(header.chain_id, &lookup)
+                .unwrap(),
+            signed_power
+        );
+
+        // validators which are not in the lookup are ignored
+        let partial_lookup = Set::new(vals.validators()[..2].to_vec()).lookup_index();
+        assert_eq!(
+            commit
+                .voting_power_in_with(header.chain_id, &partial_lookup)
+                .unwrap(),
+            6
+        );
+    }
+
+    // Replaying 100 commits against the same validator set, the lookup built once gives
+    // the same result as scanning the set for every commit.
+    #[test]
+    fn test_replay_commits_with_lookup() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let signers: Vec<usize> = (0..4).collect();
+        let commits: Vec<_> = (1..=100)
+            .map(|height| {
+                let time = Time::unix_epoch() + Duration::new(height, 0);
+                let header = light_header(height, time, vals.hash(), vals.hash());
+                (
+                    header.chain_id,
+                    signed_commit(&header, &keypairs, &vals, &signers),
+                )
+            })
+            .collect();
+
+        let lookup = vals.lookup_index();
+        for (chain_id, commit) in &commits {
+            assert_eq!(commit.voting_power_in(*chain_id, &vals).unwrap(), 4);
+            assert_eq!(commit.voting_power_in_with(*chain_id, &lookup).unwrap(), 4);
+        }
+    }
+
+    #[test]
+    fn test_multi_byte_chain_id() {
+        let keypairs = generate_keypairs(3);
+        let vals = validator_set(&keypairs, 1);
+        let mut header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let name = "テスト-ĉeno-1";
+        header.chain_id = name.parse().unwrap();
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2]);
+
+        // the chain id is the last field (6) of the canonical vote,
+        // amino encodes it as a length-prefixed UTF-8 string
+        let mut reference = vec![(6 << 3) | 2, name.len() as u8];
+        reference.extend_from_slice(name.as_bytes());
+        for vote in commit.signed_votes(header.chain_id) {
+            assert!(vote.unwrap().sign_bytes().ends_with(&reference));
+        }
+
+        assert_eq!(commit.voting_power_in(header.chain_id, &vals).unwrap(), 3);
+        // signatures are bound to the exact chain id
+        let ascii_id = "ten-1".parse().unwrap();
+        assert!(commit.voting_power_in(ascii_id, &vals).is_err());
+    }
+
+    #[test]
+    fn test_to_vote_records() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 2, 3]);
+
+        let records = commit.to_vote_records(header.chain_id).unwrap();
+        assert_eq!(records.len(), 3);
+        for (record, vote) in records.iter().zip(commit.signed_votes(header.chain_id)) {
+            let vote = vote.unwrap();
+            assert_eq!(record.height, commit.height);
+            assert_eq!(record.round, commit.round);
+            assert_eq!(record.validator, vote.validator_id());
+            assert_eq!(record.block_id, Some(commit.block_id.clone()));
+            assert_eq!(record.signature.raw(), vote.signature());
+            assert_eq!(record.sign_bytes, vote.sign_bytes());
+        }
+
+        let json = serde_json::to_value(&records).unwrap();
+        assert_eq!(json[0]["height"], "10");
+        assert_eq!(json[0]["round"], "0");
+        assert_eq!(json[0]["validator"], records[0].validator.to_string());
+        assert_eq!(
+            serde_json::from_value::<Vec<VoteRecord>>(json).unwrap(),
+            records
+        );
+    }
+
+    #[test]
+    fn test_round_range() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 2, 3]);
+
+        let mut json = serde_json::to_value(&commit).unwrap();
+        json["round"] = i32::MAX.into();
+        let deserialized: Commit = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(deserialized.round, i32::MAX as u64);
+
+        for round in &[-1, i64::from(i32::MAX) + 1] {
+            json["round"] = (*round).into();
+            let err = serde_json::from_value::<Commit>(json.clone())
+                .err()
+                .unwrap();
+            assert_eq!(
+                err.to_string(),
+                format!("value out of range: round {}", round)
+            );
+        }
+    }
+
+    #[test]
+    fn test_signature_count_to_verify() {
+        let keypairs = generate_keypairs(5);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 3, 4]);
+
+        let verified = |vals: &Set<_>| {
+            let count = Cell::new(0);
+            commit
+                .voting_power_in_counting(header.chain_id, vals, &count)
+                .unwrap();
+            count.get() as usize
+        };
+
+        assert_eq!(commit.signature_count_to_verify(&vals), 4);
+        assert_eq!(commit.signature_count_to_verify(&vals), verified(&vals));
+
+        // votes of validators which aren't part of the set aren't verified
+        let smaller_vals = Set::new(vals.validators()[..3].to_vec());
+        assert_eq!(commit.signature_count_to_verify(&smaller_vals), 2);
+        assert_eq!(
+            commit.signature_count_to_verify(&smaller_vals),
+            verified(&smaller_vals)
+        );
+    }
+
+    #[test]
+    fn test_deserialize_absent_signatures() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 3]);
+
+        // absent votes as tendermint encodes them, with a null or an empty signature
+        let mut json = serde_json::to_value(&commit).unwrap();
+        json["signatures"][1] = serde_json::json!({
+            "block_id_flag": 1,
+            "validator_address": "",
+            "timestamp": "0001-01-01T00:00:00Z",
+            "signature": null
+        });
+        json["signatures"][2] = serde_json::json!({
+            "block_id_flag": 1,
+            "validator_address": "",
+            "timestamp": "0001-01-01T00:00:00Z",
+            "signature": ""
+        });
+
+        let deserialized: Commit = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized, commit);
+        assert_eq!(deserialized.signatures[1], CommitSig::BlockIDFlagAbsent);
+        assert_eq!(deserialized.signatures[2], CommitSig::BlockIDFlagAbsent);
+        assert_eq!(
+            deserialized
+                .voting_power_in(header.chain_id, &vals)
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_encode_canonical() {
+        use crate::types::account;
+        use crate::types::block::commit::CommitSigs;
+        use crate::types::block::height::Height;
+        use crate::types::block::id::Id;
+        use crate::types::hash::{Algorithm, Hash};
+        use crate::types::signature::Signature;
+        use subtle_encoding::hex;
+
+        let timestamp = Time::from_unix(1_600_000_000, 500).unwrap();
+        let commit = Commit {
+            height: Height(10),
+            round: 1,
+            block_id: Id::new(Hash::new(Algorithm::Sha256, &[0xAA; 32]).unwrap(), None),
+            signatures: CommitSigs::new(vec![
+                CommitSig::BlockIDFlagAbsent,
+                CommitSig::BlockIDFlagCommit {
+                    validator_address: account::Id::new([0x11; 20]),
+                    timestamp,
+                    signature: Signature::new(vec![0x22; 64]),
+                },
+                CommitSig::BlockIDFlagNil {
+                    validator_address: account::Id::new([0x33; 20]),
+                    timestamp,
+                    signature: Signature::new(vec![0x44; 64]),
+                },
+            ]),
+        };
+
+        // the field tags of tendermint's Commit and CommitSig, with Go's zero time as
+        // captured in tendermint's vote test vectors (see test_amino_tendermint_vectors)
+        let expected = concat!(
+            // height, round
+            "080A",
+            "1001",
+            // block_id
+            "1A22",
+            "0A20AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA",
+            // absent: flag, zero time
+            "220F",
+            "0801",
+            "1A0B088092B8C398FEFFFFFF01",
+            // commit: flag, address, time, signature
+            "2265",
+            "0802",
+            "12141111111111111111111111111111111111111111",
+            "1A090880A0F8FA0510F403",
+            "2240",
+            "2222222222222222222222222222222222222222222222222222222222222222",
+            "2222222222222222222222222222222222222222222222222222222222222222",
+            // nil: flag, address, time, signature
+            "2265",
+            "0803",
+            "12143333333333333333333333333333333333333333",
+            "1A090880A0F8FA0510F403",
+            "2240",
+            "4444444444444444444444444444444444444444444444444444444444444444",
+            "4444444444444444444444444444444444444444444444444444444444444444",
+        );
+        assert_eq!(
+            commit.encode_canonical(),
+            hex::decode_upper(expected).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_amino_tendermint_vectors() {
+        use crate::types::amino::message::AminoMessage;
+        use crate::types::amino::{CanonicalVote, TimeMsg};
+
+        // TestVoteSignBytesTestVectors of tendermint v0.33 (types/vote_test.go), for the
+        // pieces a commit shares with votes: Go's zero time and the fixed size fields
+        let zero_time = Some(TimeMsg {
+            seconds: -62_135_596_800,
+            nanos: 0,
+        });
+        let precommit = CanonicalVote {
+            vote_type: 2,
+            height: 1,
+            round: 1,
+            block_id: None,
+            timestamp: zero_time.clone(),
+            chain_id: String::new(),
+        };
+        assert_eq!(
+            precommit.bytes_vec_length_delimited(),
+            vec![
+                0x21, 0x8, 0x2, 0x11, 0x1, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x19, 0x1, 0x0, 0x0,
+                0x0, 0x0, 0x0, 0x0, 0x0, 0x2a, 0xb, 0x8, 0x80, 0x92, 0xb8, 0xc3, 0x98, 0xfe, 0xff,
+                0xff, 0xff, 0x1,
+            ]
+        );
+        let with_chain_id = CanonicalVote {
+            vote_type: 0,
+            chain_id: "test_chain_id".to_string(),
+            ..precommit
+        };
+        assert_eq!(
+            with_chain_id.bytes_vec_length_delimited(),
+            vec![
+                0x2e, 0x11, 0x1, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x19, 0x1, 0x0, 0x0, 0x0, 0x0,
+                0x0, 0x0, 0x0, 0x2a, 0xb, 0x8, 0x80, 0x92, 0xb8, 0xc3, 0x98, 0xfe, 0xff, 0xff,
+                0xff, 0x1, 0x32, 0xd, 0x74, 0x65, 0x73, 0x74, 0x5f, 0x63, 0x68, 0x61, 0x69, 0x6e,
+                0x5f, 0x69, 0x64,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_votes_share_height_and_round() {
+        use crate::types::block::commit::non_absent_votes;
+        use crate::types::mocks::signed_commit_at_round;
+        use crate::types::vote::vote::Type;
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit_at_round(&header, &keypairs, &vals, &[0, 2, 3], 2);
+
+        let votes = non_absent_votes(&commit);
+        assert_eq!(votes.len(), 3);
+        for vote in &votes {
+            assert_eq!(vote.vote_type, Type::Precommit);
+            assert_eq!(vote.height, commit.height);
+            assert_eq!(vote.round, commit.round);
+        }
+        // validator indices follow the position of the signatures in the commit
+        let indices: Vec<u64> = votes.iter().map(|vote| vote.validator_index).collect();
+        assert_eq!(indices, vec![0, 2, 3]);
+
+        // and the votes signed over the same height and round verify
+        for vote in commit.signed_votes(header.chain_id) {
+            let vote = vote.unwrap();
+            let val = vals.validator(vote.validator_id()).unwrap();
+            assert!(val.verify_signature(&vote.sign_bytes(), vote.signature()));
+        }
+    }
+
+    #[test]
+    fn test_chain_ids_of() {
+        use crate::types::block::commit::{chain_ids_of, LightSignedHeader};
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let signed_headers: Vec<LightSignedHeader> = (1..=4)
+            .map(|height| {
+                let mut header = light_header(height, Time::unix_epoch(), vals.hash(), vals.hash());
+                if height % 2 == 0 {
+                    header.chain_id = "other-chain".parse().unwrap();
+                }
+                let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2]);
+                LightSignedHeader::new(commit, header)
+            })
+            .collect();
+
+        let chain_ids = chain_ids_of(&signed_headers);
+        assert_eq!(chain_ids.len(), 2);
+        assert!(chain_ids.contains(&"test-chain".parse().unwrap()));
+        assert!(chain_ids.contains(&"other-chain".parse().unwrap()));
+
+        assert!(chain_ids_of::<Commit, crate::LightHeader>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_signed_header_try_new() {
+        use crate::types::block::commit::LightSignedHeader;
+        use crate::types::block::height::Height;
+        use crate::types::validator::Info;
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2]);
+
+        let sh = LightSignedHeader::try_new::<Info>(commit.clone(), header.clone()).unwrap();
+        assert_eq!(sh, LightSignedHeader::new(commit.clone(), header.clone()));
+
+        let mut other_commit = commit;
+        other_commit.height = Height(11);
+        let res = LightSignedHeader::try_new::<Info>(other_commit.clone(), header.clone());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "commit is for height 11, header is at height 10"
+        );
+        // the unchecked path is still available
+        let sh = LightSignedHeader::new(other_commit, header);
+        assert_eq!(sh.commit().height.value(), 11);
+    }
+
+    #[test]
+    fn test_signer_count() {
+        use crate::types::block::commit::CommitSigs;
+
+        let keypairs = generate_keypairs(5);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2]);
+        assert_eq!(commit.signer_count(), 3);
+
+        // validator 2 votes for nil instead, 3 and 4 are absent
+        let mut signatures = commit.signatures.clone().into_vec();
+        signatures[2] = match &signatures[2] {
+            CommitSig::BlockIDFlagCommit {
+                validator_address,
+                timestamp,
+                signature,
+            } => CommitSig::BlockIDFlagNil {
+                validator_address: *validator_address,
+                timestamp: *timestamp,
+                signature: signature.clone(),
+            },
+            _ => panic!("expected a commit vote"),
+        };
+        let mut with_nil = commit.clone();
+        with_nil.signatures = CommitSigs::new(signatures.clone());
+        assert_eq!(with_nil.signer_count(), 3);
+
+        // a validator voting twice is counted once
+        signatures[3] = signatures[0].clone();
+        with_nil.signatures = CommitSigs::new(signatures);
+        assert_eq!(with_nil.signer_count(), 3);
+
+        let mut unsigned = commit;
+        unsigned.signatures = CommitSigs::new(vec![CommitSig::BlockIDFlagAbsent; 5]);
+        assert_eq!(unsigned.signer_count(), 0);
+    }
+
+    #[test]
+    fn test_ensure_precommit() {
+        use crate::types::block::commit::{ensure_precommit, non_absent_votes};
+        use crate::types::vote::vote::{SignedVote, Type};
+        use std::convert::TryInto;
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2]);
+        for vote in commit.signed_votes(header.chain_id) {
+            assert!(ensure_precommit(&vote.unwrap()).is_ok());
+        }
+
+        let mut prevote = non_absent_votes(&commit).remove(0);
+        prevote.vote_type = Type::Prevote;
+        let signed_prevote = SignedVote::new(
+            (&prevote).try_into().unwrap(),
+            header.chain_id.as_str(),
+            prevote.validator_address,
+            prevote.signature.clone(),
+        );
+        let res = ensure_precommit(&signed_prevote);
+        assert!(matches!(
+            res.as_ref().err().unwrap().kind(),
+            Kind::ImplementationSpecific
+        ));
+        assert!(res
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("is not a precommit (Some(Prevote))"));
+    }
+
+    #[test]
+    fn test_certified_block_id() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2]);
+
+        assert_eq!(commit.block_id(), &commit.block_id);
+        assert_eq!(
+            ProvableCommit::<Info>::certified_block_id(&commit),
+            Some(&commit.block_id)
+        );
+    }
+
+    #[test]
+    fn test_canonical_vote_sign_bytes_len() {
+        use crate::types::block::commit::non_absent_votes;
+        use crate::types::mocks::signed_commit_at_round;
+        use crate::types::vote::vote::canonical_vote_sign_bytes_len;
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        // a large round takes more bytes to encode
+        for round in [0, 1 << 40].iter() {
+            let commit = signed_commit_at_round(&header, &keypairs, &vals, &[0, 1, 2], *round);
+            let votes = non_absent_votes(&commit);
+            let signed_votes = commit.signed_votes(header.chain_id);
+            assert_eq!(votes.len(), 3);
+            for (vote, signed_vote) in votes.iter().zip(signed_votes) {
+                assert_eq!(
+                    canonical_vote_sign_bytes_len(vote, header.chain_id).unwrap(),
+                    signed_vote.unwrap().sign_bytes().len()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_duplicate_policy() {
+        use crate::types::block::commit::CommitSigs;
+        use crate::types::block::traits::commit::DuplicatePolicy;
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2]);
+
+        // validator 0's vote, a second time in the absent slot
+        let mut signatures = commit.signatures.clone().into_vec();
+        signatures[3] = signatures[0].clone();
+        let mut crafted = commit;
+        crafted.signatures = CommitSigs::new(signatures);
+
+        let res =
+            crafted.voting_power_in_with_policy(header.chain_id, &vals, DuplicatePolicy::default());
+        assert!(res
+            .err()
+            .unwrap()
+            .to_string()
+            .starts_with("Implementation specific error: Duplicate vote found by validator"));
+        assert_eq!(
+            crafted
+                .voting_power_in_with_policy(header.chain_id, &vals, DuplicatePolicy::IgnoreExtra)
+                .unwrap(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_validator_voting_in_two_flags() {
+        use crate::types::block::commit::CommitSigs;
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2]);
+
+        // validator 0 votes for the block, and again for nil in the absent slot
+        let mut signatures = commit.signatures.clone().into_vec();
+        signatures[3] = match &signatures[0] {
+            CommitSig::BlockIDFlagCommit {
+                validator_address,
+                timestamp,
+                signature,
+            } => CommitSig::BlockIDFlagNil {
+                validator_address: *validator_address,
+                timestamp: *timestamp,
+                signature: signature.clone(),
+            },
+            _ => panic!("expected a commit vote"),
+        };
+        let mut crafted = commit.clone();
+        crafted.signatures = CommitSigs::new(signatures);
+
+        // the commit is well-formed as far as membership goes
+        assert!(crafted.validate(&vals).is_ok());
+        // but the second vote is a duplicate, whatever its flag
+        let res = crafted.voting_power_in(header.chain_id, &vals);
+        assert!(res
+            .err()
+            .unwrap()
+            .to_string()
+            .starts_with("Implementation specific error: Duplicate vote found by validator"));
+        // and the validator is only counted once as a signer
+        assert_eq!(
+            crafted
+                .verified_signers(header.chain_id, &vals)
+                .unwrap()
+                .len(),
+            3
+        );
+    }
+
+    #[cfg(feature = "dangerous-replay")]
+    #[test]
+    fn test_voting_power_in_skipping_signatures() {
+        use crate::types::block::commit::CommitSigs;
+        use crate::types::signature::Signature;
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 5);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2]);
+
+        // every signature of the commit is garbage
+        let mut forged = commit.clone();
+        forged.signatures = CommitSigs::new(
+            commit
+                .signatures
+                .iter()
+                .map(|commit_sig| match commit_sig {
+                    CommitSig::BlockIDFlagCommit {
+                        validator_address,
+                        timestamp,
+                        ..
+                    } => CommitSig::BlockIDFlagCommit {
+                        validator_address: *validator_address,
+                        timestamp: *timestamp,
+                        signature: Signature::new(vec![0; 64]),
+                    },
+                    other => other.clone(),
+                })
+                .collect::<Vec<CommitSig>>(),
+        );
+        assert!(forged.voting_power_in(header.chain_id, &vals).is_err());
+        assert_eq!(
+            forged
+                .voting_power_in_skipping_signatures(header.chain_id, &vals)
+                .unwrap(),
+            15
+        );
+        assert_eq!(
+            commit
+                .voting_power_in_skipping_signatures(header.chain_id, &vals)
+                .unwrap(),
+            commit.voting_power_in(header.chain_id, &vals).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_voting_power_in_report() {
+        use crate::types::signature::Signature;
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 5);
+        let header = light_header(10, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2, 3]);
+        assert_eq!(
+            commit.voting_power_in_report(header.chain_id, &vals),
+            VotingPowerReport {
+                signed_power: 20,
+                failures: vec![],
+            }
+        );
+
+        // tamper with the signature of the third validator
+        let mut tampered = commit.clone();
+        let mut commit_sigs: Vec<CommitSig> = commit.signatures.iter().cloned().collect();
+        if let CommitSig::BlockIDFlagCommit { signature, .. } = &mut commit_sigs[2] {
+            let mut bytes = signature.raw();
+            bytes[0] ^= 1;
+            *signature = Signature::new(bytes);
+        }
+        tampered.signatures = CommitSigs::new(commit_sigs);
+        assert!(tampered.voting_power_in(header.chain_id, &vals).is_err());
+        assert_eq!(
+            tampered.voting_power_in_report(header.chain_id, &vals),
+            VotingPowerReport {
+                signed_power: 15,
+                failures: vec![(
+                    vals.validators()[2].address(),
+                    "invalid signature".to_string()
+                )],
+            }
+        );
+    }
+}
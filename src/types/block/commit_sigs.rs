@@ -32,6 +32,17 @@ pub enum CommitSig {
     },
 }
 
+impl CommitSig {
+    /// The block_id_flag this signature is encoded with (1=Absent, 2=Commit, 3=Nil)
+    pub fn block_id_flag(&self) -> BlockIDFlag {
+        match self {
+            CommitSig::BlockIDFlagAbsent => BlockIDFlag::Absent,
+            CommitSig::BlockIDFlagCommit { .. } => BlockIDFlag::Commit,
+            CommitSig::BlockIDFlagNil { .. } => BlockIDFlag::Nil,
+        }
+    }
+}
+
 // Todo: https://github.com/informalsystems/tendermint-rs/issues/259 - CommitSig Timestamp can be zero time
 // Todo: https://github.com/informalsystems/tendermint-rs/issues/260 - CommitSig validator address missing in Absent vote
 impl TryFrom<RawCommitSig> for CommitSig {
@@ -117,3 +128,58 @@ impl From<CommitSig> for RawCommitSig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::CommitSig;
+    use crate::serialization::raw_commit_sigs::BlockIDFlag;
+
+    const VALIDATOR_ADDRESS: &str = "A8AE5A7C1A4FBB1A5D2D5E1A3B7F0E2C9D8B6A41";
+    const SIGNATURE: &str =
+        "Hf9l9wNwHZMBlhHBbQSQC4JCyk6CnuPrLkKxIr6ITKM+gK6NaTg7EyOzOKfHT6SZzwVCN2pMSAQNXe6TEdmgDg==";
+
+    fn commit_sig_json(flag: u8) -> String {
+        format!(
+            r#"{{"block_id_flag":{},"validator_address":"{}","timestamp":"2020-07-01T10:00:00Z","signature":"{}"}}"#,
+            flag, VALIDATOR_ADDRESS, SIGNATURE
+        )
+    }
+
+    #[test]
+    fn test_deserialize_absent_flag() {
+        let json = r#"{"block_id_flag":1,"validator_address":"","timestamp":"0001-01-01T00:00:00Z","signature":null}"#;
+        let sig: CommitSig = serde_json::from_str(json).unwrap();
+        assert_eq!(sig, CommitSig::BlockIDFlagAbsent);
+        assert_eq!(sig.block_id_flag(), BlockIDFlag::Absent);
+    }
+
+    #[test]
+    fn test_deserialize_commit_flag() {
+        let sig: CommitSig = serde_json::from_str(&commit_sig_json(2)).unwrap();
+        assert!(matches!(sig, CommitSig::BlockIDFlagCommit { .. }));
+        assert_eq!(sig.block_id_flag(), BlockIDFlag::Commit);
+    }
+
+    #[test]
+    fn test_deserialize_nil_flag() {
+        let sig: CommitSig = serde_json::from_str(&commit_sig_json(3)).unwrap();
+        assert!(matches!(sig, CommitSig::BlockIDFlagNil { .. }));
+        assert_eq!(sig.block_id_flag(), BlockIDFlag::Nil);
+    }
+
+    #[test]
+    fn test_deserialize_unknown_flag() {
+        assert!(serde_json::from_str::<CommitSig>(&commit_sig_json(0)).is_err());
+        assert!(serde_json::from_str::<CommitSig>(&commit_sig_json(4)).is_err());
+    }
+
+    #[test]
+    fn test_flag_roundtrip() {
+        for flag in 2..=3 {
+            let sig: CommitSig = serde_json::from_str(&commit_sig_json(flag)).unwrap();
+            let json = serde_json::to_value(&sig).unwrap();
+            assert_eq!(json["block_id_flag"], flag);
+            assert_eq!(serde_json::from_value::<CommitSig>(json).unwrap(), sig);
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::errors::{Error, Kind};
 use crate::merkle_tree::simple_hash_from_byte_vectors;
 use crate::types::amino::{message::AminoMessage, BlockId, ConsensusVersion, TimeMsg};
 use crate::types::block;
@@ -5,8 +6,12 @@ use crate::types::block::height::Height;
 use crate::types::block::traits::header::{Header as HeaderT, Height as HeightT};
 use crate::types::hash::Hash;
 use crate::types::time::Time;
+use crate::types::traits::validator::Validator;
+use crate::types::traits::validator_set::ValidatorSet;
+use crate::types::validator::Set;
 use crate::types::{account, chain};
 use std::fmt::Debug;
+use subtle_encoding::hex;
 
 /// Block `Header` values contain metadata about the block and about the
 /// consensus, as well as commitments to the data in the current block, the
@@ -64,6 +69,96 @@ pub struct Header {
     pub proposer_address: account::Id,
 }
 
+impl Header {
+    /// Block time as seconds since the unix epoch, negative if the block is older.
+    pub fn unix_time(&self) -> i64 {
+        self.time.unix_seconds()
+    }
+
+    /// The validator of the given set which proposed this block, if it is part of it.
+    pub fn proposer<V>(&self, vals: &Set<V>) -> Option<V>
+    where
+        V: Validator,
+    {
+        vals.validator(self.proposer_address)
+    }
+
+    /// Ensure this header commits to the given application state root (e.g. to use it
+    /// for state proofs).
+    pub fn verify_app_hash(&self, expected: &[u8]) -> Result<(), Error> {
+        if self.app_hash != expected {
+            let to_hex = |bytes: &[u8]| String::from_utf8(hex::encode_upper(bytes)).unwrap();
+            return Err(Kind::InvalidAppHash {
+                header_app_hash: to_hex(&self.app_hash),
+                expected_app_hash: to_hex(expected),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Hash of the header as computed by the given tendermint version, which determines
+    /// the fields committed to and their order. `try_hash` uses the default version.
+    pub fn try_hash_with_version(&self, version: HeaderVersion) -> Result<Hash, Error> {
+        let mut fields_bytes: Vec<Vec<u8>> = Vec::with_capacity(version.fields_count());
+        fields_bytes.push(AminoMessage::bytes_vec(&ConsensusVersion::from(
+            &self.version,
+        )));
+        fields_bytes.push(bytes_enc(self.chain_id.as_bytes()));
+        fields_bytes.push(encode_varint(self.height.value()));
+        fields_bytes.push(encode_time(self.time)?);
+        if let HeaderVersion::V0_32 { num_txs, total_txs } = version {
+            fields_bytes.push(encode_varint(num_txs));
+            fields_bytes.push(encode_varint(total_txs));
+        }
+        fields_bytes.push(
+            self.last_block_id
+                .as_ref()
+                .map_or(vec![], |id| AminoMessage::bytes_vec(&BlockId::from(id))),
+        );
+        fields_bytes.push(self.last_commit_hash.as_ref().map_or(vec![], encode_hash));
+        fields_bytes.push(self.data_hash.as_ref().map_or(vec![], encode_hash));
+        fields_bytes.push(encode_hash(&self.validators_hash));
+        fields_bytes.push(encode_hash(&self.next_validators_hash));
+        fields_bytes.push(encode_hash(&self.consensus_hash));
+        fields_bytes.push(bytes_enc(&self.app_hash));
+        fields_bytes.push(self.last_results_hash.as_ref().map_or(vec![], encode_hash));
+        fields_bytes.push(self.evidence_hash.as_ref().map_or(vec![], encode_hash));
+        fields_bytes.push(bytes_enc(self.proposer_address.as_bytes()));
+        debug_assert_eq!(fields_bytes.len(), version.fields_count());
+
+        Ok(Hash::Sha256(simple_hash_from_byte_vectors(fields_bytes)))
+    }
+}
+
+/// Tendermint version whose header layout is used to hash a [`Header`]: versions differ in
+/// the fields the header hash commits to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum HeaderVersion {
+    /// Tendermint v0.32 and earlier, where the header also commits to the number of
+    /// transactions in the block and in the whole chain (right after the time). These
+    /// aren't part of [`Header`], so they have to be provided.
+    V0_32 {
+        /// Number of transactions in the block
+        num_txs: u64,
+        /// Number of transactions in the chain up to this block
+        total_txs: u64,
+    },
+    /// Tendermint v0.33, which removed `num_txs` and `total_txs`
+    #[default]
+    V0_33,
+}
+
+impl HeaderVersion {
+    /// Number of fields the header hash commits to.
+    pub fn fields_count(self) -> usize {
+        match self {
+            HeaderVersion::V0_32 { .. } => 16,
+            HeaderVersion::V0_33 => 14,
+        }
+    }
+}
+
 impl HeaderT for Header {
     type Time = Time;
 
@@ -87,38 +182,35 @@ impl HeaderT for Header {
         self.next_validators_hash
     }
 
+    fn consensus_hash(&self) -> Option<Hash> {
+        Some(self.consensus_hash)
+    }
+
+    fn last_block_hash(&self) -> Option<Hash> {
+        self.last_block_id.as_ref().map(|id| id.hash)
+    }
+
     fn hash(&self) -> Hash {
         // Note that if there is an encoding problem this will
         // panic (as the golang code would):
         // https://github.com/tendermint/tendermint/blob/134fe2896275bb926b49743c1e25493f6b24cc31/types/block.go#L393
         // https://github.com/tendermint/tendermint/blob/134fe2896275bb926b49743c1e25493f6b24cc31/types/encoding_helper.go#L9:6
+        self.try_hash().unwrap()
+    }
 
-        let mut fields_bytes: Vec<Vec<u8>> = Vec::with_capacity(16);
-        fields_bytes.push(AminoMessage::bytes_vec(&ConsensusVersion::from(
-            &self.version,
-        )));
-        fields_bytes.push(bytes_enc(self.chain_id.as_bytes()));
-        fields_bytes.push(encode_varint(self.height.value()));
-        fields_bytes.push(AminoMessage::bytes_vec(&TimeMsg::from(self.time)));
-        fields_bytes.push(
-            self.last_block_id
-                .as_ref()
-                .map_or(vec![], |id| AminoMessage::bytes_vec(&BlockId::from(id))),
-        );
-        fields_bytes.push(self.last_commit_hash.as_ref().map_or(vec![], encode_hash));
-        fields_bytes.push(self.data_hash.as_ref().map_or(vec![], encode_hash));
-        fields_bytes.push(encode_hash(&self.validators_hash));
-        fields_bytes.push(encode_hash(&self.next_validators_hash));
-        fields_bytes.push(encode_hash(&self.consensus_hash));
-        fields_bytes.push(bytes_enc(&self.app_hash));
-        fields_bytes.push(self.last_results_hash.as_ref().map_or(vec![], encode_hash));
-        fields_bytes.push(self.evidence_hash.as_ref().map_or(vec![], encode_hash));
-        fields_bytes.push(bytes_enc(self.proposer_address.as_bytes()));
-
-        Hash::Sha256(simple_hash_from_byte_vectors(fields_bytes))
+    fn try_hash(&self) -> Result<Hash, Error> {
+        self.try_hash_with_version(HeaderVersion::default())
     }
 }
 
+// TimeMsg::from(Time) panics for times before the unix epoch,
+// so check the time can be represented before encoding it.
+fn encode_time(time: Time) -> Result<Vec<u8>, Error> {
+    time.duration_since(Time::unix_epoch())
+        .map_err(|e| Kind::ImplementationSpecific.context(e))?;
+    Ok(AminoMessage::bytes_vec(&TimeMsg::from(time)))
+}
+
 fn bytes_enc(bytes: &[u8]) -> Vec<u8> {
     let mut chain_id_enc = vec![];
     prost_amino::encode_length_delimiter(bytes.len(), &mut chain_id_enc).unwrap();
@@ -150,3 +242,139 @@ pub struct Version {
     #[serde(with = "crate::serialization::from_str")]
     pub app: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::HeaderVersion;
+    use crate::types::block;
+    use crate::types::block::height::Height;
+    use crate::types::block::traits::header::Header;
+    use crate::types::hash::{Algorithm, Hash};
+    use crate::types::mocks::{fixed_hash, generate_keypairs, light_header, validator_set};
+    use crate::types::traits::validator::Validator;
+    use crate::types::traits::validator_set::ValidatorSet;
+    use crate::types::{account, chain};
+    use crate::Time;
+    use sha2::{Digest, Sha256};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_try_hash() {
+        let header = light_header(4, Time::unix_epoch(), fixed_hash(), fixed_hash());
+        assert_eq!(header.try_hash().unwrap(), header.hash());
+
+        let mut pre_epoch_header = header;
+        pre_epoch_header.time = Time::parse_from_rfc3339("1969-12-31T23:59:59Z").unwrap();
+        assert!(pre_epoch_header.try_hash().is_err());
+    }
+
+    // The header of `TestHeaderHash` in tendermint's types/block_test.go.
+    fn tendermint_test_header() -> super::Header {
+        let tmhash = |bytes: &[u8]| Hash::new(Algorithm::Sha256, &Sha256::digest(bytes)).unwrap();
+        let zero_hash = Hash::new(Algorithm::Sha256, &[0; 32]).unwrap();
+        let mut proposer_address = [0; 20];
+        proposer_address.copy_from_slice(&Sha256::digest(b"proposer_address")[..20]);
+        super::Header {
+            version: super::Version { block: 1, app: 2 },
+            chain_id: chain::Id::from_str("chainId").unwrap(),
+            height: Height(3),
+            time: Time::parse_from_rfc3339("2019-10-13T16:14:44Z").unwrap(),
+            last_block_id: Some(block::id::Id {
+                hash: zero_hash,
+                part_set_header: Some(block::parts::Header {
+                    total: 6,
+                    hash: zero_hash,
+                }),
+            }),
+            last_commit_hash: Some(tmhash(b"last_commit_hash")),
+            data_hash: Some(tmhash(b"data_hash")),
+            validators_hash: tmhash(b"validators_hash"),
+            next_validators_hash: tmhash(b"next_validators_hash"),
+            consensus_hash: tmhash(b"consensus_hash"),
+            app_hash: Sha256::digest(b"app_hash").to_vec(),
+            last_results_hash: Some(tmhash(b"last_results_hash")),
+            evidence_hash: Some(tmhash(b"evidence_hash")),
+            proposer_address: account::Id::new(proposer_address),
+        }
+    }
+
+    #[test]
+    fn test_try_hash_with_version() {
+        let header = tendermint_test_header();
+        let hash_with_version = |version| header.try_hash_with_version(version).unwrap();
+
+        // the expected hash of `TestHeaderHash` as of tendermint v0.33
+        assert_eq!(HeaderVersion::default(), HeaderVersion::V0_33);
+        assert_eq!(hash_with_version(HeaderVersion::V0_33), header.hash());
+        assert_eq!(
+            hash_with_version(HeaderVersion::V0_33).to_string(),
+            "ABDC78921B18A47EE6BEF5E31637BADB0F3E587E3C0F4DB2D1E93E9FF0533862"
+        );
+
+        // v0.32 also commits to the transactions counts, right after the time (regression
+        // value, not cross-checked against a v0.32 node)
+        let v0_32 = hash_with_version(HeaderVersion::V0_32 {
+            num_txs: 4,
+            total_txs: 5,
+        });
+        assert_eq!(
+            v0_32.to_string(),
+            "A37A7A69D89D3A66D599B0914A53F959EFE490EE9B449C95852F6FB331D58D07"
+        );
+        assert_ne!(
+            v0_32,
+            hash_with_version(HeaderVersion::V0_32 {
+                num_txs: 0,
+                total_txs: 5,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unix_time() {
+        let header = light_header(
+            4,
+            Time::parse_from_rfc3339("2020-09-13T12:26:40.75Z").unwrap(),
+            fixed_hash(),
+            fixed_hash(),
+        );
+        assert_eq!(header.unix_time(), 1_600_000_000);
+
+        let mut epoch_header = header.clone();
+        epoch_header.time = Time::unix_epoch();
+        assert_eq!(epoch_header.unix_time(), 0);
+
+        let mut pre_epoch_header = header;
+        pre_epoch_header.time = Time::parse_from_rfc3339("1969-12-31T23:59:59Z").unwrap();
+        assert_eq!(pre_epoch_header.unix_time(), -1);
+        pre_epoch_header.time = Time::parse_from_rfc3339("1969-12-31T23:59:58.5Z").unwrap();
+        assert_eq!(pre_epoch_header.unix_time(), -2);
+    }
+
+    #[test]
+    fn test_proposer() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let mut header = light_header(4, Time::unix_epoch(), vals.hash(), vals.hash());
+        assert!(header.proposer(&vals).is_none());
+
+        let proposer = vals.validators()[2];
+        header.proposer_address = proposer.address();
+        assert_eq!(header.proposer(&vals), Some(proposer));
+    }
+
+    #[test]
+    fn test_verify_app_hash() {
+        let mut header = light_header(4, Time::unix_epoch(), fixed_hash(), fixed_hash());
+        header.app_hash = vec![0xAB, 0xCD];
+
+        assert!(header.verify_app_hash(&[0xAB, 0xCD]).is_ok());
+
+        let res = header.verify_app_hash(&[0xAB, 0xCE]);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "header's app hash does not match the expected app hash (ABCD!=ABCE)"
+        );
+        assert!(header.verify_app_hash(&[]).is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use crate::types::block::commit::SignedHeader;
+use crate::types::block::traits::commit::ProvableCommit;
+use crate::types::block::traits::header::Header;
+use crate::types::traits::validator::Validator;
+use serde::Deserialize;
+
+/// LightBlock bundles a [`SignedHeader`] with the validators which signed it and the
+/// next validators it announces, i.e. everything needed to verify it, as IBC and
+/// tendermint-rs exchange light blocks:
+/// `{"signed_header": .., "validator_set": [..], "next_validator_set": [..]}`.
+/// Other fields (e.g. the `provider` of tendermint-rs) are ignored.
+///
+/// **Note:** As for [`TrustedState`], the `#[serde(bound = ...)]` attribute is required
+/// to derive `Deserialize` as the validator sets are associated types.
+///
+/// [`TrustedState`]: crate::TrustedState
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(bound(deserialize = "C::ValidatorSet: Deserialize<'de>"))]
+pub struct LightBlock<C, H, V>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    signed_header: SignedHeader<C, H>,
+    validator_set: C::ValidatorSet,
+    next_validator_set: C::ValidatorSet,
+}
+
+impl<C, H, V> LightBlock<C, H, V>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    /// Bundle the signed header with its validators and next validators, without any
+    /// check: they are checked when verifying the light block.
+    pub fn new(
+        signed_header: SignedHeader<C, H>,
+        validator_set: C::ValidatorSet,
+        next_validator_set: C::ValidatorSet,
+    ) -> Self {
+        Self {
+            signed_header,
+            validator_set,
+            next_validator_set,
+        }
+    }
+
+    pub fn signed_header(&self) -> &SignedHeader<C, H> {
+        &self.signed_header
+    }
+
+    pub fn validators(&self) -> &C::ValidatorSet {
+        &self.validator_set
+    }
+
+    pub fn next_validators(&self) -> &C::ValidatorSet {
+        &self.next_validator_set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::block::commit::Commit;
+    use crate::types::block::header::Header as LightHeader;
+    use crate::types::mocks::{
+        generate_keypairs, signed_header_at, trusted_state_at, validator_set,
+    };
+    use crate::types::time::Time;
+    use crate::types::validator::Info;
+    use crate::{verify_single_light_block, TrustThresholdFraction};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_verify_light_block_json() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let sh = signed_header_at(2, time, &keypairs, &vals, &[0, 1, 2, 3]);
+
+        let json = serde_json::json!({
+            "signed_header": sh,
+            "validator_set": vals,
+            "next_validator_set": vals,
+            "provider": "ignored",
+        })
+        .to_string();
+        let light_block: LightBlock<Commit, LightHeader, Info> =
+            serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            light_block,
+            LightBlock::new(sh.clone(), vals.clone(), vals.clone())
+        );
+
+        let new_state = verify_single_light_block(
+            trusted_state,
+            &light_block,
+            TrustThresholdFraction::default(),
+            Duration::new(100, 0),
+            SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+        )
+        .unwrap();
+        assert_eq!(new_state.last_header(), &sh);
+        assert_eq!(new_state.validators(), &vals);
+    }
+}
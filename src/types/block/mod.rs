@@ -1,7 +1,9 @@
+pub(crate) mod bitmap_commit;
 pub(crate) mod commit;
-mod commit_sigs;
+pub(crate) mod commit_sigs;
 pub(crate) mod header;
 pub(crate) mod height;
 pub(crate) mod id;
+pub(crate) mod light_block;
 pub(crate) mod parts;
 pub(crate) mod traits;
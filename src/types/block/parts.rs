@@ -6,6 +6,7 @@ use crate::types::hash::Hash;
 #[derive(Serialize, Deserialize, Clone, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
 pub struct Header {
     /// Number of parts in this block
+    #[serde(deserialize_with = "crate::serialization::custom::parse_u64_string_or_int")]
     pub total: u64,
 
     /// Hash of the parts set header,
@@ -18,3 +19,19 @@ impl Header {
         Header { total, hash }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Header;
+
+    #[test]
+    fn test_deserialize_total() {
+        let hash = "E4E7D9A8C2A8A6B7E0F5D3C1B9A7F5E3D1C9B7A5F3E1D9C7B5A3F1E9D7C5B3A1";
+        let from_int: Header =
+            serde_json::from_str(&format!(r#"{{"total":3,"hash":"{}"}}"#, hash)).unwrap();
+        let from_string: Header =
+            serde_json::from_str(&format!(r#"{{"total":"3","hash":"{}"}}"#, hash)).unwrap();
+        assert_eq!(from_int, from_string);
+        assert_eq!(from_int.total, 3);
+    }
+}
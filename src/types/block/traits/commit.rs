@@ -1,10 +1,15 @@
-use crate::errors::Error;
+use crate::errors::{Error, Kind};
+use crate::types::block::id::Id as BlockId;
+use crate::types::block::traits::header::Height;
 use crate::types::chain;
 use crate::types::hash::Hash;
+use crate::types::signature_scheme::SignatureSchemeRegistry;
 use crate::types::traits::validator::Validator;
 use crate::types::traits::validator_set::ValidatorSet;
+use anomaly::fail;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::cell::Cell;
 use std::fmt::Debug;
 
 /// Commit is used to prove a Header can be trusted.
@@ -19,6 +24,23 @@ where
     /// Hash of the header this commit is for.
     fn header_hash(&self) -> Hash;
 
+    /// Height of the header this commit is for, for commits which carry it.
+    fn height(&self) -> Option<Height> {
+        None
+    }
+
+    /// Id (hash and part set header) of the block this commit is for, for commits which
+    /// carry it.
+    fn certified_block_id(&self) -> Option<&BlockId> {
+        None
+    }
+
+    /// Round at which the header this commit is for was decided, for commits which
+    /// carry it.
+    fn round(&self) -> Option<u64> {
+        None
+    }
+
     /// Compute the voting power of the validators that correctly signed the commit,
     /// according to their voting power in the passed in validator set.
     /// Will return an error in case an invalid signature was included.
@@ -32,9 +54,189 @@ where
     fn voting_power_in(&self, chain_id: chain::Id, vals: &Self::ValidatorSet)
         -> Result<u64, Error>;
 
+    /// Same as `voting_power_in`, adding the number of signatures verified along the way
+    /// to `signatures_verified`. The default implementation doesn't count anything;
+    /// implementers verifying signatures one by one should override it.
+    fn voting_power_in_counting(
+        &self,
+        chain_id: chain::Id,
+        vals: &Self::ValidatorSet,
+        signatures_verified: &Cell<u64>,
+    ) -> Result<u64, Error> {
+        let _ = signatures_verified;
+        self.voting_power_in(chain_id, vals)
+    }
+
+    /// Same as `voting_power_in`, verifying the signatures with the verifiers of `schemes`
+    /// (see `Validator::verify_signature_with`) and, if given, counting them as
+    /// `voting_power_in_counting` does. The default implementation ignores the registry;
+    /// implementers verifying signatures one by one should override it.
+    fn voting_power_in_with_schemes(
+        &self,
+        chain_id: chain::Id,
+        vals: &Self::ValidatorSet,
+        schemes: &SignatureSchemeRegistry,
+        signatures_verified: Option<&Cell<u64>>,
+    ) -> Result<u64, Error> {
+        let _ = schemes;
+        match signatures_verified {
+            Some(count) => self.voting_power_in_counting(chain_id, vals, count),
+            None => self.voting_power_in(chain_id, vals),
+        }
+    }
+
+    /// Same as `voting_power_in`, with `duplicates` deciding what happens when a validator
+    /// voted more than once. The default implementation ignores the policy; implementers
+    /// which can see duplicate votes should override it.
+    fn voting_power_in_with_policy(
+        &self,
+        chain_id: chain::Id,
+        vals: &Self::ValidatorSet,
+        duplicates: DuplicatePolicy,
+    ) -> Result<u64, Error> {
+        let _ = duplicates;
+        self.voting_power_in(chain_id, vals)
+    }
+
     /// Implementers should add addition validation against the given validator set
     /// or other implementation specific validation here.
     /// E.g. validate that the length of the included signatures in the commit match
     /// with the number of validators.
     fn validate(&self, vals: &Self::ValidatorSet) -> Result<(), Error>;
+
+    /// Same as `validate`; with `strict_count` false, a commit may carry more signatures
+    /// than there are validators (e.g. extension data appended by newer tendermint
+    /// versions). The default implementation ignores the flag; implementers checking the
+    /// number of signatures should override it.
+    fn validate_with(&self, vals: &Self::ValidatorSet, strict_count: bool) -> Result<(), Error> {
+        let _ = strict_count;
+        self.validate(vals)
+    }
+}
+
+/// What to do with the later votes of a validator which voted more than once in a commit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Fail, as tendermint never produces such a commit.
+    #[default]
+    Reject,
+    /// Count the validator once, skipping its later votes without verifying them.
+    IgnoreExtra,
+}
+
+/// AggregateCommit is implemented by commits where the signers are identified by a
+/// bitmap over the validator set and a single aggregate signature (e.g. BLS)
+/// covers all of their votes at once.
+///
+/// Such commits can't be verified one signature at a time, so implementers of
+/// [`ProvableCommit`] should delegate `voting_power_in` to
+/// [`AggregateCommit::aggregate_voting_power_in`] with the verifier of their scheme.
+pub trait AggregateCommit {
+    /// Bitmap over the validator set; the i-th entry is true iff the i-th validator signed.
+    fn signers(&self) -> &[bool];
+
+    /// The bytes covered by the aggregate signature.
+    fn aggregate_sign_bytes(&self, chain_id: chain::Id) -> Vec<u8>;
+
+    /// The aggregate signature of all signers.
+    fn aggregate_signature(&self) -> &[u8];
+
+    /// Compute the voting power of the signers in the given validator set after verifying
+    /// the aggregate signature. Fails if the bitmap doesn't cover the validator set
+    /// or if the aggregate signature is invalid.
+    fn aggregate_voting_power_in<V, S, A>(
+        &self,
+        chain_id: chain::Id,
+        vals: &S,
+        verifier: &A,
+    ) -> Result<u64, Error>
+    where
+        V: Validator,
+        S: ValidatorSet<V>,
+        A: AggregateVerifier<V>,
+    {
+        let bitmap = self.signers();
+        if bitmap.len() != vals.validators().len() {
+            fail!(
+                Kind::ImplementationSpecific,
+                "signers bitmap length: {} doesn't match validators count: {}",
+                bitmap.len(),
+                vals.validators().len()
+            );
+        }
+
+        let signers: Vec<V> = vals
+            .validators()
+            .iter()
+            .zip(bitmap.iter())
+            .filter(|(_, signed)| **signed)
+            .map(|(val, _)| val.clone())
+            .collect();
+        if signers.is_empty() {
+            return Ok(0);
+        }
+
+        let sign_bytes = self.aggregate_sign_bytes(chain_id);
+        if !verifier.verify_aggregate(&signers, &sign_bytes, self.aggregate_signature()) {
+            fail!(
+                Kind::ImplementationSpecific,
+                "Couldn't verify aggregate signature of {} signers on sign_bytes {:?}",
+                signers.len(),
+                sign_bytes,
+            );
+        }
+
+        Ok(signers.iter().map(|val| val.power()).sum())
+    }
+}
+
+/// AggregateVerifier verifies one aggregate signature made by several validators.
+pub trait AggregateVerifier<V>
+where
+    V: Validator,
+{
+    fn verify_aggregate(&self, signers: &[V], sign_bytes: &[u8], signature: &[u8]) -> bool;
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::block::traits::commit::ProvableCommit;
+    use crate::types::chain;
+    use crate::types::mocks::{fixed_hash, MockAggregateCommit, MockValSet};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_aggregate_voting_power_in() {
+        let chain_id = chain::Id::from_str("test").unwrap();
+        let vals = MockValSet::new(vec![1, 2, 3, 4]);
+
+        // validators with power 1, 3 and 4 signed
+        let commit = MockAggregateCommit::new(fixed_hash(), &vals, vec![true, false, true, true]);
+        assert_eq!(commit.voting_power_in(chain_id, &vals).unwrap(), 8);
+
+        // nobody signed
+        let commit = MockAggregateCommit::new(fixed_hash(), &vals, vec![false; 4]);
+        assert_eq!(commit.voting_power_in(chain_id, &vals).unwrap(), 0);
+
+        // the aggregate signature doesn't match the signers
+        let mut commit = MockAggregateCommit::new(fixed_hash(), &vals, vec![true; 4]);
+        commit.tamper_signature();
+        let res = commit.voting_power_in(chain_id, &vals);
+        assert!(res.is_err());
+        assert!(res
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("Couldn't verify aggregate signature of 4 signers"));
+
+        // the bitmap doesn't cover the validator set
+        let commit = MockAggregateCommit::new(fixed_hash(), &vals, vec![true; 3]);
+        let res = commit.voting_power_in(chain_id, &vals);
+        assert!(res.is_err());
+        assert!(res
+            .err()
+            .unwrap()
+            .to_string()
+            .ends_with("signers bitmap length: 3 doesn't match validators count: 4"));
+    }
 }
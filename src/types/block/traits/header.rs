@@ -1,3 +1,4 @@
+use crate::errors::Error;
 use crate::types::chain;
 use crate::types::hash::Hash;
 use serde::de::DeserializeOwned;
@@ -22,6 +23,22 @@ pub trait Header: Clone + Debug + Serialize + DeserializeOwned {
     fn validators_hash(&self) -> Hash;
     fn next_validators_hash(&self) -> Hash;
 
+    /// Hash of the consensus parameters, for headers which carry it.
+    fn consensus_hash(&self) -> Option<Hash> {
+        None
+    }
+
+    /// Hash of the previous block, for headers which carry it.
+    fn last_block_hash(&self) -> Option<Hash> {
+        None
+    }
+
     /// Hash of the header (ie. the hash of the block).
     fn hash(&self) -> Hash;
+
+    /// Hash of the header, returning an error instead of panicking if the
+    /// header can't be encoded. Use this for headers received from untrusted sources.
+    fn try_hash(&self) -> Result<Hash, Error> {
+        Ok(self.hash())
+    }
 }
@@ -69,9 +69,13 @@ impl FromStr for Id {
             return Err(Kind::Length.into());
         }
 
-        for byte in name.as_bytes() {
-            match byte {
-                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'_' | b'.' => (),
+        // Tendermint doesn't restrict chain ids to ASCII, and signs them as UTF-8.
+        // Multi-byte characters are allowed as long as they are printable;
+        // the length limit applies to the encoded bytes.
+        for c in name.chars() {
+            match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' | '.' => (),
+                c if !c.is_ascii() && !c.is_control() && !c.is_whitespace() => (),
                 _ => return Err(Kind::Parse.into()),
             }
         }
@@ -144,6 +148,38 @@ mod tests {
         assert_eq!(&long_id.parse::<Id>().unwrap().as_str(), &long_id);
     }
 
+    #[test]
+    fn parses_multi_byte_chain_ids() {
+        let name = "テスト-ĉeno-1";
+        let id = name.parse::<Id>().unwrap();
+        assert_eq!(id.as_str(), name);
+        assert_eq!(id.as_bytes(), name.as_bytes());
+
+        // the length limit is in bytes, not characters
+        let long_id = "é".repeat(MAX_LENGTH / 2);
+        assert_eq!(long_id.parse::<Id>().unwrap().as_str(), long_id);
+        let overlong_id = "é".repeat(MAX_LENGTH / 2 + 1);
+        assert_eq!(
+            *overlong_id.parse::<Id>().unwrap_err().to_string(),
+            Kind::Length.to_string()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_characters() {
+        for name in &[
+            "gaia 9000",
+            "gaia\u{0}9000",
+            "gaia\u{2003}9000",
+            "gaia/9000",
+        ] {
+            assert_eq!(
+                *name.parse::<Id>().unwrap_err().to_string(),
+                Kind::Parse.to_string()
+            );
+        }
+    }
+
     #[test]
     fn rejects_empty_chain_ids() {
         assert_eq!(
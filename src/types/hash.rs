@@ -14,6 +14,9 @@ use crate::errors::{Error, Kind};
 /// Output size for the SHA-256 hash function
 pub const SHA256_HASH_SIZE: usize = 32;
 
+/// Algorithm tag some tools prefix SHA-256 hashes with, e.g. `sha256:ABCD...`
+const SHA256_PREFIX: &str = "sha256:";
+
 /// Hash algorithms
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum Algorithm {
@@ -56,6 +59,12 @@ impl Hash {
         }
     }
 
+    /// Encode the hash as lower-case hexadecimal, the way block explorers show it.
+    /// `Display` (and serialization) keep the canonical upper case.
+    pub fn to_hex_lower(self) -> String {
+        Hex::lower_case().encode_to_string(self.as_bytes()).unwrap()
+    }
+
     /// Return the digest algorithm used to produce this hash
     pub fn algorithm(self) -> Algorithm {
         match self {
@@ -69,6 +78,11 @@ impl Hash {
             Hash::Sha256(ref h) => h.as_ref(),
         }
     }
+
+    /// Is every byte of the hash zero? Malformed headers carry such placeholder hashes.
+    pub fn is_zero(&self) -> bool {
+        self.as_bytes().iter().all(|b| *b == 0)
+    }
 }
 
 impl Debug for Hash {
@@ -93,7 +107,11 @@ impl FromStr for Hash {
     type Err = BoxError;
 
     fn from_str(s: &str) -> Result<Self, BoxError> {
-        Self::from_hex_upper(Algorithm::Sha256, s)
+        let hex = match s.get(..SHA256_PREFIX.len()) {
+            Some(prefix) if prefix.eq_ignore_ascii_case(SHA256_PREFIX) => &s[SHA256_PREFIX.len()..],
+            _ => s,
+        };
+        Self::from_hex_upper(Algorithm::Sha256, hex)
     }
 }
 
@@ -114,3 +132,55 @@ impl Serialize for Hash {
         self.to_string().serialize(serializer)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_SHA256_HASH: &str =
+        "26C0A41F3243C6BCD7AD2DFF8A8D83A71D29D307B5326C227F734A1A512FE47D";
+
+    #[test]
+    fn parses_unprefixed_hex() {
+        let hash = Hash::from_str(EXAMPLE_SHA256_HASH).unwrap();
+        assert_eq!(hash.to_string(), EXAMPLE_SHA256_HASH);
+    }
+
+    #[test]
+    fn lower_case_hex() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Block {
+            #[serde(with = "crate::serialization::bytes::lower_hash")]
+            hash: Hash,
+        }
+
+        let hash = Hash::from_str(EXAMPLE_SHA256_HASH).unwrap();
+        let lower = EXAMPLE_SHA256_HASH.to_lowercase();
+        assert_eq!(hash.to_hex_lower(), lower);
+        assert_eq!(hash.to_string(), EXAMPLE_SHA256_HASH);
+
+        let block = Block { hash };
+        let json = serde_json::to_string(&block).unwrap();
+        assert_eq!(json, format!(r#"{{"hash":"{}"}}"#, lower));
+        assert_eq!(serde_json::from_str::<Block>(&json).unwrap(), block);
+        let upper_json = format!(r#"{{"hash":"{}"}}"#, EXAMPLE_SHA256_HASH);
+        assert_eq!(serde_json::from_str::<Block>(&upper_json).unwrap(), block);
+    }
+
+    #[test]
+    fn parses_algorithm_prefixed_hex() {
+        let expected = Hash::from_str(EXAMPLE_SHA256_HASH).unwrap();
+        for prefix in &["sha256:", "SHA256:"] {
+            let prefixed = format!("{}{}", prefix, EXAMPLE_SHA256_HASH);
+            assert_eq!(Hash::from_str(&prefixed).unwrap(), expected);
+
+            let json = format!("\"{}\"", prefixed);
+            let deserialized: Hash = serde_json::from_str(&json).unwrap();
+            assert_eq!(deserialized, expected);
+        }
+
+        // unknown algorithms are still rejected
+        let prefixed = format!("sha512:{}", EXAMPLE_SHA256_HASH);
+        assert!(Hash::from_str(&prefixed).is_err());
+    }
+}
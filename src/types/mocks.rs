@@ -4,15 +4,24 @@ use sha2::{Digest, Sha256};
 
 use crate::errors::{Error, Kind};
 use crate::types::account::Id;
-use crate::types::block::traits::commit::ProvableCommit;
+use crate::types::block::commit::{Commit, LightSignedHeader};
+use crate::types::block::header::{Header as LightHeader, Version};
+use crate::types::block::height::Height as BlockHeight;
+use crate::types::block::traits::commit::{AggregateCommit, AggregateVerifier, ProvableCommit};
 use crate::types::block::traits::header::{Header, Height};
 use crate::types::chain;
 use crate::types::hash::{Algorithm, Hash};
 use crate::types::proposer_priority::ProposerPriority;
+use crate::types::pubkey::PublicKey;
+use crate::types::test_util;
+use crate::types::time::Time;
 use crate::types::traits::validator::Validator;
 use crate::types::traits::validator_set::ValidatorSet;
+use crate::types::trusted::LightTrustedState;
+use crate::types::validator::{Info, Set};
 use crate::types::vote::power::Power;
 use crate::SignedHeader;
+use ed25519_dalek::Keypair;
 use std::collections::HashSet;
 use std::iter::FromIterator;
 use std::str::FromStr;
@@ -139,6 +148,10 @@ where
     fn number_of_validators(&self) -> usize {
         unimplemented!()
     }
+
+    fn validators(&self) -> &[V] {
+        &self.vals
+    }
 }
 
 // commit is a list of vals that signed.
@@ -199,6 +212,179 @@ where
 
 pub type MockSignedHeader = SignedHeader<MockCommit<usize>, MockHeader>;
 
+// BLS-like commit: a bitmap of signers and one signature covering all of them.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MockAggregateCommit {
+    hash: Hash,
+    signers: Vec<bool>,
+    signature: Vec<u8>,
+}
+
+impl MockAggregateCommit {
+    // sign the commit with the validators selected by the bitmap.
+    pub fn new(hash: Hash, vals: &MockValSet<usize>, signers: Vec<bool>) -> MockAggregateCommit {
+        let mut commit = MockAggregateCommit {
+            hash,
+            signers,
+            signature: vec![],
+        };
+        let signing_vals: Vec<usize> = vals
+            .vals
+            .iter()
+            .zip(commit.signers.iter())
+            .filter(|(_, signed)| **signed)
+            .map(|(val, _)| *val)
+            .collect();
+        let sign_bytes = commit.aggregate_sign_bytes(chain::Id::from_str("test").unwrap());
+        commit.signature = mock_aggregate_signature(&signing_vals, &sign_bytes);
+        commit
+    }
+
+    pub fn tamper_signature(&mut self) {
+        self.signature[0] ^= 1;
+    }
+}
+
+// the aggregate "signature" of the mock scheme is a digest over the signers and the message.
+fn mock_aggregate_signature(signers: &[usize], sign_bytes: &[u8]) -> Vec<u8> {
+    let mut bytes = serde_json::to_vec(signers).unwrap();
+    bytes.extend_from_slice(sign_bytes);
+    Sha256::digest(&bytes).to_vec()
+}
+
+pub struct MockAggregateVerifier;
+
+impl AggregateVerifier<usize> for MockAggregateVerifier {
+    fn verify_aggregate(&self, signers: &[usize], sign_bytes: &[u8], signature: &[u8]) -> bool {
+        mock_aggregate_signature(signers, sign_bytes) == signature
+    }
+}
+
+impl AggregateCommit for MockAggregateCommit {
+    fn signers(&self) -> &[bool] {
+        &self.signers
+    }
+
+    fn aggregate_sign_bytes(&self, chain_id: chain::Id) -> Vec<u8> {
+        let mut sign_bytes = self.hash.as_bytes().to_vec();
+        sign_bytes.extend_from_slice(chain_id.as_bytes());
+        sign_bytes
+    }
+
+    fn aggregate_signature(&self) -> &[u8] {
+        &self.signature
+    }
+}
+
+impl ProvableCommit<usize> for MockAggregateCommit {
+    type ValidatorSet = MockValSet<usize>;
+
+    fn header_hash(&self) -> Hash {
+        self.hash
+    }
+
+    fn voting_power_in(
+        &self,
+        chain_id: chain::Id,
+        vals: &Self::ValidatorSet,
+    ) -> Result<u64, Error> {
+        self.aggregate_voting_power_in(chain_id, vals, &MockAggregateVerifier)
+    }
+
+    fn validate(&self, _vals: &Self::ValidatorSet) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
 pub fn fixed_hash() -> Hash {
     Hash::new(Algorithm::Sha256, &Sha256::digest(&[5])).unwrap()
 }
+
+// a concrete header with the given height and time, referring to the given validator hashes.
+pub fn light_header(height: u64, time: Time, vals: Hash, next_vals: Hash) -> LightHeader {
+    LightHeader {
+        version: Version { block: 10, app: 0 },
+        chain_id: chain::Id::from_str("test-chain").unwrap(),
+        height: BlockHeight(height),
+        time,
+        last_block_id: None,
+        last_commit_hash: None,
+        data_hash: None,
+        validators_hash: vals,
+        next_validators_hash: next_vals,
+        consensus_hash: fixed_hash(),
+        app_hash: vec![],
+        last_results_hash: None,
+        evidence_hash: None,
+        proposer_address: Id::new([0; 20]),
+    }
+}
+
+// ed25519 keypairs ordered by the address they derive, i.e. in the same order
+// the validators built from them end up in a `Set`.
+pub fn generate_keypairs(number_of_keypairs: usize) -> Vec<Keypair> {
+    let mut rng = rand::thread_rng();
+    let mut keypairs: Vec<Keypair> = (0..number_of_keypairs)
+        .map(|_| Keypair::generate(&mut rng))
+        .collect();
+    keypairs.sort_by_key(|kp| Id::from(kp.public));
+    keypairs
+}
+
+pub fn validator_info(keypair: &Keypair, power: u64) -> Info {
+    Info::new(PublicKey::Ed25519(keypair.public), Power::new(power))
+}
+
+// a validator set where every keypair has the same voting power.
+pub fn validator_set(keypairs: &[Keypair], power: u64) -> Set<Info> {
+    Set::new(
+        keypairs
+            .iter()
+            .map(|kp| validator_info(kp, power))
+            .collect(),
+    )
+}
+
+// a concrete commit for the header, where the validators at the given indexes
+// of the set correctly signed and all others are absent.
+pub fn signed_commit(
+    header: &LightHeader,
+    keypairs: &[Keypair],
+    vals: &Set<Info>,
+    signers: &[usize],
+) -> Commit {
+    signed_commit_at_round(header, keypairs, vals, signers, 0)
+}
+
+// same as `signed_commit`, for a block committed in the given round.
+pub fn signed_commit_at_round(
+    header: &LightHeader,
+    keypairs: &[Keypair],
+    vals: &Set<Info>,
+    signers: &[usize],
+    round: u64,
+) -> Commit {
+    test_util::sign_commit(header, keypairs, vals, signers, round)
+}
+
+// a header at the given height and time, signed by the validators at the given indexes of
+// the set, which are also its next validators.
+pub fn signed_header_at(
+    height: u64,
+    time: Time,
+    keypairs: &[Keypair],
+    vals: &Set<Info>,
+    signers: &[usize],
+) -> LightSignedHeader {
+    let header = light_header(height, time, vals.hash(), vals.hash());
+    let commit = signed_commit(&header, keypairs, vals, signers);
+    LightSignedHeader::new(commit, header)
+}
+
+// a state trusting the header at the given height and the unix epoch, signed by all of
+// the validators of the set, which are also its next validators.
+pub fn trusted_state_at(height: u64, keypairs: &[Keypair], vals: &Set<Info>) -> LightTrustedState {
+    let signers: Vec<usize> = (0..vals.number_of_validators()).collect();
+    let sh = signed_header_at(height, Time::unix_epoch(), keypairs, vals, &signers);
+    LightTrustedState::new(sh, vals.clone())
+}
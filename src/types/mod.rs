@@ -1,16 +1,24 @@
 pub(crate) mod account;
 mod amino;
 pub(crate) mod block;
-mod chain;
+pub(crate) mod chain;
 pub(crate) mod client;
 pub(crate) mod hash;
+#[cfg(feature = "hash-cache")]
+pub(crate) mod hash_cache;
 pub(crate) mod proposer_priority;
 pub(crate) mod pubkey;
 pub(crate) mod signature;
+pub(crate) mod signature_scheme;
+#[cfg(any(test, feature = "test-util"))]
+pub(crate) mod test_util;
 pub(crate) mod time;
 pub(crate) mod traits;
 pub(crate) mod trusted;
 pub(crate) mod validator;
+#[cfg(feature = "csv")]
+mod validator_csv;
+pub(crate) mod validator_response;
 pub(crate) mod vote;
 
 #[cfg(test)]
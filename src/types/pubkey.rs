@@ -1,6 +1,7 @@
 //! Public keys used in Tendermint networks
 
 use crate::errors::{Error, Kind};
+use crate::types::signature_scheme::{ED25519_KEY_TYPE, SECP256K1_KEY_TYPE};
 use anomaly::fail;
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
@@ -51,6 +52,14 @@ impl PublicKey {
         }
     }
 
+    /// Type of this key, as tendermint names it (e.g. `tendermint/PubKeyEd25519`)
+    pub fn key_type(self) -> &'static str {
+        match self {
+            PublicKey::Ed25519(_) => ED25519_KEY_TYPE,
+            PublicKey::Secp256k1(_) => SECP256K1_KEY_TYPE,
+        }
+    }
+
     /// Get Ed25519 public key
     pub fn ed25519(self) -> Option<ed25519_dalek::PublicKey> {
         match self {
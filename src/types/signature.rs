@@ -8,6 +8,11 @@ use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 pub struct Signature(Vec<u8>);
 
 impl Signature {
+    /// Create a signature from its raw bytes
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Signature(bytes)
+    }
+
     pub fn raw(&self) -> Vec<u8> {
         self.0.clone()
     }
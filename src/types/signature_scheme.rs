@@ -0,0 +1,291 @@
+//! Registry of the signature schemes validator signatures can be verified with
+
+use crate::errors::{Error, Kind};
+use crate::types::pubkey::PublicKey;
+use anomaly::fail;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use ed25519_dalek::Verifier as _;
+use sha2::{Digest, Sha512};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Key type of ed25519 keys, as tendermint names it.
+pub const ED25519_KEY_TYPE: &str = "tendermint/PubKeyEd25519";
+/// Key type of secp256k1 keys, as tendermint names it.
+pub const SECP256K1_KEY_TYPE: &str = "tendermint/PubKeySecp256k1";
+
+/// Whether `signature` is a valid signature of `sign_bytes` by the raw `public_key`.
+/// Malformed keys and signatures are invalid.
+pub type SignatureVerifier = fn(public_key: &[u8], sign_bytes: &[u8], signature: &[u8]) -> bool;
+
+/// SignatureSchemeRegistry maps key types (e.g. `ED25519_KEY_TYPE`) to the verifier of
+/// their signatures. The default registry supports ed25519 and secp256k1.
+///
+/// Commit signatures are verified through it when it is set as
+/// `VerificationOptions::signature_schemes`. The keys of a [`LightValidator`] are ed25519
+/// or secp256k1 only, so for them a registry can replace the verifier of these types;
+/// validators with keys of other types need their own `Validator` implementation,
+/// overriding `Validator::verify_signature_with`.
+///
+/// [`LightValidator`]: crate::LightValidator
+#[derive(Clone)]
+pub struct SignatureSchemeRegistry {
+    verifiers: HashMap<String, SignatureVerifier>,
+}
+
+impl SignatureSchemeRegistry {
+    /// A registry without any scheme.
+    pub fn empty() -> Self {
+        Self {
+            verifiers: HashMap::new(),
+        }
+    }
+
+    /// Verify signatures of keys of the given type with `verifier`. Returns the verifier
+    /// previously registered for the type, if any.
+    pub fn register(
+        &mut self,
+        key_type: &str,
+        verifier: SignatureVerifier,
+    ) -> Option<SignatureVerifier> {
+        self.verifiers.insert(key_type.to_string(), verifier)
+    }
+
+    /// The default schemes, with ed25519 signatures verified without any heap allocation,
+    /// for constrained verifiers such as zkVM guests: set it as
+    /// `VerificationOptions::signature_schemes`.
+    ///
+    /// The ed25519 verifier is deterministic (it uses no randomness) and accepts exactly
+    /// the signatures the default one accepts, i.e. it also requires a reduced `s`. It is
+    /// variable time, which is fine as it only handles public data. ed25519-dalek, used by default,
+    /// allocates the error of every rejected signature instead.
+    pub fn no_alloc() -> Self {
+        let mut registry = Self::default();
+        registry.register(ED25519_KEY_TYPE, verify_ed25519_no_alloc);
+        registry
+    }
+
+    /// Whether signatures of keys of the given type can be verified.
+    pub fn is_registered(&self, key_type: &str) -> bool {
+        self.verifiers.contains_key(key_type)
+    }
+
+    /// Verify a signature with the verifier registered for the key type. Fails if no
+    /// verifier is registered for it.
+    pub fn verify(
+        &self,
+        key_type: &str,
+        public_key: &[u8],
+        sign_bytes: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, Error> {
+        match self.verifiers.get(key_type) {
+            Some(verifier) => Ok(verifier(public_key, sign_bytes, signature)),
+            None => fail!(
+                Kind::InvalidKey,
+                "no signature scheme registered for key type {}",
+                key_type
+            ),
+        }
+    }
+
+    /// Same as `verify`, for a tendermint public key.
+    pub fn verify_public_key(
+        &self,
+        public_key: &PublicKey,
+        sign_bytes: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, Error> {
+        let public_key_bytes: &[u8] = match public_key {
+            PublicKey::Ed25519(pk) => pk.as_bytes(),
+            PublicKey::Secp256k1(pk) => pk.as_bytes(),
+        };
+        self.verify(
+            public_key.key_type(),
+            public_key_bytes,
+            sign_bytes,
+            signature,
+        )
+    }
+}
+
+impl Default for SignatureSchemeRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(ED25519_KEY_TYPE, verify_ed25519);
+        registry.register(SECP256K1_KEY_TYPE, verify_secp256k1);
+        registry
+    }
+}
+
+impl fmt::Debug for SignatureSchemeRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut key_types: Vec<&String> = self.verifiers.keys().collect();
+        key_types.sort();
+        f.debug_struct("SignatureSchemeRegistry")
+            .field("key_types", &key_types)
+            .finish()
+    }
+}
+
+fn verify_ed25519(public_key: &[u8], sign_bytes: &[u8], signature: &[u8]) -> bool {
+    match (
+        ed25519_dalek::PublicKey::from_bytes(public_key),
+        ed25519_dalek::Signature::try_from(signature),
+    ) {
+        (Ok(pk), Ok(sig)) => pk.verify(sign_bytes, &sig).is_ok(),
+        _ => false,
+    }
+}
+
+// Same as `verify_ed25519`, straight on curve25519-dalek: ed25519-dalek's `verify` without
+// its errors. Checks that R == [s]B - [k]A, with k = sha512(R || A || sign_bytes).
+fn verify_ed25519_no_alloc(public_key: &[u8], sign_bytes: &[u8], signature: &[u8]) -> bool {
+    if public_key.len() != 32 || signature.len() != 64 {
+        return false;
+    }
+    let mut public_key_bytes = [0; 32];
+    public_key_bytes.copy_from_slice(public_key);
+    let a = match CompressedEdwardsY(public_key_bytes).decompress() {
+        Some(a) => a,
+        None => return false,
+    };
+    let mut r_bytes = [0; 32];
+    r_bytes.copy_from_slice(&signature[..32]);
+    let mut s_bytes = [0; 32];
+    s_bytes.copy_from_slice(&signature[32..]);
+    let s = match Scalar::from_canonical_bytes(s_bytes) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let mut hasher = Sha512::new();
+    hasher.input(r_bytes);
+    hasher.input(public_key_bytes);
+    hasher.input(sign_bytes);
+    let mut digest = [0; 64];
+    digest.copy_from_slice(&hasher.result());
+    let k = Scalar::from_bytes_mod_order_wide(&digest);
+
+    let r = EdwardsPoint::vartime_double_scalar_mul_basepoint(&k, &-a, &s);
+    r.compress() == CompressedEdwardsY(r_bytes)
+}
+
+// tendermint signs the sha256 of the sign bytes, with a 64 bytes r || s signature.
+fn verify_secp256k1(public_key: &[u8], sign_bytes: &[u8], signature: &[u8]) -> bool {
+    match (
+        k256::ecdsa::VerifyingKey::from_sec1_bytes(public_key),
+        k256::ecdsa::Signature::try_from(signature),
+    ) {
+        (Ok(pk), Ok(sig)) => pk.verify(sign_bytes, &sig).is_ok(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::signature::Signer as _;
+
+    const DUMMY_KEY_TYPE: &str = "test/PubKeyDummy";
+
+    // "signs" with the public key itself
+    fn verify_dummy(public_key: &[u8], _sign_bytes: &[u8], signature: &[u8]) -> bool {
+        public_key == signature
+    }
+
+    #[test]
+    fn test_register_scheme() {
+        let mut registry = SignatureSchemeRegistry::default();
+        assert!(!registry.is_registered(DUMMY_KEY_TYPE));
+        let res = registry.verify(DUMMY_KEY_TYPE, &[1, 2], b"sign bytes", &[1, 2]);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "invalid key: no signature scheme registered for key type test/PubKeyDummy"
+        );
+
+        assert!(registry.register(DUMMY_KEY_TYPE, verify_dummy).is_none());
+        assert!(registry.is_registered(DUMMY_KEY_TYPE));
+        assert!(registry
+            .verify(DUMMY_KEY_TYPE, &[1, 2], b"sign bytes", &[1, 2])
+            .unwrap());
+        assert!(!registry
+            .verify(DUMMY_KEY_TYPE, &[1, 2], b"sign bytes", &[2, 1])
+            .unwrap());
+        assert!(!SignatureSchemeRegistry::empty().is_registered(ED25519_KEY_TYPE));
+    }
+
+    #[test]
+    fn test_default_schemes() {
+        let registry = SignatureSchemeRegistry::default();
+        let sign_bytes = b"sign bytes";
+
+        let keypair = ed25519_dalek::Keypair::generate(&mut rand::thread_rng());
+        let public_key = PublicKey::Ed25519(keypair.public);
+        let signature = ed25519_dalek::Signer::sign(&keypair, sign_bytes);
+        assert!(registry
+            .verify_public_key(&public_key, sign_bytes, signature.as_ref())
+            .unwrap());
+        assert!(!registry
+            .verify_public_key(&public_key, b"other bytes", signature.as_ref())
+            .unwrap());
+
+        let signing_key = k256::ecdsa::SigningKey::from_bytes(&[7; 32]).unwrap();
+        let public_key = PublicKey::Secp256k1(k256::EncodedPoint::from(&signing_key.verify_key()));
+        let signature: k256::ecdsa::Signature = signing_key.sign(sign_bytes);
+        assert!(registry
+            .verify_public_key(&public_key, sign_bytes, signature.as_ref())
+            .unwrap());
+        assert!(!registry
+            .verify_public_key(&public_key, b"other bytes", signature.as_ref())
+            .unwrap());
+        assert!(!registry
+            .verify_public_key(&public_key, sign_bytes, &signature.as_ref()[1..])
+            .unwrap());
+    }
+
+    #[test]
+    fn test_no_alloc_ed25519_agrees_with_dalek() {
+        let mut rng = rand::thread_rng();
+        let verifiers = SignatureSchemeRegistry::no_alloc();
+        let agree = |public_key: &[u8], sign_bytes: &[u8], signature: &[u8]| {
+            let expected = verify_ed25519(public_key, sign_bytes, signature);
+            let verified = verifiers
+                .verify(ED25519_KEY_TYPE, public_key, sign_bytes, signature)
+                .unwrap();
+            assert_eq!(verified, expected, "{:?} {:?}", public_key, signature);
+            verified
+        };
+
+        for len in 0..64 {
+            let keypair = ed25519_dalek::Keypair::generate(&mut rng);
+            let public_key = keypair.public.to_bytes();
+            let sign_bytes = vec![len as u8; len];
+            let signature = ed25519_dalek::Signer::sign(&keypair, &sign_bytes).to_bytes();
+            assert!(agree(&public_key, &sign_bytes, &signature));
+            assert!(!agree(&public_key, b"other bytes", &signature));
+            assert!(!agree(&public_key, &sign_bytes, &signature[..63]));
+            assert!(!agree(&public_key[..31], &sign_bytes, &signature));
+
+            let mut tampered = signature;
+            tampered[len] ^= 1;
+            assert!(!agree(&public_key, &sign_bytes, &tampered));
+
+            // the same s plus the group order, i.e. not reduced
+            let mut unreduced = signature;
+            let order = curve25519_dalek::constants::BASEPOINT_ORDER.to_bytes();
+            let mut carry = 0;
+            for (byte, order_byte) in unreduced[32..].iter_mut().zip(order.iter()) {
+                let sum = *byte as u16 + *order_byte as u16 + carry;
+                *byte = sum as u8;
+                carry = sum >> 8;
+            }
+            assert!(!agree(&public_key, &sign_bytes, &unreduced));
+
+            // keys which may not be points of the curve
+            agree(&[len as u8; 32], &sign_bytes, &signature);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! Helpers for the tests of downstream crates, enabled by the `test-util` feature.
+
+use crate::types::account::Id;
+use crate::types::block::commit::{Commit, CommitSigs, LightSignedHeader, SignedHeader};
+use crate::types::block::commit_sigs::CommitSig;
+use crate::types::block::header::{Header as LightHeader, Version};
+use crate::types::block::height::Height;
+use crate::types::block::id::Id as BlockId;
+use crate::types::block::traits::header::Header;
+use crate::types::chain;
+use crate::types::hash::Hash;
+use crate::types::pubkey::PublicKey;
+use crate::types::signature::Signature;
+use crate::types::time::Time;
+use crate::types::traits::validator::Validator;
+use crate::types::traits::validator_set::ValidatorSet;
+use crate::types::trusted::TrustedState;
+use crate::types::validator::{Info, Set};
+use crate::types::vote::power::Power;
+use ed25519_dalek::{Keypair, SecretKey, Signer};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+const MOCK_CHAIN_ID: &str = "mock-chain";
+const MOCK_VALIDATORS: u8 = 4;
+const MOCK_POWER: u64 = 10;
+
+impl TrustedState<Commit, LightHeader, Info> {
+    /// A self-consistent trusted state at the given height of chain "mock-chain",
+    /// signed by all of a fixed set of 4 validators with 10 voting power each,
+    /// which is also the next validator set. The header at height h has time
+    /// `unix_epoch + h seconds`. The keys are deterministic and **not secret**.
+    pub fn mock(height: u64) -> Self {
+        let keypairs = mock_keypairs();
+        let vals = mock_validator_set(&keypairs);
+        let time = Time::unix_epoch() + Duration::from_secs(height);
+        Self::new(mock_signed_header(height, time, &keypairs, &vals), vals)
+    }
+
+    /// The signed header at the next height of a state created with `mock`, one second
+    /// later and signed by the same validators, so that it passes `verify_single`
+    /// against this state (with the state's validators as both vals and next vals).
+    ///
+    /// Panics if the state's validators aren't the mock validators.
+    pub fn mock_next(&self) -> LightSignedHeader {
+        let keypairs = mock_keypairs();
+        let vals = mock_validator_set(&keypairs);
+        assert!(
+            self.validators().hash_eq(&vals),
+            "mock_next requires a state created with TrustedState::mock"
+        );
+        let header = self.last_header().header();
+        let time = header.time + Duration::from_secs(1);
+        mock_signed_header(header.height.value() + 1, time, &keypairs, &vals)
+    }
+}
+
+// the keypairs of the mock validators, ordered by address like in their `Set`.
+fn mock_keypairs() -> Vec<Keypair> {
+    let mut keypairs: Vec<Keypair> = (1..=MOCK_VALIDATORS)
+        .map(|seed| {
+            let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+            let public = (&secret).into();
+            Keypair { secret, public }
+        })
+        .collect();
+    keypairs.sort_by_key(|kp| Id::from(kp.public));
+    keypairs
+}
+
+fn mock_validator_set(keypairs: &[Keypair]) -> Set<Info> {
+    Set::new(
+        keypairs
+            .iter()
+            .map(|kp| Info::new(PublicKey::Ed25519(kp.public), Power::new(MOCK_POWER)))
+            .collect(),
+    )
+}
+
+fn mock_signed_header(
+    height: u64,
+    time: Time,
+    keypairs: &[Keypair],
+    vals: &Set<Info>,
+) -> LightSignedHeader {
+    let header = LightHeader {
+        version: Version { block: 10, app: 0 },
+        chain_id: chain::Id::from_str(MOCK_CHAIN_ID).unwrap(),
+        height: Height(height),
+        time,
+        last_block_id: None,
+        last_commit_hash: None,
+        data_hash: None,
+        validators_hash: vals.hash(),
+        next_validators_hash: vals.hash(),
+        consensus_hash: Hash::Sha256([0; 32]),
+        app_hash: vec![],
+        last_results_hash: None,
+        evidence_hash: None,
+        proposer_address: Id::new([0; 20]),
+    };
+    let signers: Vec<usize> = (0..keypairs.len()).collect();
+    let commit = sign_commit(&header, keypairs, vals, &signers, 0);
+    SignedHeader::new(commit, header)
+}
+
+// a commit for the header in the given round, where the validators at the given indexes
+// of the set correctly signed and all others are absent.
+pub(crate) fn sign_commit(
+    header: &LightHeader,
+    keypairs: &[Keypair],
+    vals: &Set<Info>,
+    signers: &[usize],
+    round: u64,
+) -> Commit {
+    let keypairs_by_address: HashMap<Id, &Keypair> = keypairs
+        .iter()
+        .map(|kp| (Id::from(kp.public), kp))
+        .collect();
+
+    let placeholder_sigs: Vec<CommitSig> = vals
+        .validators()
+        .iter()
+        .enumerate()
+        .map(|(i, val)| {
+            if signers.contains(&i) {
+                CommitSig::BlockIDFlagCommit {
+                    validator_address: val.address(),
+                    timestamp: header.time,
+                    signature: Signature::new(vec![]),
+                }
+            } else {
+                CommitSig::BlockIDFlagAbsent
+            }
+        })
+        .collect();
+    let mut commit = Commit {
+        height: header.height,
+        round,
+        block_id: BlockId::new(header.hash(), None),
+        signatures: CommitSigs::new(placeholder_sigs),
+    };
+
+    // sign bytes don't depend on the signatures, so we can fill them in afterwards.
+    let signatures: HashMap<Id, Signature> = commit
+        .signed_votes(header.chain_id)
+        .into_iter()
+        .map(|vote| {
+            let vote = vote.unwrap();
+            let keypair = keypairs_by_address[&vote.validator_id()];
+            let signature = keypair.sign(&vote.sign_bytes()).to_bytes().to_vec();
+            (vote.validator_id(), Signature::new(signature))
+        })
+        .collect();
+    commit.signatures = CommitSigs::new(
+        commit
+            .signatures
+            .iter()
+            .map(|commit_sig| match commit_sig {
+                CommitSig::BlockIDFlagCommit {
+                    validator_address,
+                    timestamp,
+                    ..
+                } => CommitSig::BlockIDFlagCommit {
+                    validator_address: *validator_address,
+                    timestamp: *timestamp,
+                    signature: signatures[validator_address].clone(),
+                },
+                other => other.clone(),
+            })
+            .collect::<Vec<CommitSig>>(),
+    );
+    commit
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::traits::validator_set::ValidatorSet;
+    use crate::{verify_single, TrustThresholdFraction, TrustedState};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_mock_trusted_state() {
+        let state = TrustedState::mock(5);
+        assert!(state.validate_self().is_ok());
+
+        let next = state.mock_next();
+        let vals = state.validators().clone();
+        let new_state = verify_single(
+            state,
+            &next,
+            &vals,
+            &vals,
+            TrustThresholdFraction::default(),
+            Duration::from_secs(3600),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(60),
+        )
+        .unwrap();
+        assert_eq!(new_state.last_header().header().height.value(), 6);
+
+        // the mock keys are deterministic
+        assert!(TrustedState::mock(1).validators().hash_eq(&vals));
+    }
+}
@@ -1,6 +1,6 @@
 use crate::errors::{Error, Kind};
 use anomaly::BoxError;
-use chrono::{DateTime, SecondsFormat, Utc};
+use chrono::{DateTime, SecondsFormat, TimeZone, Timelike, Utc};
 use std::fmt;
 use std::ops::{Add, Sub};
 use std::str::FromStr;
@@ -8,7 +8,14 @@ use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Tendermint timestamps
 /// <https://github.com/tendermint/tendermint/blob/master/docs/spec/blockchain/blockchain.md#time>
+///
+/// Tendermint (like protobuf timestamps) has no leap seconds, while chrono represents
+/// them with more than a second of nanoseconds (e.g. `23:59:60.5`). Leap seconds are
+/// folded into the next second (`00:00:00.5`) whenever a `Time` is created, which is
+/// what durations, `SystemTime`s and encodings of the time already amount to: otherwise
+/// the order of two times could disagree with the duration between them.
 #[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[serde(from = "DateTime<Utc>")]
 pub struct Time(DateTime<Utc>);
 
 impl Time {
@@ -22,6 +29,24 @@ impl Time {
         UNIX_EPOCH.into()
     }
 
+    /// Create a timestamp from seconds and nanoseconds since the unix epoch,
+    /// as used by `TimeMsg`. Returns an error if the value can't be represented.
+    pub fn from_unix(seconds: i64, nanos: u32) -> Result<Self, Error> {
+        if nanos >= 1_000_000_000 {
+            return Err(Kind::OutOfRange.into());
+        }
+        Utc.timestamp_opt(seconds, nanos)
+            .single()
+            .map(Time)
+            .ok_or_else(|| Kind::OutOfRange.into())
+    }
+
+    /// Whole seconds since the unix epoch, negative for times before it.
+    /// Sub-second precision is dropped, rounding towards the past (-0.5s is -1).
+    pub fn unix_seconds(&self) -> i64 {
+        self.0.timestamp()
+    }
+
     /// Calculate the amount of time which has passed since another `Timestamp`
     /// as a `std::time::Duration`
     pub fn duration_since(&self, other: Time) -> Result<Duration, BoxError> {
@@ -33,7 +58,7 @@ impl Time {
 
     /// Parse a timestamp from an RFC 3339 date
     pub fn parse_from_rfc3339(s: &str) -> Result<Time, BoxError> {
-        Ok(Time(DateTime::parse_from_rfc3339(s)?.with_timezone(&Utc)))
+        Ok(DateTime::parse_from_rfc3339(s)?.with_timezone(&Utc).into())
     }
 
     /// Return an RFC 3339 and ISO 8601 date and time string with 6 subseconds digits and Z.
@@ -64,8 +89,18 @@ impl FromStr for Time {
 
 impl From<DateTime<Utc>> for Time {
     fn from(t: DateTime<Utc>) -> Time {
-        Time(t)
+        Time(fold_leap_second(t))
+    }
+}
+
+// Move a leap second (nanoseconds past 1e9) to the same fraction of the next second.
+fn fold_leap_second(t: DateTime<Utc>) -> DateTime<Utc> {
+    if t.timestamp_subsec_nanos() < 1_000_000_000 {
+        return t;
     }
+    t.with_nanosecond(t.timestamp_subsec_nanos() - 1_000_000_000)
+        .expect("nanoseconds within a second are valid")
+        + chrono::Duration::seconds(1)
 }
 
 impl From<Time> for DateTime<Utc> {
@@ -109,3 +144,84 @@ pub trait ParseTimestamp {
     /// Parse `Timestamp`, or return an `Error` if parsing failed
     fn parse_timestamp(&self) -> Result<Time, Error>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_unix() {
+        assert_eq!(Time::from_unix(0, 0).unwrap(), Time::unix_epoch());
+        assert_eq!(
+            Time::from_unix(1_600_000_000, 500).unwrap(),
+            Time::parse_from_rfc3339("2020-09-13T12:26:40.0000005Z").unwrap()
+        );
+        assert_eq!(
+            Time::from_unix(-1, 0).unwrap(),
+            Time::parse_from_rfc3339("1969-12-31T23:59:59Z").unwrap()
+        );
+
+        // out of the range chrono can represent
+        assert!(Time::from_unix(i64::MAX, 0).is_err());
+        assert!(Time::from_unix(i64::MIN, 0).is_err());
+        // nanos must be less than a second
+        assert!(Time::from_unix(0, 1_000_000_000).is_err());
+    }
+
+    #[test]
+    fn test_try_from_time_msg() {
+        use crate::types::amino::TimeMsg;
+        use std::convert::TryFrom;
+
+        let time = Time::parse_from_rfc3339("2020-09-13T12:26:40.0000005Z").unwrap();
+        assert_eq!(Time::try_from(TimeMsg::from(time)).unwrap(), time);
+
+        let out_of_range = TimeMsg {
+            seconds: i64::MAX,
+            nanos: 0,
+        };
+        let err = Time::try_from(out_of_range).unwrap_err();
+        assert!(matches!(err.kind(), Kind::OutOfRange));
+        let negative_nanos = TimeMsg {
+            seconds: 0,
+            nanos: -1,
+        };
+        assert!(Time::try_from(negative_nanos).is_err());
+    }
+
+    #[test]
+    fn test_leap_second() {
+        use crate::types::amino::TimeMsg;
+        use std::convert::TryFrom;
+
+        let before = Time::parse_from_rfc3339("2016-12-31T23:59:59.9Z").unwrap();
+        let leap = Time::parse_from_rfc3339("2016-12-31T23:59:60.5Z").unwrap();
+        let after = Time::parse_from_rfc3339("2017-01-01T00:00:00.1Z").unwrap();
+        assert_eq!(
+            leap,
+            Time::parse_from_rfc3339("2017-01-01T00:00:00.5Z").unwrap()
+        );
+        let leap_json: Time = serde_json::from_str("\"2016-12-31T23:59:60.5Z\"").unwrap();
+        assert_eq!(leap_json, leap);
+
+        // order and durations agree around the leap second
+        assert!(before < after && after < leap);
+        assert_eq!(
+            leap.duration_since(before).unwrap(),
+            Duration::from_millis(600)
+        );
+        assert_eq!(
+            leap.duration_since(after).unwrap(),
+            Duration::from_millis(400)
+        );
+        assert!(after.duration_since(leap).is_err());
+        let leap_system_time: SystemTime = leap.into();
+        assert_eq!(Time::from(leap_system_time), leap);
+
+        // and so do encodings
+        let msg = TimeMsg::from(leap);
+        assert_eq!((msg.seconds, msg.nanos), (1_483_228_800, 500_000_000));
+        assert_eq!(Time::try_from(msg).unwrap(), leap);
+        assert_eq!(leap.to_rfc3339(), "2017-01-01T00:00:00.500000000Z");
+    }
+}
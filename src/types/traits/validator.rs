@@ -1,5 +1,7 @@
+use crate::errors::Error;
 use crate::types::account;
 use crate::types::proposer_priority::ProposerPriority;
+use crate::types::signature_scheme::SignatureSchemeRegistry;
 use crate::types::vote::power::Power as VotePower;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -10,6 +12,19 @@ pub trait Validator: Clone + Debug + Serialize + DeserializeOwned {
 
     fn verify_signature(&self, sign_bytes: &[u8], signature: &[u8]) -> bool;
 
+    /// Same as `verify_signature`, with the verifier `schemes` registered for the type of
+    /// the validator's key. The default implementation ignores the registry; implementers
+    /// whose keys have a type should override it.
+    fn verify_signature_with(
+        &self,
+        schemes: &SignatureSchemeRegistry,
+        sign_bytes: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, Error> {
+        let _ = schemes;
+        Ok(self.verify_signature(sign_bytes, signature))
+    }
+
     fn address(&self) -> account::Id;
 
     fn vote_power(&self) -> VotePower;
@@ -17,4 +32,10 @@ pub trait Validator: Clone + Debug + Serialize + DeserializeOwned {
     fn proposer_priority(&self) -> Option<ProposerPriority>;
 
     fn hash_bytes(&self) -> Vec<u8>;
+
+    /// Whether `hash_bytes` can encode this validator's key type. If not, the hash of
+    /// a set including this validator won't match the one tendermint computed.
+    fn has_hashable_key(&self) -> bool {
+        true
+    }
 }
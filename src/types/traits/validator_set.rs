@@ -22,4 +22,13 @@ where
     fn intersect(&self, validator_set: &Self) -> Self;
 
     fn number_of_validators(&self) -> usize;
+
+    /// Validators of the set, in the order their signatures appear in a commit.
+    fn validators(&self) -> &[V];
+
+    /// Whether both sets have the same hash, i.e. are the same set as far as
+    /// headers are concerned. Handy to cross-check sets from different providers.
+    fn hash_eq(&self, other: &Self) -> bool {
+        self.hash() == other.hash()
+    }
 }
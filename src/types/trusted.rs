@@ -1,10 +1,24 @@
 use crate::errors::{Error, Kind};
-use crate::types::block::commit::SignedHeader;
-use crate::types::block::traits::{commit::ProvableCommit, header::Header};
+use crate::merkle_tree::simple_hash_from_byte_vectors;
+use crate::types::account;
+use crate::types::block::commit::{Commit, CommitSigs, SignedHeader};
+use crate::types::block::header::{Header as LightHeader, Version};
+use crate::types::block::height::Height;
+use crate::types::block::id::Id as BlockId;
+use crate::types::block::traits::commit::ProvableCommit;
+use crate::types::block::traits::header::{Header, Height as HeightT};
+use crate::types::chain;
+use crate::types::hash::Hash;
+use crate::types::time::Time;
 use crate::types::traits::trusted::TrustThreshold;
 use crate::types::traits::validator::Validator;
-use serde::de::Deserialize;
+use crate::types::traits::validator_set::ValidatorSet;
+use crate::types::validator::{Info, Set};
+use serde::de::{Deserialize, Deserializer};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
 use std::fmt::Debug;
+use std::time::{Duration, SystemTime};
 
 /// TrustThresholdFraction defines what fraction of the total voting power of a known
 /// and trusted validator set is sufficient for a commit to be
@@ -13,7 +27,11 @@ use std::fmt::Debug;
 /// voting power signed (in other words at least one honest validator signed).
 /// Some clients might require more than +1/3 and can implement their own
 /// [`TrustThreshold`] which can be passed into all relevant methods.
+///
+/// Besides `{"numerator": "2", "denominator": "3"}`, it deserializes from a bare number
+/// such as `0.667`, see [`TrustThresholdFraction::from_float`].
 #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "TrustThresholdFractionRepr")]
 pub struct TrustThresholdFraction {
     #[serde(with = "crate::serialization::from_str")]
     pub numerator: u64,
@@ -21,6 +39,37 @@ pub struct TrustThresholdFraction {
     pub denominator: u64,
 }
 
+/// Denominator of the thresholds created by [`TrustThresholdFraction::from_float`],
+/// before reducing the fraction.
+pub const TRUST_THRESHOLD_FLOAT_DENOMINATOR: u64 = 1000;
+
+// The forms a TrustThresholdFraction deserializes from.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TrustThresholdFractionRepr {
+    Fraction {
+        #[serde(deserialize_with = "crate::serialization::from_str::deserialize")]
+        numerator: u64,
+        #[serde(deserialize_with = "crate::serialization::from_str::deserialize")]
+        denominator: u64,
+    },
+    Float(f64),
+}
+
+impl TryFrom<TrustThresholdFractionRepr> for TrustThresholdFraction {
+    type Error = Error;
+
+    fn try_from(repr: TrustThresholdFractionRepr) -> Result<Self, Error> {
+        match repr {
+            TrustThresholdFractionRepr::Fraction {
+                numerator,
+                denominator,
+            } => Self::new(numerator, denominator),
+            TrustThresholdFractionRepr::Float(threshold) => Self::from_float(threshold),
+        }
+    }
+}
+
 impl TrustThresholdFraction {
     /// Instantiate a TrustThresholdFraction if the given denominator and
     /// numerator are valid.
@@ -39,6 +88,61 @@ impl TrustThresholdFraction {
         }
         .into())
     }
+
+    /// The strictest threshold, 1/1: all the validators have to sign.
+    pub fn all() -> Self {
+        Self {
+            numerator: 1,
+            denominator: 1,
+        }
+    }
+
+    /// Instantiate a TrustThresholdFraction from a percentage, e.g. 67 is 67/100.
+    ///
+    /// The same bounds as in [`TrustThresholdFraction::new`] apply, so the percentage
+    /// has to be between 34 and 100.
+    pub fn from_percent(percent: u8) -> Result<Self, Error> {
+        Self::new(u64::from(percent), 100)
+    }
+
+    /// Instantiate a TrustThresholdFraction from a float, e.g. as stored by configuration
+    /// systems: it is rounded to the nearest thousandth, so 0.667 is 667/1000 and 0.5 is
+    /// 1/2 (fractions are reduced).
+    ///
+    /// The same bounds as in [`TrustThresholdFraction::new`] apply to the rounded
+    /// fraction, so the float has to be between 0.3335 and 1. Note that 1/3 itself can't
+    /// be expressed as a float.
+    pub fn from_float(threshold: f64) -> Result<Self, Error> {
+        let invalid = || Kind::InvalidTrustThreshold {
+            got: threshold.to_string(),
+        };
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(invalid().into());
+        }
+        let numerator = (threshold * TRUST_THRESHOLD_FLOAT_DENOMINATOR as f64).round() as u64;
+        let divisor = gcd(numerator, TRUST_THRESHOLD_FLOAT_DENOMINATOR);
+        Self::new(
+            numerator / divisor,
+            TRUST_THRESHOLD_FLOAT_DENOMINATOR / divisor,
+        )
+        .map_err(|_| invalid().into())
+    }
+
+    /// Whether this threshold requires at least as large a fraction of the voting power
+    /// as `other`, e.g. to check a witness is held to the same standard as the primary.
+    pub fn is_at_least_as_strict_as(&self, other: &Self) -> bool {
+        // a/b >= c/d, without overflowing or losing precision
+        u128::from(self.numerator) * u128::from(other.denominator)
+            >= u128::from(other.numerator) * u128::from(self.denominator)
+    }
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
 }
 
 // TODO: should this go in the central place all impls live instead? (currently lite_impl)
@@ -48,7 +152,14 @@ impl TrustThreshold for TrustThresholdFraction {
     }
 
     fn minimum_power_to_be_trusted(&self, total_voting_power: u64) -> u64 {
-        return ((total_voting_power * self.numerator) / self.denominator) + 1;
+        let more_than_fraction = u128::from(total_voting_power) * u128::from(self.numerator)
+            / u128::from(self.denominator)
+            + 1;
+        // no commit can have more than all of the power: 1/1 means all the validators
+        // (and an empty set can't be trusted)
+        more_than_fraction
+            .min(u128::from(total_voting_power))
+            .max(1) as u64
     }
 }
 
@@ -59,6 +170,21 @@ impl Default for TrustThresholdFraction {
     }
 }
 
+/// Relationship of a target height to the height of a trusted state,
+/// see [`TrustedState::relationship_to`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Relationship {
+    /// The target is the trusted header itself.
+    Same,
+    /// The target directly follows the trusted header and can be verified sequentially.
+    Adjacent,
+    /// The target is further ahead, by the given number of heights (at least 2),
+    /// and has to be verified by skipping.
+    Skip(u64),
+    /// The target is before the trusted header, it can't be verified from this state.
+    Backward,
+}
+
 /// TrustedState contains a state trusted by a lite client,
 /// including the last header (at height h-1) and the validator set
 /// (at height h) to use to verify the next header.
@@ -78,6 +204,9 @@ where
     validators: C::ValidatorSet,     // height H
 }
 
+/// Trusted state of the concrete header, commit and validator types.
+pub type LightTrustedState = TrustedState<Commit, LightHeader, Info>;
+
 impl<C, H, V> TrustedState<C, H, V>
 where
     H: Header,
@@ -101,12 +230,400 @@ where
     pub fn validators(&self) -> &C::ValidatorSet {
         &self.validators
     }
+
+    /// How a header at `target_height` relates to the last trusted header, i.e. whether
+    /// it can be verified sequentially, requires skipping or can't be verified at all.
+    pub fn relationship_to(&self, target_height: HeightT) -> Relationship {
+        let trusted_height = self.last_header.header().height();
+        match target_height.cmp(&trusted_height) {
+            Ordering::Less => Relationship::Backward,
+            Ordering::Equal => Relationship::Same,
+            Ordering::Greater if target_height - trusted_height == 1 => Relationship::Adjacent,
+            Ordering::Greater => Relationship::Skip(target_height - trusted_height),
+        }
+    }
+
+    /// Time elapsed between the last trusted header and `now`, e.g. to pick the freshest
+    /// of several trusted states. Fails if the header is from after `now`.
+    pub fn age(&self, now: SystemTime) -> Result<Duration, Error> {
+        let header_time: SystemTime = self.last_header.header().bft_time().into();
+        now.duration_since(header_time)
+            .map_err(|_| Kind::TrustedHeaderFromFuture { header_time, now }.into())
+    }
+
+    /// Check that the validator set of this state is the one announced by its last header,
+    /// i.e. that it hashes to the header's `next_validators_hash` (see [`TrustedState::new`]).
+    /// Use this before relying on a state loaded from an untrusted medium, e.g. the disk.
+    pub fn validate_self(&self) -> Result<(), Error> {
+        let header_next_val_hash = self.last_header.header().next_validators_hash();
+        let expected_next_val_hash = self.validators.hash();
+        if header_next_val_hash != expected_next_val_hash {
+            return Err(Kind::InvalidNextValidatorSet {
+                header_next_val_hash,
+                expected_next_val_hash,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Compact anchor of this state, e.g. for a contract to pin: the merkle root of the
+    /// height (big-endian), chain id, block hash and validators hash of the state, so that
+    /// clients trusting the same state produce the same checkpoint hash. The block hash is
+    /// the one certified by the commit, which is also meaningful for initialized states.
+    pub fn checkpoint_hash(&self) -> Hash {
+        let header = self.last_header.header();
+        let leaves = vec![
+            header.height().to_be_bytes().to_vec(),
+            header.chain_id().as_bytes().to_vec(),
+            self.last_header.commit().header_hash().as_bytes().to_vec(),
+            self.validators.hash().as_bytes().to_vec(),
+        ];
+        Hash::Sha256(simple_hash_from_byte_vectors(leaves))
+    }
+
+    /// Replace the validator set of this state while keeping the last header.
+    ///
+    /// **DANGER:** this bypasses verification entirely. The new validator set is
+    /// trusted as is, it is *not* checked against the `next_validators_hash` of the
+    /// last header or against anything else. It only exists so that an operator can
+    /// correct a trusted state by hand in rare recovery scenarios; never feed it with
+    /// data from an untrusted source.
+    #[cfg(feature = "manual-recovery")]
+    pub fn with_validators(self, validators: C::ValidatorSet) -> Self {
+        Self {
+            last_header: self.last_header,
+            validators,
+        }
+    }
+}
+
+impl<V> TrustedState<Commit, LightHeader, V>
+where
+    V: Validator,
+{
+    /// Subjective initialization: start trusting the chain at the block with the given
+    /// height, hash and time, obtained out of band from a source the operator trusts
+    /// (e.g. a block explorer or a validator they know). `validators` is the set which
+    /// signs the *next* block, i.e. the one announced by the block's `next_validators_hash`.
+    ///
+    /// **DANGER:** by design nothing is verified here, the light client is only as
+    /// trustworthy as the values passed in. The header of the returned state is a
+    /// placeholder: only its chain id, height, time and `next_validators_hash` are
+    /// meaningful, the remaining hashes are zeroed and it does not hash to `block_hash`,
+    /// which is recorded as the block id of its (signature-less) commit instead.
+    pub fn initialize(
+        chain_id: chain::Id,
+        height: Height,
+        block_hash: Hash,
+        time: Time,
+        validators: Set<V>,
+    ) -> Self {
+        let zero_hash = Hash::Sha256([0; 32]);
+        let header = LightHeader {
+            version: Version { block: 0, app: 0 },
+            chain_id,
+            height,
+            time,
+            last_block_id: None,
+            last_commit_hash: None,
+            data_hash: None,
+            validators_hash: zero_hash,
+            next_validators_hash: validators.hash(),
+            consensus_hash: zero_hash,
+            app_hash: vec![],
+            last_results_hash: None,
+            evidence_hash: None,
+            proposer_address: account::Id::new([0; 20]),
+        };
+        let commit = Commit {
+            height,
+            round: 0,
+            block_id: BlockId {
+                hash: block_hash,
+                part_set_header: None,
+            },
+            signatures: CommitSigs::default(),
+        };
+        Self::new(SignedHeader::new(commit, header), validators)
+    }
+}
+
+/// A TrustedState along with the id of its chain, for stores persisting the
+/// trusted states of several chains side by side.
+/// The chain id is serialized at the top level, so it can be read with
+/// [`PersistedTrustedState::peek_chain_id`] without deserializing the state.
+/// Deserializing fails if it isn't the chain id of the state's last header.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(
+    bound(deserialize = "C::ValidatorSet: Deserialize<'de>"),
+    try_from = "PersistedTrustedStateRepr<C, H, V>"
+)]
+pub struct PersistedTrustedState<C, H, V>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    chain_id: chain::Id,
+    state: TrustedState<C, H, V>,
+}
+
+// The form a PersistedTrustedState deserializes from, before checking its chain id.
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "C::ValidatorSet: Deserialize<'de>"))]
+struct PersistedTrustedStateRepr<C, H, V>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    chain_id: chain::Id,
+    state: TrustedState<C, H, V>,
+}
+
+impl<C, H, V> TryFrom<PersistedTrustedStateRepr<C, H, V>> for PersistedTrustedState<C, H, V>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    type Error = Error;
+
+    fn try_from(repr: PersistedTrustedStateRepr<C, H, V>) -> Result<Self, Error> {
+        let expected = repr.state.last_header().header().chain_id();
+        if repr.chain_id != expected {
+            return Err(Kind::ChainIdMismatch {
+                got: repr.chain_id,
+                expected,
+            }
+            .into());
+        }
+        Ok(Self::new(repr.state))
+    }
+}
+
+impl<C, H, V> PersistedTrustedState<C, H, V>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    /// Wrap the given state, taking the chain id from its last header.
+    pub fn new(state: TrustedState<C, H, V>) -> Self {
+        Self {
+            chain_id: state.last_header().header().chain_id(),
+            state,
+        }
+    }
+
+    pub fn chain_id(&self) -> chain::Id {
+        self.chain_id
+    }
+
+    pub fn state(&self) -> &TrustedState<C, H, V> {
+        &self.state
+    }
+
+    pub fn into_state(self) -> TrustedState<C, H, V> {
+        self.state
+    }
+
+    /// Read only the chain id of a serialized PersistedTrustedState.
+    /// The state itself is skipped over without being deserialized.
+    pub fn peek_chain_id<'de, D>(deserializer: D) -> Result<chain::Id, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct ChainIdOnly {
+            chain_id: chain::Id,
+        }
+
+        ChainIdOnly::deserialize(deserializer).map(|persisted| persisted.chain_id)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::errors::Kind;
+    use crate::types::block::traits::header::Header;
+    use crate::types::mocks::{MockCommit, MockHeader, MockSignedHeader, MockValSet};
     use crate::types::traits::trusted::TrustThreshold;
-    use crate::TrustThresholdFraction;
+    use crate::types::traits::validator_set::ValidatorSet;
+    use crate::types::trusted::{PersistedTrustedState, Relationship};
+    use crate::{TrustThresholdFraction, TrustedState};
+    use std::time::{Duration, SystemTime};
+
+    type MockState = TrustedState<MockCommit<usize>, MockHeader, usize>;
+
+    // a state trusting the header at the given height and time, whose validators and
+    // next validators are both `vals`
+    fn mock_state(height: u64, time: SystemTime, vals: &MockValSet<usize>) -> MockState {
+        let header = MockHeader::new(height, time, vals.hash(), vals.hash());
+        let commit = MockCommit::new(header.hash(), vec![0, 1]);
+        TrustedState::new(MockSignedHeader::new(commit, header), vals.clone())
+    }
+
+    #[cfg(feature = "manual-recovery")]
+    #[test]
+    fn test_with_validators() {
+        let state = mock_state(1, SystemTime::UNIX_EPOCH, &MockValSet::new(vec![0, 1]));
+
+        let corrected_vals = MockValSet::new(vec![0, 1, 2]);
+        let corrected_state = state.clone().with_validators(corrected_vals.clone());
+        assert_eq!(corrected_state.last_header(), state.last_header());
+        assert_eq!(corrected_state.validators(), &corrected_vals);
+    }
+
+    #[test]
+    fn test_checkpoint_hash() {
+        let state = |height, vals| mock_state(height, SystemTime::UNIX_EPOCH, vals);
+        let vals = MockValSet::new(vec![0, 1]);
+        let other_vals = MockValSet::new(vec![1, 2]);
+
+        // two clients at the same state
+        assert_eq!(
+            state(1, &vals).checkpoint_hash(),
+            state(1, &vals).checkpoint_hash()
+        );
+
+        assert_ne!(
+            state(1, &vals).checkpoint_hash(),
+            state(2, &vals).checkpoint_hash()
+        );
+        assert_ne!(
+            state(1, &vals).checkpoint_hash(),
+            state(1, &other_vals).checkpoint_hash()
+        );
+    }
+
+    #[test]
+    fn test_validate_self() {
+        let vals = MockValSet::new(vec![0, 1]);
+        let next_vals = MockValSet::new(vec![1, 2]);
+        let header = MockHeader::new(1, SystemTime::UNIX_EPOCH, vals.hash(), next_vals.hash());
+        let commit = MockCommit::new(header.hash(), vec![0, 1]);
+        let signed_header = MockSignedHeader::new(commit, header);
+
+        let state: MockState = TrustedState::new(signed_header.clone(), next_vals.clone());
+        assert!(state.validate_self().is_ok());
+
+        // corrupted: the set is the one of the header's height, not of the next one
+        let corrupted: MockState = TrustedState::new(signed_header, vals.clone());
+        let res = corrupted.validate_self();
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            format!(
+                "header's next validator hash does not match next_val_hash ({:?}!={:?})",
+                next_vals.hash(),
+                vals.hash()
+            )
+        );
+    }
+
+    #[test]
+    fn test_initialize() {
+        use crate::types::block::height::Height;
+        use crate::types::block::id::Id as BlockId;
+        use crate::types::hash::{Algorithm, Hash};
+        use crate::types::mocks::{
+            fixed_hash, generate_keypairs, light_header, signed_commit, validator_set,
+        };
+        use crate::{verify_single, LightSignedHeader, Time};
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let block_hash = fixed_hash();
+        let header_after = |last_block_hash| {
+            let mut header = light_header(
+                2,
+                Time::unix_epoch() + Duration::new(10, 0),
+                vals.hash(),
+                vals.hash(),
+            );
+            header.last_block_id = Some(BlockId {
+                hash: last_block_hash,
+                part_set_header: None,
+            });
+            let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2, 3]);
+            LightSignedHeader::new(commit, header)
+        };
+        let next_header = header_after(block_hash).header().clone();
+
+        let state = TrustedState::initialize(
+            next_header.chain_id,
+            Height(1),
+            block_hash,
+            Time::unix_epoch(),
+            vals.clone(),
+        );
+        assert_eq!(state.last_header().header().height(), 1);
+        assert_eq!(state.last_header().commit().block_id.hash, block_hash);
+        assert_eq!(state.validators(), &vals);
+        assert!(state.validate_self().is_ok());
+
+        let verify = |sh: &LightSignedHeader| {
+            verify_single(
+                state.clone(),
+                sh,
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+            )
+        };
+
+        // a next header following another block is rejected
+        let other_hash = Hash::new(Algorithm::Sha256, &[6; 32]).unwrap();
+        let err = verify(&header_after(other_hash)).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            Kind::InvalidLastBlockId {
+                header_last_block_hash,
+                trusted_block_hash,
+            } if *header_last_block_hash == other_hash && *trusted_block_hash == block_hash
+        ));
+
+        // the next header following the trusted block can be verified against it
+        let sh = header_after(block_hash);
+        let new_state = verify(&sh).unwrap();
+        assert_eq!(new_state.last_header(), &sh);
+    }
+
+    #[test]
+    fn test_relationship_to() {
+        let state = mock_state(10, SystemTime::UNIX_EPOCH, &MockValSet::new(vec![0, 1]));
+
+        assert_eq!(state.relationship_to(0), Relationship::Backward);
+        assert_eq!(state.relationship_to(9), Relationship::Backward);
+        assert_eq!(state.relationship_to(10), Relationship::Same);
+        assert_eq!(state.relationship_to(11), Relationship::Adjacent);
+        assert_eq!(state.relationship_to(12), Relationship::Skip(2));
+        assert_eq!(
+            state.relationship_to(u64::MAX),
+            Relationship::Skip(u64::MAX - 10)
+        );
+    }
+
+    #[test]
+    fn test_age() {
+        let header_time = SystemTime::UNIX_EPOCH + Duration::new(100, 0);
+        let state = mock_state(10, header_time, &MockValSet::new(vec![0, 1]));
+
+        assert_eq!(state.age(header_time).unwrap(), Duration::new(0, 0));
+        assert_eq!(
+            state.age(header_time + Duration::new(42, 5)).unwrap(),
+            Duration::new(42, 5)
+        );
+        let res = state.age(header_time - Duration::new(1, 0));
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::TrustedHeaderFromFuture { .. }
+        ));
+    }
 
     #[test]
     fn test_threshold_fraction() {
@@ -137,13 +654,150 @@ mod tests {
         assert!(!threshold_fraction.is_enough_power(2, 3));
         assert!(threshold_fraction.is_enough_power(3, 3));
 
-        // All+ need to sign
+        // All need to sign
         threshold_fraction.numerator = 3;
         threshold_fraction.denominator = 3;
         assert!(!threshold_fraction.is_enough_power(0, 3));
         assert!(!threshold_fraction.is_enough_power(1, 3));
         assert!(!threshold_fraction.is_enough_power(2, 3));
-        assert!(!threshold_fraction.is_enough_power(3, 3));
+        assert!(threshold_fraction.is_enough_power(3, 3));
         assert!(threshold_fraction.is_enough_power(4, 3));
     }
+
+    #[test]
+    fn test_threshold_from_float() {
+        let threshold: TrustThresholdFraction = serde_json::from_str("0.667").unwrap();
+        assert_eq!(threshold, TrustThresholdFraction::new(667, 1000).unwrap());
+        assert!(!threshold.is_enough_power(667, 1000));
+        assert!(threshold.is_enough_power(668, 1000));
+
+        assert_eq!(
+            TrustThresholdFraction::from_float(0.5).unwrap(),
+            TrustThresholdFraction::new(1, 2).unwrap()
+        );
+        assert_eq!(
+            TrustThresholdFraction::from_float(1.0).unwrap(),
+            TrustThresholdFraction::all()
+        );
+        for invalid in &[0.333, 0.2, 1.5, -0.5, f64::NAN] {
+            assert!(TrustThresholdFraction::from_float(*invalid).is_err());
+        }
+        let res = serde_json::from_str::<TrustThresholdFraction>("0.2");
+        assert!(res.err().unwrap().to_string().contains("got: 0.2"));
+
+        // the struct form is unchanged
+        let threshold: TrustThresholdFraction =
+            serde_json::from_str(r#"{"numerator":"2","denominator":"3"}"#).unwrap();
+        assert_eq!(threshold, TrustThresholdFraction::default());
+        assert_eq!(
+            serde_json::to_string(&threshold).unwrap(),
+            r#"{"numerator":"2","denominator":"3"}"#
+        );
+
+        // and as bounded as the float form
+        let cases = [
+            (r#"{"numerator":"1","denominator":"0"}"#, "got: 1/0"),
+            (r#"{"numerator":"1","denominator":"4"}"#, "got: 1/4"),
+        ];
+        for (json, expected) in cases.iter() {
+            let res = serde_json::from_str::<TrustThresholdFraction>(json);
+            assert!(res.err().unwrap().to_string().contains(expected));
+        }
+    }
+
+    #[test]
+    fn test_threshold_all() {
+        let all = TrustThresholdFraction::all();
+        assert_eq!(all, TrustThresholdFraction::new(1, 1).unwrap());
+        assert_eq!(all.minimum_power_to_be_trusted(100), 100);
+        assert!(all.is_enough_power(100, 100));
+        assert!(!all.is_enough_power(99, 100));
+        assert_eq!(all.minimum_power_to_be_trusted(u64::MAX), u64::MAX);
+        // nothing can be trusted with an empty set
+        assert_eq!(all.minimum_power_to_be_trusted(0), 1);
+        assert_eq!(
+            TrustThresholdFraction::default().minimum_power_to_be_trusted(0),
+            1
+        );
+    }
+
+    #[test]
+    fn test_threshold_strictness() {
+        let two_thirds = TrustThresholdFraction::new(2, 3).unwrap();
+        let three_quarters = TrustThresholdFraction::new(3, 4).unwrap();
+        assert!(three_quarters.is_at_least_as_strict_as(&two_thirds));
+        assert!(!two_thirds.is_at_least_as_strict_as(&three_quarters));
+
+        // equal fractions are as strict as each other, however they are written
+        let four_sixths = TrustThresholdFraction::new(4, 6).unwrap();
+        assert!(two_thirds.is_at_least_as_strict_as(&four_sixths));
+        assert!(four_sixths.is_at_least_as_strict_as(&two_thirds));
+
+        // products of large terms don't overflow: just under 3/4
+        let large = TrustThresholdFraction::new(u64::MAX / 4, u64::MAX / 3).unwrap();
+        assert!(large.is_at_least_as_strict_as(&two_thirds));
+        assert!(!large.is_at_least_as_strict_as(&three_quarters));
+    }
+
+    #[test]
+    fn test_threshold_from_percent() {
+        assert_eq!(
+            TrustThresholdFraction::from_percent(34).unwrap(),
+            TrustThresholdFraction::new(34, 100).unwrap()
+        );
+
+        let threshold_fraction = TrustThresholdFraction::from_percent(67).unwrap();
+        assert_eq!(threshold_fraction.numerator, 67);
+        assert_eq!(threshold_fraction.denominator, 100);
+        assert!(!threshold_fraction.is_enough_power(67, 100));
+        assert!(threshold_fraction.is_enough_power(68, 100));
+
+        assert!(TrustThresholdFraction::from_percent(100).is_ok());
+
+        let res = TrustThresholdFraction::from_percent(20);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "A valid threshold is `1/3 <= threshold <= 1`, got: 20/100"
+        );
+        assert!(TrustThresholdFraction::from_percent(33).is_err());
+        assert!(TrustThresholdFraction::from_percent(101).is_err());
+    }
+
+    #[test]
+    fn test_persisted_trusted_state() {
+        type MockPersistedState = PersistedTrustedState<MockCommit<usize>, MockHeader, usize>;
+
+        let state = mock_state(1, SystemTime::UNIX_EPOCH, &MockValSet::new(vec![0, 1]));
+        let header = state.last_header().header().clone();
+
+        let persisted = MockPersistedState::new(state.clone());
+        assert_eq!(persisted.chain_id(), header.chain_id());
+        assert_eq!(persisted.state(), &state);
+
+        let json = serde_json::to_string(&persisted).unwrap();
+        assert!(json.starts_with(r#"{"chain_id":"test","state":"#));
+        let deserialized: MockPersistedState = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.chain_id(), persisted.chain_id());
+        assert_eq!(deserialized.state(), persisted.state());
+
+        // the chain id must be the one of the state
+        let mismatched = json.replacen(r#""chain_id":"test""#, r#""chain_id":"other""#, 1);
+        let err = serde_json::from_str::<MockPersistedState>(&mismatched).unwrap_err();
+        assert!(err.to_string().contains("(other!=test)"), "{}", err);
+
+        let mut deserializer = serde_json::Deserializer::from_str(&json);
+        assert_eq!(
+            MockPersistedState::peek_chain_id(&mut deserializer).unwrap(),
+            header.chain_id()
+        );
+        // the state isn't deserialized, anything goes
+        let mut deserializer =
+            serde_json::Deserializer::from_str(r#"{"state":{"opaque":true},"chain_id":"test"}"#);
+        assert_eq!(
+            MockPersistedState::peek_chain_id(&mut deserializer).unwrap(),
+            header.chain_id()
+        );
+
+        assert_eq!(persisted.into_state(), state);
+    }
 }
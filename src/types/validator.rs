@@ -1,23 +1,29 @@
 use std::fmt::Debug;
 
-use crate::merkle_tree::simple_hash_from_byte_vectors;
+use crate::errors::Error;
+use crate::merkle_tree;
+use crate::merkle_tree::{simple_hash_from_byte_slices, IncrementalHasher};
 use crate::types::account;
 use crate::types::account::Id;
 use crate::types::amino::message::AminoMessage;
 use crate::types::hash::Hash;
+#[cfg(feature = "hash-cache")]
+use crate::types::hash_cache;
 use crate::types::proposer_priority::ProposerPriority;
 use crate::types::pubkey::PublicKey;
+use crate::types::signature_scheme::SignatureSchemeRegistry;
 use crate::types::traits;
+use crate::types::traits::trusted::TrustThreshold;
 use crate::types::traits::validator::Validator;
 use crate::types::vote::power::Power as VotePower;
 use core::fmt;
+use ed25519_dalek::{Signature, Verifier};
 use prost_amino_derive::Message;
 use serde::de::{SeqAccess, Visitor};
 use serde::ser::SerializeSeq;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use ed25519_dalek::{Signature, Verifier};
-use std::convert::TryFrom;
 use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 use std::iter::FromIterator;
 use std::marker::PhantomData;
 
@@ -28,6 +34,8 @@ where
     V: Validator,
 {
     validators: Vec<V>,
+    // sum of the validators' power, computed once as it is needed on every verification
+    total_power: u64,
 }
 
 impl<V> Serialize for Set<V>
@@ -93,11 +101,291 @@ where
     V: Validator,
 {
     /// Create a new validator set.
-    /// vals is mutable so it can be sorted by address.
+    /// vals is mutable so it can be sorted by address. The order of the set only depends on
+    /// the addresses, which are unique once duplicates are dropped: validators of equal
+    /// power are never reordered by power, and the input order doesn't matter.
     pub fn new(mut vals: Vec<V>) -> Set<V> {
-        vals.dedup_by(|a, b| a.address() == b.address());
+        // sort first, so that duplicates are next to each other
         vals.sort_by(|v1, v2| v1.address().cmp(&v2.address()));
-        Set { validators: vals }
+        vals.dedup_by(|a, b| a.address() == b.address());
+        let total_power = vals.iter().map(|val| val.power()).sum();
+        Set {
+            validators: vals,
+            total_power,
+        }
+    }
+
+    /// Validators of the set, in canonical order (sorted by address).
+    pub fn as_slice(&self) -> &[V] {
+        &self.validators
+    }
+
+    /// Consume the set, returning its validators in canonical order (sorted by address).
+    pub fn into_sorted_vec(self) -> Vec<V> {
+        self.validators
+    }
+
+    /// Whether the validator with the given address is part of the set.
+    /// The set is sorted by address, so this is O(log n); for O(1) membership checks
+    /// use `ValidatorLookup::contains` on the `lookup_index` of the set.
+    pub fn contains(&self, val_id: account::Id) -> bool {
+        self.validators
+            .binary_search_by(|val| val.address().cmp(&val_id))
+            .is_ok()
+    }
+
+    /// The `n` validators with the highest voting power, in descending order of power.
+    /// Validators with equal power are ordered by address. If the set has fewer than `n`
+    /// validators, all of them are returned.
+    pub fn top_n(&self, n: usize) -> Vec<V> {
+        let mut vals = self.validators.clone();
+        vals.sort_by(|v1, v2| {
+            v2.power()
+                .cmp(&v1.power())
+                .then_with(|| v1.address().cmp(&v2.address()))
+        });
+        vals.truncate(n);
+        vals
+    }
+
+    /// Addresses of the fewest validators whose combined power reaches the minimum power
+    /// to be trusted with `threshold`, e.g. the signers to keep in a minimal commit proof:
+    /// the validators with the highest power, in the order of `top_n`. `None` if all the
+    /// validators of the set together don't reach it.
+    pub fn minimal_signing_subset<L>(&self, threshold: L) -> Option<Vec<account::Id>>
+    where
+        L: TrustThreshold,
+    {
+        let required_power = threshold.minimum_power_to_be_trusted(self.total_power);
+        let mut signed_power = 0u64;
+        let mut subset = vec![];
+        for val in self.top_n(self.validators.len()) {
+            if signed_power >= required_power {
+                break;
+            }
+            signed_power += val.power();
+            subset.push(val.address());
+        }
+        if signed_power >= required_power {
+            Some(subset)
+        } else {
+            None
+        }
+    }
+
+    /// Changes turning this set into `next`: validators added, validators removed and
+    /// validators whose power changed, each in address order.
+    pub fn update_to(&self, next: &Set<V>) -> ValSetUpdate<V> {
+        let mut update = ValSetUpdate {
+            added: vec![],
+            removed: vec![],
+            changed: vec![],
+        };
+        // both sets are sorted by address, so walk them side by side
+        let mut old_vals = self.validators.iter().peekable();
+        let mut new_vals = next.validators.iter().peekable();
+        loop {
+            match (old_vals.peek(), new_vals.peek()) {
+                (Some(old), Some(new)) if old.address() == new.address() => {
+                    if old.power() != new.power() {
+                        update
+                            .changed
+                            .push((old.address(), old.power(), new.power()));
+                    }
+                    old_vals.next();
+                    new_vals.next();
+                }
+                (Some(old), Some(new)) if old.address() < new.address() => {
+                    update.removed.push(old.address());
+                    old_vals.next();
+                }
+                (Some(old), None) => {
+                    update.removed.push(old.address());
+                    old_vals.next();
+                }
+                (_, Some(new)) => {
+                    update.added.push((*new).clone());
+                    new_vals.next();
+                }
+                (None, None) => break,
+            }
+        }
+        update
+    }
+
+    /// Merkle proof that the validator at `index` (in canonical order) is part of this set,
+    /// i.e. the aunts of its leaf from the bottom of the tree up, or `None` if there is no
+    /// validator at `index`. Together with the validator's leaf hash, its index and the
+    /// number of validators, the proof is enough to recompute `hash`.
+    pub fn inclusion_proof(&self, index: usize) -> Option<Vec<Hash>> {
+        if index >= self.validators.len() {
+            return None;
+        }
+        let validator_bytes: Vec<Vec<u8>> = self
+            .validators
+            .iter()
+            .map(|validator| validator.hash_bytes())
+            .collect();
+        Some(
+            merkle_tree::aunts_from_byte_vectors(&validator_bytes, index)
+                .into_iter()
+                .map(Hash::Sha256)
+                .collect(),
+        )
+    }
+
+    /// The hash of this set as a plain array, e.g. as a `HashMap` key for caching
+    /// data about validator sets. Equal sets have equal fingerprints.
+    pub fn fingerprint(&self) -> [u8; 32] {
+        match traits::validator_set::ValidatorSet::hash(self) {
+            Hash::Sha256(bytes) => bytes,
+        }
+    }
+
+    // Merkle root of the validators' hash bytes.
+    #[cfg(any(test, not(feature = "hash-cache")))]
+    fn merkle_root(&self) -> Hash {
+        let validator_bytes: Vec<Vec<u8>> = self
+            .validators
+            .iter()
+            .map(|validator| validator.hash_bytes())
+            .collect();
+        Self::merkle_root_of(&validator_bytes)
+    }
+
+    // Merkle root of the given validator hash bytes, in order.
+    fn merkle_root_of(validator_bytes: &[Vec<u8>]) -> Hash {
+        Hash::Sha256(simple_hash_from_byte_slices(validator_bytes))
+    }
+
+    /// Index the validators of this set by address. Building the index is O(n), after
+    /// which each lookup is O(1); reuse it when verifying many commits against this set.
+    pub fn lookup_index(&self) -> ValidatorLookup<V> {
+        ValidatorLookup {
+            validators: self
+                .validators
+                .iter()
+                .map(|v| (v.address(), v.clone()))
+                .collect(),
+            total_power: self.total_power,
+        }
+    }
+}
+
+/// `Set<Info>` is the concrete validator set: it is the `ValidatorSet` of `Commit`, so it
+/// is what generic code over `C: ProvableCommit<V>` sees as `C::ValidatorSet` when `C` is
+/// `Commit` and `V` is `Info`. Going the other way, `as_slice` and `into_sorted_vec` give
+/// back the validators, e.g. to build a `MockValSet` in tests.
+impl Set<Info> {
+    /// Create the concrete validator set from the validators' info,
+    /// e.g. as returned by the /validators RPC endpoint.
+    pub fn from_infos(infos: Vec<Info>) -> Self {
+        Set::new(infos)
+    }
+
+    /// Create the concrete validator set from validators indexed by address.
+    pub fn from_map(map: HashMap<account::Id, Info>) -> Self {
+        Set::new(map.into_values().collect())
+    }
+
+    /// Create the concrete validator set from the validators' public keys and voting
+    /// powers, for sources omitting the addresses: they are derived from the keys.
+    pub fn from_pubkey_power_pairs(pairs: Vec<(PublicKey, u64)>) -> Self {
+        Set::new(
+            pairs
+                .into_iter()
+                .map(|(pub_key, power)| Info::new(pub_key, VotePower::new(power)))
+                .collect(),
+        )
+    }
+}
+
+/// Difference between two validator sets, in the shape of a validator set update
+/// (e.g. for IBC): new validators, addresses of the validators which left the set, and
+/// `(address, old power, new power)` for the validators whose power changed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValSetUpdate<V> {
+    pub added: Vec<V>,
+    pub removed: Vec<account::Id>,
+    pub changed: Vec<(account::Id, u64, u64)>,
+}
+
+impl<V> ValSetUpdate<V> {
+    /// Whether the two sets had the same validators with the same power.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Computes the hash of a validator set one validator at a time, e.g. while streaming a
+/// large set, without buffering all of them. The result matches `Set::hash` provided the
+/// validators are pushed in the canonical order of the set (sorted by address).
+#[derive(Clone, Debug, Default)]
+pub struct ValidatorSetHasher {
+    hasher: IncrementalHasher,
+}
+
+impl ValidatorSetHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append the hash bytes (`Validator::hash_bytes`) of the next validator.
+    pub fn push_leaf(&mut self, hash_bytes: &[u8]) {
+        self.hasher.push(hash_bytes);
+    }
+
+    /// Append the next validator.
+    pub fn push<V>(&mut self, validator: &V)
+    where
+        V: Validator,
+    {
+        self.push_leaf(&validator.hash_bytes());
+    }
+
+    /// Number of validators pushed so far.
+    pub fn number_of_validators(&self) -> usize {
+        self.hasher.leaves()
+    }
+
+    /// Hash of the validator set made of all the validators pushed.
+    pub fn finalize(self) -> Hash {
+        Hash::Sha256(self.hasher.finalize())
+    }
+}
+
+/// Validators of a [`Set`] indexed by their address.
+#[derive(Clone, Debug)]
+pub struct ValidatorLookup<V>
+where
+    V: Validator,
+{
+    validators: HashMap<account::Id, V>,
+    total_power: u64,
+}
+
+impl<V> ValidatorLookup<V>
+where
+    V: Validator,
+{
+    /// Get the validator with the given address, if it is part of the set.
+    pub fn get(&self, val_id: account::Id) -> Option<&V> {
+        self.validators.get(&val_id)
+    }
+
+    /// Whether the validator with the given address is part of the set, in O(1).
+    pub fn contains(&self, val_id: account::Id) -> bool {
+        self.validators.contains_key(&val_id)
+    }
+
+    /// Number of validators in the set.
+    pub fn number_of_validators(&self) -> usize {
+        self.validators.len()
+    }
+
+    /// Total voting power of the set.
+    pub fn total_power(&self) -> u64 {
+        self.total_power
     }
 }
 
@@ -105,20 +393,20 @@ impl<V> traits::validator_set::ValidatorSet<V> for Set<V>
 where
     V: Validator,
 {
-    /// Compute the Merkle root of the validator set
+    /// Compute the Merkle root of the validator set.
+    /// With the `hash-cache` feature, recently computed roots are reused.
     fn hash(&self) -> Hash {
-        let validator_bytes: Vec<Vec<u8>> = self
-            .validators
-            .iter()
-            .map(|validator| validator.hash_bytes())
-            .collect();
-        Hash::Sha256(simple_hash_from_byte_vectors(validator_bytes))
+        #[cfg(feature = "hash-cache")]
+        {
+            let identity = hash_cache::identity(&self.validators);
+            hash_cache::get_or_compute(identity, Self::merkle_root_of)
+        }
+        #[cfg(not(feature = "hash-cache"))]
+        self.merkle_root()
     }
 
     fn total_power(&self) -> u64 {
-        self.validators.iter().fold(0u64, |total, val_info| {
-            total + val_info.vote_power().value()
-        })
+        self.total_power
     }
 
     fn validator(&self, val_id: account::Id) -> Option<V> {
@@ -151,6 +439,10 @@ where
     fn number_of_validators(&self) -> usize {
         self.validators.len()
     }
+
+    fn validators(&self) -> &[V] {
+        &self.validators
+    }
 }
 
 /// Validator information
@@ -178,15 +470,29 @@ impl Validator for Info {
 
     /// Verify the given signature against the given sign_bytes using the validators
     /// public key.
+    ///
+    /// The ed25519 verification is deterministic (it uses no randomness), but
+    /// ed25519-dalek allocates the error of a rejected signature. Verifiers which can't
+    /// allocate should use `SignatureSchemeRegistry::no_alloc` instead.
     fn verify_signature(&self, sign_bytes: &[u8], signature: &[u8]) -> bool {
         if let Some(pk) = &self.pub_key.ed25519() {
             if let Ok(sig) = Signature::try_from(signature) {
-                return pk.verify(sign_bytes, &sig).is_ok()
+                return pk.verify(sign_bytes, &sig).is_ok();
             }
         }
         false
     }
 
+    /// Fails if no verifier is registered for the type of the validator's key.
+    fn verify_signature_with(
+        &self,
+        schemes: &SignatureSchemeRegistry,
+        sign_bytes: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, Error> {
+        schemes.verify_public_key(&self.pub_key, sign_bytes, signature)
+    }
+
     fn address(&self) -> Id {
         self.address
     }
@@ -202,6 +508,11 @@ impl Validator for Info {
     fn hash_bytes(&self) -> Vec<u8> {
         AminoMessage::bytes_vec(&InfoHashable::from(self))
     }
+
+    /// Only ed25519 keys are hashed correctly for now, see `InfoHashable`.
+    fn has_hashable_key(&self) -> bool {
+        self.pub_key.ed25519().is_some()
+    }
 }
 
 impl From<PublicKey> for account::Id {
@@ -223,6 +534,12 @@ impl Info {
             proposer_priority: None,
         }
     }
+
+    /// Hash of this validator as a leaf of the merkle tree of its validator set,
+    /// e.g. to check an inclusion proof against the set's hash.
+    pub fn leaf_hash(&self) -> Hash {
+        Hash::Sha256(merkle_tree::leaf_hash(&self.hash_bytes()))
+    }
 }
 
 /// InfoHashable is the form of the validator used for computing the Merkle tree.
@@ -250,11 +567,12 @@ impl From<&Info> for InfoHashable {
 
 #[cfg(test)]
 mod tests {
+    use crate::types::pubkey::PublicKey;
     use crate::types::pubkey::PublicKey::Ed25519;
-    use crate::types::traits::{validator_set::ValidatorSet, validator::Validator};
-    use crate::types::validator::{Info, Set};
+    use crate::types::traits::{validator::Validator, validator_set::ValidatorSet};
+    use crate::types::trusted::TrustThresholdFraction;
+    use crate::types::validator::{Info, Set, ValidatorSetHasher};
     use crate::types::vote::power::Power;
-    use crate::types::pubkey::PublicKey;
     use subtle_encoding::hex;
 
     fn generate_random_validators(number_of_validators: usize, vote_power: u64) -> Vec<Info> {
@@ -317,9 +635,422 @@ mod tests {
         assert_eq!(intersection.total_power(), 0);
     }
 
+    #[cfg(feature = "hash-cache")]
+    #[test]
+    fn test_cached_hash() {
+        let validators = generate_random_validators(10, 1);
+        let set = Set::new(validators.clone());
+
+        // computed on the first call, served from the cache afterwards
+        assert_eq!(set.hash(), set.merkle_root());
+        assert_eq!(set.hash(), set.merkle_root());
+
+        // same validators, different power: a different set
+        let other_set = Set::new(
+            validators
+                .iter()
+                .map(|val| Info::new(val.pub_key, Power::new(2)))
+                .collect(),
+        );
+        assert_eq!(other_set.hash(), other_set.merkle_root());
+        assert_ne!(other_set.hash(), set.hash());
+    }
+
+    #[test]
+    fn test_sorted_validators() {
+        let validators = generate_random_validators(10, 1);
+        let set = Set::new(validators.clone());
+
+        let slice = set.as_slice();
+        assert_eq!(slice.len(), validators.len());
+        assert!(slice
+            .windows(2)
+            .all(|pair| pair[0].address() < pair[1].address()));
+
+        let sorted = set.clone().into_sorted_vec();
+        assert_eq!(sorted.as_slice(), set.as_slice());
+        // canonical order is stable, rebuilding the set doesn't change it
+        assert_eq!(Set::new(sorted), set);
+    }
+
+    #[test]
+    fn test_contains() {
+        let validators = generate_random_validators(10, 1);
+        let set = Set::new(validators[0..5].to_vec());
+        let lookup = set.lookup_index();
+
+        for val in &validators[0..5] {
+            assert!(set.contains(val.address));
+            assert!(lookup.contains(val.address));
+        }
+        for val in &validators[5..10] {
+            assert!(!set.contains(val.address));
+            assert!(!lookup.contains(val.address));
+        }
+    }
+
+    #[test]
+    fn test_from_infos() {
+        use crate::types::mocks::{
+            generate_keypairs, signed_header_at, trusted_state_at, validator_info,
+        };
+        use crate::{verify_single, Time, TrustThresholdFraction};
+        use std::time::{Duration, SystemTime};
+
+        let keypairs = generate_keypairs(3);
+        let infos: Vec<Info> = keypairs.iter().map(|kp| validator_info(kp, 1)).collect();
+        let vals = Set::from_infos(infos.clone());
+        assert_eq!(vals.as_slice(), &infos[..]);
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        assert!(verify_single(
+            trusted_state,
+            &signed_header_at(2, time, &keypairs, &vals, &[0, 1, 2]),
+            &vals,
+            &vals,
+            TrustThresholdFraction::default(),
+            Duration::new(100, 0),
+            SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_equal_power_order() {
+        use rand::seq::SliceRandom;
+
+        let vals = generate_random_validators(64, 10);
+        let set = Set::new(vals.clone());
+        assert_eq!(set.number_of_validators(), 64);
+        assert!(set
+            .validators()
+            .windows(2)
+            .all(|pair| pair[0].address() < pair[1].address()));
+
+        let mut rng = rand::thread_rng();
+        for _ in 0..10 {
+            let mut shuffled = vals.clone();
+            shuffled.shuffle(&mut rng);
+            let shuffled_set = Set::new(shuffled);
+            assert_eq!(shuffled_set.validators(), set.validators());
+            assert_eq!(shuffled_set.hash(), set.hash());
+        }
+    }
+
+    #[test]
+    fn test_deserialize_unsorted() {
+        use crate::types::mocks::{generate_keypairs, signed_header_at, validator_info};
+        use crate::{verify_single, Time, TrustThresholdFraction, TrustedState};
+        use std::time::{Duration, SystemTime};
+
+        let keypairs = generate_keypairs(4);
+        let infos: Vec<Info> = keypairs.iter().map(|kp| validator_info(kp, 1)).collect();
+        let vals = Set::new(infos.clone());
+
+        // out of order, with a duplicate which isn't next to the other one
+        let mut reversed = infos.clone();
+        reversed.reverse();
+        reversed.push(infos[3]);
+        let json = serde_json::to_string(&reversed).unwrap();
+        let deserialized: Set<Info> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, vals);
+        assert_eq!(deserialized.total_power(), 4);
+        assert_eq!(deserialized.hash(), vals.hash());
+
+        let signers = [0, 1, 2, 3];
+        let trusted_state = TrustedState::new(
+            signed_header_at(1, Time::unix_epoch(), &keypairs, &vals, &signers),
+            deserialized.clone(),
+        );
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let sh = signed_header_at(2, time, &keypairs, &vals, &signers);
+        let commit_sigs = sh.commit().pair_with(&deserialized).unwrap();
+        assert_eq!(commit_sigs[0].0, infos[0]);
+
+        assert!(verify_single(
+            trusted_state,
+            &sh,
+            &deserialized,
+            &deserialized,
+            TrustThresholdFraction::default(),
+            Duration::new(100, 0),
+            SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        use std::collections::HashMap;
+
+        let validators = generate_random_validators(4, 1);
+        let vals = Set::new(validators.clone());
+        let mut reversed = validators.clone();
+        reversed.reverse();
+        let same_vals = Set::new(reversed);
+        let other_vals = Set::new(validators[..3].to_vec());
+
+        assert_eq!(vals.fingerprint(), same_vals.fingerprint());
+        assert_ne!(vals.fingerprint(), other_vals.fingerprint());
+        assert_eq!(&vals.fingerprint()[..], vals.hash().as_bytes());
+
+        let mut cache = HashMap::new();
+        cache.insert(vals.fingerprint(), "cached");
+        assert_eq!(cache.get(&same_vals.fingerprint()), Some(&"cached"));
+        assert_eq!(cache.get(&other_vals.fingerprint()), None);
+    }
+
+    #[test]
+    fn test_from_map() {
+        use std::collections::HashMap;
+
+        let validators = generate_random_validators(10, 3);
+        let map: HashMap<_, _> = validators.iter().map(|v| (v.address(), *v)).collect();
+
+        let vals = Set::from_map(map);
+        assert_eq!(vals, Set::new(validators.clone()));
+        assert_eq!(vals.hash(), Set::new(validators).hash());
+    }
+
+    #[test]
+    fn test_from_pubkey_power_pairs() {
+        let validators: Vec<Info> = generate_random_validators(10, 1)
+            .into_iter()
+            .enumerate()
+            .map(|(i, val)| Info {
+                voting_power: Power::new(i as u64 + 1),
+                ..val
+            })
+            .collect();
+        let pairs = validators
+            .iter()
+            .map(|val| (val.pub_key, val.power()))
+            .collect();
+
+        let vals = Set::from_pubkey_power_pairs(pairs);
+        let explicit = Set::new(validators);
+        assert_eq!(vals, explicit);
+        assert_eq!(vals.hash(), explicit.hash());
+    }
+
+    #[test]
+    fn test_inclusion_proof() {
+        use crate::merkle_tree::compute_hash_from_aunts;
+        use crate::types::hash::Hash;
+
+        let to_bytes = |hash: Hash| match hash {
+            Hash::Sha256(bytes) => bytes,
+        };
+        // not a power of two, so the tree isn't balanced
+        let set = Set::new(generate_random_validators(5, 1));
+        for (index, val) in set.as_slice().iter().enumerate() {
+            let aunts: Vec<_> = set
+                .inclusion_proof(index)
+                .unwrap()
+                .into_iter()
+                .map(to_bytes)
+                .collect();
+            let root = compute_hash_from_aunts(index, 5, to_bytes(val.leaf_hash()), &aunts);
+            assert_eq!(root.map(Hash::Sha256), Some(set.hash()));
+
+            // the proof is for this position only
+            let other_index = (index + 1) % 5;
+            let root = compute_hash_from_aunts(other_index, 5, to_bytes(val.leaf_hash()), &aunts);
+            assert_ne!(root.map(Hash::Sha256), Some(set.hash()));
+        }
+        assert!(set.inclusion_proof(5).is_none());
+    }
+
+    #[test]
+    fn test_update_to() {
+        let validators = generate_random_validators(6, 10);
+        let old_set = Set::new(validators[0..4].to_vec());
+        // 0 and 1 stay, 2 and 3 leave, 4 and 5 join, and 1 goes from 10 to 25
+        let reweighted = Info::new(validators[1].pub_key, Power::new(25));
+        let new_set = Set::new(vec![
+            validators[0],
+            reweighted,
+            validators[4],
+            validators[5],
+        ]);
+
+        let update = old_set.update_to(&new_set);
+        let mut added = vec![validators[4], validators[5]];
+        added.sort_by_key(|val| val.address());
+        assert_eq!(update.added, added);
+        let mut removed = vec![validators[2].address(), validators[3].address()];
+        removed.sort();
+        assert_eq!(update.removed, removed);
+        assert_eq!(update.changed, vec![(validators[1].address(), 10, 25)]);
+
+        // the reverse update undoes it
+        let reverse = new_set.update_to(&old_set);
+        assert_eq!(
+            reverse.removed,
+            added.iter().map(|val| val.address()).collect::<Vec<_>>()
+        );
+        assert_eq!(reverse.changed, vec![(validators[1].address(), 25, 10)]);
+
+        assert!(old_set.update_to(&old_set).is_empty());
+    }
+
+    #[test]
+    fn test_top_n() {
+        let validators: Vec<Info> = [5, 1, 3, 5, 2, 3]
+            .iter()
+            .zip(generate_random_validators(6, 1))
+            .map(|(power, val)| Info::new(val.pub_key, Power::new(*power)))
+            .collect();
+        let set = Set::new(validators.clone());
+
+        let top = set.top_n(3);
+        assert_eq!(top.len(), 3);
+        let powers: Vec<u64> = top.iter().map(|val| val.power()).collect();
+        assert_eq!(powers, vec![5, 5, 3]);
+        // ties are broken by address
+        assert!(top[0].address() < top[1].address());
+        let mut tied_at_3: Vec<Info> = validators
+            .iter()
+            .filter(|val| val.power() == 3)
+            .cloned()
+            .collect();
+        tied_at_3.sort_by_key(|val| val.address());
+        assert_eq!(top[2], tied_at_3[0]);
+
+        assert!(set.top_n(0).is_empty());
+        let all = set.top_n(10);
+        assert_eq!(all.len(), 6);
+        assert!(all
+            .windows(2)
+            .all(|pair| pair[0].power() >= pair[1].power()));
+    }
+
+    #[test]
+    fn test_minimal_signing_subset() {
+        let validators: Vec<Info> = [5, 50, 10, 20, 15]
+            .iter()
+            .zip(generate_random_validators(5, 1))
+            .map(|(power, val)| Info::new(val.pub_key, Power::new(*power)))
+            .collect();
+        let set = Set::new(validators.clone());
+        let address_with_power = |power| {
+            validators
+                .iter()
+                .find(|val| val.power() == power)
+                .unwrap()
+                .address
+        };
+
+        // more than 1/3 of 100 is 34: the validator with 50 is enough on its own
+        let one_third = TrustThresholdFraction::new(1, 3).unwrap();
+        assert_eq!(
+            set.minimal_signing_subset(one_third),
+            Some(vec![address_with_power(50)])
+        );
+        // more than 2/3 is 67, which takes 50 + 20
+        assert_eq!(
+            set.minimal_signing_subset(TrustThresholdFraction::default()),
+            Some(vec![address_with_power(50), address_with_power(20)])
+        );
+        // 1/1 takes all of them
+        let subset = set.minimal_signing_subset(TrustThresholdFraction::all());
+        assert_eq!(subset.map(|subset| subset.len()), Some(5));
+        assert_eq!(
+            Set::<Info>::new(vec![]).minimal_signing_subset(one_third),
+            None
+        );
+    }
+
+    #[test]
+    fn test_incremental_hash() {
+        let validators = generate_random_validators(20, 1);
+        for n in 0..=validators.len() {
+            let set = Set::new(validators[..n].to_vec());
+
+            let mut hasher = ValidatorSetHasher::new();
+            for val in set.as_slice() {
+                hasher.push(val);
+            }
+            assert_eq!(hasher.number_of_validators(), n);
+            assert_eq!(hasher.finalize(), set.hash(), "{} validators", n);
+
+            let mut hasher = ValidatorSetHasher::new();
+            for val in set.as_slice() {
+                hasher.push_leaf(&val.hash_bytes());
+            }
+            assert_eq!(hasher.finalize(), set.hash(), "{} validators", n);
+        }
+    }
+
+    #[test]
+    fn test_hash_eq() {
+        let validators = generate_random_validators(10, 1);
+        let set = Set::new(validators.clone());
+
+        let mut reversed = validators.clone();
+        reversed.reverse();
+        let same_set = Set::new(reversed);
+        assert!(set.hash_eq(&same_set));
+        assert!(same_set.hash_eq(&set));
+
+        let smaller_set = Set::new(validators[1..].to_vec());
+        assert!(!set.hash_eq(&smaller_set));
+
+        let more_power = Set::new(
+            validators
+                .iter()
+                .map(|val| Info::new(val.pub_key, Power::new(2)))
+                .collect(),
+        );
+        assert!(!set.hash_eq(&more_power));
+    }
+
+    #[test]
+    fn test_cached_total_power() {
+        let validators: Vec<Info> = generate_random_validators(10, 1)
+            .iter()
+            .enumerate()
+            .map(|(i, val)| Info::new(val.pub_key, Power::new(i as u64 * 7 + 1)))
+            .collect();
+        let fold = |vals: &[Info]| vals.iter().fold(0u64, |total, val| total + val.power());
+
+        let set = Set::new(validators.clone());
+        assert_eq!(set.total_power(), fold(&validators));
+        assert_eq!(set.total_power(), fold(set.as_slice()));
+
+        // derived sets compute their own totals
+        let other_set = Set::new(validators[3..].to_vec());
+        let intersection = set.intersect(&other_set);
+        assert_eq!(intersection.total_power(), fold(&validators[3..]));
+        assert_eq!(set.lookup_index().total_power(), set.total_power());
+
+        let json = serde_json::to_string(&set).unwrap();
+        let deserialized: Set<Info> = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.total_power(), set.total_power());
+    }
+
+    #[test]
+    fn test_lookup_index() {
+        let validators = generate_random_validators(10, 1);
+        let set = Set::new(validators[0..5].to_vec());
+        let lookup = set.lookup_index();
+
+        for val in &validators[0..5] {
+            assert_eq!(lookup.get(val.address), set.validator(val.address).as_ref());
+        }
+        for val in &validators[5..10] {
+            assert!(lookup.get(val.address).is_none());
+        }
+        assert_eq!(lookup.number_of_validators(), set.number_of_validators());
+        assert_eq!(lookup.total_power(), set.total_power());
+    }
+
     #[test]
     fn test_validate_signature() {
-        let pk_bytes = hex::decode("330b745d9da896f6f89f288633d25b4608d53c0a03f53336c5b03713f1a95559").unwrap();
+        let pk_bytes =
+            hex::decode("330b745d9da896f6f89f288633d25b4608d53c0a03f53336c5b03713f1a95559")
+                .unwrap();
         let signed_bytes = hex::decode("f7d9e1b08c814154f60760e9cb7cd3c3618743f665b7af1661e9dbbab3ee005d7a4314fb992cade8a048bca5b5d27170450ca5ce87cfffb36d43a95d34b62c00").unwrap();
 
         let pub_key = PublicKey::from_raw_ed25519(&pk_bytes).unwrap();
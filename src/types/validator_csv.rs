@@ -0,0 +1,158 @@
+//! Reading validator sets from CSV (or TSV) files, for airgapped setups
+
+use std::io::Read;
+use std::str::FromStr;
+
+use anomaly::{fail, format_err};
+use subtle_encoding::{base64, hex};
+
+use crate::errors::{Error, Kind};
+use crate::types::account;
+use crate::types::pubkey::PublicKey;
+use crate::types::traits::validator::Validator;
+use crate::types::validator::{Info, Set};
+use crate::types::vote::power::Power;
+
+impl Set<Info> {
+    /// Parse a validator set from CSV with a header row and the columns
+    /// `address,pubkey,power`. Public keys are raw ed25519 (or secp256k1) keys,
+    /// encoded as hex or base64. The address of every row has to be the one
+    /// derived from its public key.
+    pub fn from_csv<R: Read>(reader: R) -> Result<Self, Error> {
+        Self::from_delimited(reader, b',')
+    }
+
+    /// Same as [`Set::from_csv`], with the columns separated by `delimiter`
+    /// (e.g. `b'\t'` for TSV).
+    pub fn from_delimited<R: Read>(reader: R, delimiter: u8) -> Result<Self, Error> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(delimiter)
+            .trim(csv::Trim::All)
+            .from_reader(reader);
+
+        let mut validators = vec![];
+        for (i, record) in reader.records().enumerate() {
+            // the header is line 1
+            let line = i + 2;
+            let record = record.map_err(|e| Kind::Parse.context(e))?;
+            if record.len() != 3 {
+                fail!(
+                    Kind::Parse,
+                    "line {}: expected 3 columns (address, pubkey, power), got {}",
+                    line,
+                    record.len()
+                );
+            }
+
+            let address = account::Id::from_str(&record[0])
+                .map_err(|e| format_err!(Kind::Parse, "line {}: invalid address: {}", line, e))?;
+            let pub_key = parse_pub_key(&record[1])
+                .ok_or_else(|| format_err!(Kind::InvalidKey, "line {}: invalid pubkey", line))?;
+            let power = record[2]
+                .parse::<u64>()
+                .map_err(|e| format_err!(Kind::Parse, "line {}: invalid power: {}", line, e))?;
+
+            let validator = Info::new(pub_key, Power::new(power));
+            if validator.address() != address {
+                fail!(
+                    Kind::InvalidKey,
+                    "line {}: address {} doesn't match the pubkey's address {}",
+                    line,
+                    address,
+                    validator.address()
+                );
+            }
+            validators.push(validator);
+        }
+
+        Ok(Set::new(validators))
+    }
+}
+
+// Decode a hex or base64 encoded raw public key.
+fn parse_pub_key(encoded: &str) -> Option<PublicKey> {
+    // raw keys are 32 (ed25519) or 33 (secp256k1) bytes long
+    let is_hex = matches!(encoded.len(), 64 | 66) && encoded.chars().all(|c| c.is_ascii_hexdigit());
+    let bytes = if is_hex {
+        hex::decode(encoded.to_ascii_lowercase()).ok()?
+    } else {
+        base64::decode(encoded).ok()?
+    };
+
+    match bytes.len() {
+        32 => PublicKey::from_raw_ed25519(&bytes),
+        33 => PublicKey::from_raw_secp256k1(&bytes),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::types::pubkey::PublicKey;
+    use crate::types::traits::validator::Validator;
+    use crate::types::traits::validator_set::ValidatorSet;
+    use crate::types::validator::{Info, Set};
+    use crate::types::vote::power::Power;
+    use subtle_encoding::{base64, hex};
+
+    const PUB_KEYS: [&str; 3] = [
+        "F349539C7E5EF7C49549B09C4BFC2335318AB0FE51FBFAA2433B4F13E816F4A7",
+        "5646AA4C706B7AF73768903E77D117487D2584B76D83EB8FF287934EE7758AFC",
+        "76A2B3F5CBB567F0D689D9DF7155FC89A4C878F040D7A5BB85FF68B74D253FC7",
+    ];
+
+    fn pub_key(hex_key: &str) -> PublicKey {
+        PublicKey::from_raw_ed25519(&hex::decode_upper(hex_key).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_from_csv() {
+        let expected = Set::new(
+            PUB_KEYS
+                .iter()
+                .zip(&[239, 144, 235])
+                .map(|(key, power)| Info::new(pub_key(key), Power::new(*power)))
+                .collect(),
+        );
+
+        // one key in lowercase hex, one in base64
+        let base64_key =
+            String::from_utf8(base64::encode(pub_key(PUB_KEYS[2]).as_bytes())).unwrap();
+        let csv = format!(
+            "address,pubkey,power\n{},{},239\n{},{},144\n{}, {}, 235\n",
+            Info::new(pub_key(PUB_KEYS[0]), Power::new(0)).address(),
+            PUB_KEYS[0],
+            Info::new(pub_key(PUB_KEYS[1]), Power::new(0)).address(),
+            PUB_KEYS[1].to_lowercase(),
+            Info::new(pub_key(PUB_KEYS[2]), Power::new(0)).address(),
+            base64_key,
+        );
+        let set = Set::from_csv(csv.as_bytes()).unwrap();
+        assert_eq!(set, expected);
+        assert_eq!(set.hash(), expected.hash());
+
+        let tsv = csv.replace(",", "\t");
+        let set = Set::from_delimited(tsv.as_bytes(), b'\t').unwrap();
+        assert_eq!(set.hash(), expected.hash());
+    }
+
+    #[test]
+    fn test_from_csv_invalid() {
+        let address = Info::new(pub_key(PUB_KEYS[0]), Power::new(0)).address();
+        let other_address = Info::new(pub_key(PUB_KEYS[1]), Power::new(0)).address();
+
+        for row in &[
+            // address of another key
+            format!("{},{},1", other_address, PUB_KEYS[0]),
+            // truncated key
+            format!("{},{},1", address, &PUB_KEYS[0][..62]),
+            // negative power
+            format!("{},{},-1", address, PUB_KEYS[0]),
+            // missing column
+            format!("{},{}", address, PUB_KEYS[0]),
+        ] {
+            let csv = format!("address,pubkey,power\n{}\n", row);
+            assert!(Set::from_csv(csv.as_bytes()).is_err(), "accepted {}", row);
+        }
+    }
+}
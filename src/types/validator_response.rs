@@ -0,0 +1,135 @@
+//! Adapter for the response of the `/validators` RPC endpoint
+
+use crate::errors::{Error, Kind};
+use crate::types::block::height::Height;
+use crate::types::traits::validator::Validator;
+use crate::types::validator::{Info, Set};
+
+/// Result of the `/validators` RPC endpoint, for a single page.
+///
+/// `total` is the number of validators of the whole set, so a response whose
+/// validators don't add up to it is truncated (or only one page of several).
+/// Some providers also report the `total_voting_power` of the set, which is
+/// cross-checked against the parsed validators when present.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ValidatorSetResponse {
+    /// Height the validator set is for
+    pub block_height: Height,
+
+    /// Validators in this response
+    pub validators: Vec<Info>,
+
+    /// Number of validators in this response
+    #[serde(default, with = "crate::serialization::from_str::option")]
+    pub count: Option<u64>,
+
+    /// Number of validators in the whole set
+    #[serde(with = "crate::serialization::from_str")]
+    pub total: u64,
+
+    /// Total voting power of the whole set, if reported
+    #[serde(default, with = "crate::serialization::from_str::option")]
+    pub total_voting_power: Option<u64>,
+}
+
+impl ValidatorSetResponse {
+    /// Build the validator set, after making sure the response holds all of it.
+    pub fn into_validator_set(self) -> Result<Set<Info>, Error> {
+        let got = self.validators.len() as u64;
+        if self.count.unwrap_or(got) != got || self.total != got {
+            return Err(Kind::IncompleteValidatorSet {
+                expected: self.total,
+                got,
+            }
+            .into());
+        }
+
+        // the total power of a set has to fit in a u64, whether it is reported or not
+        let computed = self
+            .validators
+            .iter()
+            .try_fold(0u64, |total, val| total.checked_add(val.power()))
+            .ok_or_else(|| Error::from(Kind::OutOfRange))?;
+        if let Some(reported) = self.total_voting_power {
+            if reported != computed {
+                return Err(Kind::TotalVotingPowerMismatch { reported, computed }.into());
+            }
+        }
+
+        Ok(Set::new(self.validators))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ValidatorSetResponse;
+    use crate::errors::Kind;
+    use crate::types::mocks::{generate_keypairs, validator_info, validator_set};
+    use serde_json::json;
+
+    fn response(total: &str, total_voting_power: Option<&str>) -> ValidatorSetResponse {
+        let vals = validator_set(&generate_keypairs(3), 10);
+        let mut response = json!({
+            "block_height": "42",
+            "validators": vals.as_slice(),
+            "count": "3",
+            "total": total,
+        });
+        if let Some(power) = total_voting_power {
+            response["total_voting_power"] = json!(power);
+        }
+        serde_json::from_value(response).unwrap()
+    }
+
+    #[test]
+    fn test_into_validator_set() {
+        let set = response("3", None).into_validator_set().unwrap();
+        assert_eq!(set.as_slice().len(), 3);
+
+        assert!(response("3", Some("30")).into_validator_set().is_ok());
+    }
+
+    #[test]
+    fn test_mismatched_total_voting_power() {
+        let err = response("3", Some("31")).into_validator_set().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            Kind::TotalVotingPowerMismatch {
+                reported: 31,
+                computed: 30
+            }
+        ));
+    }
+
+    #[test]
+    fn test_overflowing_voting_power() {
+        let keypairs = generate_keypairs(2);
+        let vals = vec![
+            validator_info(&keypairs[0], u64::MAX),
+            validator_info(&keypairs[1], 1),
+        ];
+        for total_voting_power in &[json!(null), json!("1")] {
+            let response: ValidatorSetResponse = serde_json::from_value(json!({
+                "block_height": "42",
+                "validators": vals,
+                "total": "2",
+                "total_voting_power": total_voting_power,
+            }))
+            .unwrap();
+            let err = response.into_validator_set().unwrap_err();
+            assert!(matches!(err.kind(), Kind::OutOfRange));
+        }
+    }
+
+    #[test]
+    fn test_truncated_page() {
+        let err = response("4", None).into_validator_set().unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            Kind::IncompleteValidatorSet {
+                expected: 4,
+                got: 3
+            }
+        ));
+    }
+}
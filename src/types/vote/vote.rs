@@ -1,10 +1,12 @@
+use crate::errors::Error;
 use crate::types::amino::message::AminoMessage;
 use crate::types::block;
 use crate::types::signature::Signature;
 use crate::types::time::Time;
-use crate::types::{account, amino, hash};
+use crate::types::{account, amino, chain, hash};
 use serde::de::Error as _;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryFrom;
 
 /// Votes are signed messages from validators for a particular block which
 /// include information about the validator signing it.
@@ -41,6 +43,32 @@ pub struct Vote {
     pub signature: Signature,
 }
 
+/// A vote from a commit as a standalone record, e.g. for external indexing.
+/// Unlike [`SignedVote`] it is serializable, and carries the exact bytes
+/// the validator signed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct VoteRecord {
+    /// Block height
+    pub height: block::height::Height,
+
+    /// Round
+    #[serde(with = "crate::serialization::from_str")]
+    pub round: u64,
+
+    /// Address of the validator who cast the vote
+    pub validator: account::Id,
+
+    /// Block voted for, `None` for a nil vote
+    pub block_id: Option<block::id::Id>,
+
+    /// Signature of the validator
+    pub signature: Signature,
+
+    /// Bytes (of the canonicalized vote) that were signed
+    #[serde(with = "crate::serialization::bytes::hexstring")]
+    pub sign_bytes: Vec<u8>,
+}
+
 impl Vote {
     /// Is this vote a prevote?
     pub fn is_prevote(&self) -> bool {
@@ -67,6 +95,14 @@ impl Vote {
     }
 }
 
+/// Length of the bytes a validator signs for `vote` on the chain `chain_id` (see
+/// `SignedVote::sign_bytes`), computed without encoding them. Meant for charging gas by
+/// the bytes verified before verifying anything.
+pub fn canonical_vote_sign_bytes_len(vote: &Vote, chain_id: chain::Id) -> Result<usize, Error> {
+    let canonical_vote = amino::CanonicalVote::new(amino::Vote::try_from(vote)?, chain_id.as_str());
+    Ok(canonical_vote.encoded_len_length_delimited())
+}
+
 /// SignedVote is the union of a canonicalized vote, the signature on
 /// the sign bytes of that vote and the id of the validator who signed it.
 pub struct SignedVote {
@@ -102,6 +138,13 @@ impl SignedVote {
         self.vote.bytes_vec_length_delimited()
     }
 
+    /// Type of the vote, `None` if the canonical vote carries an unknown type.
+    pub fn vote_type(&self) -> Option<Type> {
+        u8::try_from(self.vote.vote_type)
+            .ok()
+            .and_then(Type::from_u8)
+    }
+
     /// Return the actual signature on the canonicalized vote.
     pub fn signature(&self) -> &[u8] {
         self.signature.as_ref()
@@ -1,17 +1,177 @@
-use std::cmp::Ordering;
-use std::ops::Add;
+use std::cell::{Cell, RefCell};
 use std::time::{Duration, SystemTime};
 
-use anomaly::ensure;
+use anomaly::{ensure, fail};
+use sha2::{Digest, Sha256};
 
 use crate::errors::{Error, Kind};
-use crate::types::block::commit::SignedHeader;
+use crate::merkle_tree::{compute_hash_from_aunts, simple_hash_from_byte_vectors};
+use crate::telemetry;
+use crate::types::block::commit::{Commit, LightSignedHeader, SignedHeader};
+use crate::types::block::header::Header as LightHeader;
+use crate::types::block::light_block::LightBlock;
 use crate::types::block::traits::commit::ProvableCommit;
 use crate::types::block::traits::header::Header;
+use crate::types::hash::Hash;
+use crate::types::signature_scheme::SignatureSchemeRegistry;
 use crate::types::traits::trusted::TrustThreshold;
 use crate::types::traits::validator::Validator;
 use crate::types::traits::validator_set::ValidatorSet;
-use crate::types::trusted::TrustedState;
+use crate::types::trusted::{Relationship, TrustThresholdFraction, TrustedState};
+use crate::types::validator::ValidatorLookup;
+use crate::types::validator::{Info, Set};
+use crate::types::{account, chain};
+
+/// Default tolerance for untrusted headers timestamped ahead of the local clock.
+pub const DEFAULT_MAX_CLOCK_DRIFT: Duration = Duration::from_secs(10);
+
+/// Default `VerificationOptions::max_commit_round`: the largest round tendermint can
+/// encode, as it uses an int32.
+pub const DEFAULT_MAX_COMMIT_ROUND: u64 = i32::MAX as u64;
+
+/// Optional behaviour of `verify_single_with_options`.
+/// The default matches `verify_single`.
+#[derive(Clone, Copy)]
+pub struct VerificationOptions<'a> {
+    /// How far the untrusted header's time may be ahead of `now`,
+    /// to tolerate clock drift between the client and the chain.
+    pub max_clock_drift: Duration,
+    /// Called with the computed and the expected hash whenever a hash doesn't match,
+    /// right before the (unchanged) error is returned. Meant for telemetry.
+    pub on_hash_mismatch: Option<&'a dyn Fn(&HashMismatch)>,
+    /// Reject headers whose consensus hash is all zeros.
+    pub strict_consensus_hash: bool,
+    /// Incremented for every signature verified, for performance tuning.
+    /// Only commits overriding `ProvableCommit::voting_power_in_counting` are counted.
+    pub signatures_verified: Option<&'a Cell<u64>>,
+    /// Require exactly one commit signature per validator. When false, commits may carry
+    /// extra signatures, for chains running newer tendermint versions; signers must
+    /// still be part of the validator set.
+    pub strict_count: bool,
+    /// Reject headers from any other chain, for clients dedicated to a single chain.
+    pub expected_chain_id: Option<chain::Id>,
+    /// Reject headers more than this many heights ahead of the trusted header,
+    /// forcing skips to go through intermediate headers.
+    pub max_skip_distance: Option<u64>,
+    /// Reject commits decided at a later round than this one, as they are almost
+    /// certainly malformed. Only checked for commits which carry their round.
+    pub max_commit_round: Option<u64>,
+    /// Reject untrusted headers whose time is further than this before `now`, to detect
+    /// stale data independently of the trusting period.
+    pub max_header_age: Option<Duration>,
+    /// Reject validator sets (and next validator sets) of more validators than this, as
+    /// tendermint caps the active set to far less (e.g. 100 to 175 on most chains): a huge
+    /// set is suspicious and costly to hash and verify.
+    pub max_validators: Option<usize>,
+    /// Appended with every decision taken while verifying, to cross-check the verification
+    /// against the spec. See `verify_single_with_trace`.
+    pub decision_trace: Option<&'a RefCell<DecisionTrace>>,
+    /// Verify the commit signatures with the verifiers registered for the key types of
+    /// the validators, rather than with `Validator::verify_signature`, e.g. to swap in
+    /// another ed25519 implementation (see `SignatureSchemeRegistry::no_alloc`). Only
+    /// commits overriding `ProvableCommit::voting_power_in_with_schemes` use it.
+    pub signature_schemes: Option<&'a SignatureSchemeRegistry>,
+}
+
+impl Default for VerificationOptions<'_> {
+    fn default() -> Self {
+        Self {
+            max_clock_drift: DEFAULT_MAX_CLOCK_DRIFT,
+            on_hash_mismatch: None,
+            strict_consensus_hash: false,
+            signatures_verified: None,
+            strict_count: true,
+            expected_chain_id: None,
+            max_skip_distance: None,
+            max_commit_round: Some(DEFAULT_MAX_COMMIT_ROUND),
+            max_header_age: None,
+            max_validators: None,
+            decision_trace: None,
+            signature_schemes: None,
+        }
+    }
+}
+
+/// Hash which failed to match during validation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MismatchedHash {
+    /// Hash of the validator set vs the header's validators_hash
+    Validators,
+    /// Hash of the next validator set vs the header's next_validators_hash
+    NextValidators,
+    /// Hash of the header vs the hash certified by the commit
+    Header,
+}
+
+/// Reported to `VerificationOptions::on_hash_mismatch`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HashMismatch {
+    /// Which hash didn't match
+    pub hash: MismatchedHash,
+    /// Hash computed from the data being verified
+    pub computed: Hash,
+    /// Hash the header (or commit) says it should be
+    pub expected: Hash,
+}
+
+/// A check passed, or a branch taken, while verifying a header, see `DecisionTrace`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Decision {
+    /// The trusted header is within its trusting period
+    WithinTrustingPeriod,
+    /// The untrusted header is from the chain of `VerificationOptions::expected_chain_id`
+    ExpectedChainId,
+    /// The untrusted header matches its commit, validators and next validators
+    /// (validateSignedHeaderAndVals in the spec)
+    SignedHeaderAndValsValid,
+    /// The untrusted header is more recent than the trusted one, isn't from the future,
+    /// and isn't older than `VerificationOptions::max_header_age`
+    TimeValid,
+    /// Branch taken on the height of the untrusted header relative to the trusted state
+    Relationship(Relationship),
+    /// Adjacent: the validators of the untrusted header are the next validators of the
+    /// trusted header
+    AdjacentValidatorsHash,
+    /// Skip: the untrusted header is within `VerificationOptions::max_skip_distance`
+    SkipDistance,
+    /// Skip: the trusted validators which signed the untrusted header have enough power
+    /// for the trust threshold
+    TrustedOverlap { signed: u64, required: u64 },
+    /// The untrusted validators which signed the commit have more than 2/3 of their power
+    /// (verifyCommitFull in the spec)
+    CommitFull { signed: u64, total: u64 },
+}
+
+/// The decisions taken while verifying a header, in order, for conformance testing
+/// against the light client spec. When the header is rejected, the trace ends with the
+/// last check which passed.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DecisionTrace {
+    decisions: Vec<Decision>,
+}
+
+impl DecisionTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The decisions taken, in order.
+    pub fn decisions(&self) -> &[Decision] {
+        &self.decisions
+    }
+
+    /// Whether the given decision was taken.
+    pub fn contains(&self, decision: &Decision) -> bool {
+        self.decisions.contains(decision)
+    }
+}
+
+// Append the decision to the trace of the options, if any.
+fn record_decision(options: &VerificationOptions<'_>, decision: Decision) {
+    if let Some(trace) = options.decision_trace {
+        trace.borrow_mut().decisions.push(decision);
+    }
+}
 
 /// Verify a single untrusted header against a trusted state.
 /// Ensures our last trusted header hasn't expired yet, and that
@@ -37,20 +197,205 @@ where
     L: TrustThreshold,
     V: Validator,
 {
+    verify_single_with_options(
+        trusted_state,
+        untrusted_sh,
+        untrusted_vals,
+        untrusted_next_vals,
+        trust_threshold,
+        trusting_period,
+        now,
+        &VerificationOptions::default(),
+    )
+}
+
+/// Same as `verify_single`, with the non-default behaviour set in `options`.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_single_with_options<H, C, L, V>(
+    trusted_state: TrustedState<C, H, V>,
+    untrusted_sh: &SignedHeader<C, H>,
+    untrusted_vals: &C::ValidatorSet,
+    untrusted_next_vals: &C::ValidatorSet,
+    trust_threshold: L,
+    trusting_period: Duration,
+    now: SystemTime,
+    options: &VerificationOptions<'_>,
+) -> Result<TrustedState<C, H, V>, Error>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    L: TrustThreshold,
+    V: Validator,
+{
+    telemetry::verification_attempted();
+
     // Fetch the latest state and ensure it hasn't expired.
     let trusted_sh = trusted_state.last_header();
-    is_within_trust_period(trusted_sh.header(), trusting_period, now)?;
+    let result = is_within_trust_period(trusted_sh.header(), trusting_period, now).and_then(|()| {
+        record_decision(options, Decision::WithinTrustingPeriod);
+        verify_single_inner(
+            &trusted_state,
+            untrusted_sh,
+            untrusted_vals,
+            Some(untrusted_next_vals),
+            trust_threshold,
+            now,
+            options,
+        )
+    });
+    telemetry::verification_finished(&result);
+    result?;
 
-    verify_single_inner(
-        &trusted_state,
+    // The untrusted header is now trusted;
+    // return to the caller so they can update the store:
+    Ok(TrustedState::new(
+        untrusted_sh.clone(),
+        untrusted_next_vals.clone(),
+    ))
+}
+
+/// Returned by `verify_single_with_output` for a verified header.
+#[derive(Clone, Debug)]
+pub struct VerificationOutput<C, H, V>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    /// The new trusted state, as returned by `verify_single`
+    pub trusted_state: TrustedState<C, H, V>,
+    /// Addresses of the validators whose signatures the header was trusted on: the
+    /// untrusted validators when adjacent to the trusted header, only the ones which
+    /// are also trusted validators when skipping.
+    pub checked_validators: Vec<account::Id>,
+}
+
+/// Same as `verify_single_with_options`, also returning which validators the header was
+/// trusted on, e.g. to show by whom a header was verified.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_single_with_output<H, C, L, V>(
+    trusted_state: TrustedState<C, H, V>,
+    untrusted_sh: &SignedHeader<C, H>,
+    untrusted_vals: &C::ValidatorSet,
+    untrusted_next_vals: &C::ValidatorSet,
+    trust_threshold: L,
+    trusting_period: Duration,
+    now: SystemTime,
+    options: &VerificationOptions<'_>,
+) -> Result<VerificationOutput<C, H, V>, Error>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    L: TrustThreshold,
+    V: Validator,
+{
+    // Only the trusted validators which are still part of the set count when skipping.
+    // Computed upfront as `trusted_state` is consumed by the verification.
+    let common_vals = match trusted_state.relationship_to(untrusted_sh.header().height()) {
+        Relationship::Skip(_) => Some(trusted_state.validators().intersect(untrusted_vals)),
+        _ => None,
+    };
+    let trusted_state = verify_single_with_options(
+        trusted_state,
         untrusted_sh,
         untrusted_vals,
         untrusted_next_vals,
         trust_threshold,
+        trusting_period,
+        now,
+        options,
+    )?;
+
+    let checked_validators = common_vals
+        .as_ref()
+        .unwrap_or(untrusted_vals)
+        .validators()
+        .iter()
+        .map(|val| val.address())
+        .collect();
+    Ok(VerificationOutput {
+        trusted_state,
+        checked_validators,
+    })
+}
+
+/// Same as `verify_single_with_options`, also returning the trace of the decisions taken,
+/// whether the header was verified or not. Overrides `options.decision_trace`.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn verify_single_with_trace<H, C, L, V>(
+    trusted_state: TrustedState<C, H, V>,
+    untrusted_sh: &SignedHeader<C, H>,
+    untrusted_vals: &C::ValidatorSet,
+    untrusted_next_vals: &C::ValidatorSet,
+    trust_threshold: L,
+    trusting_period: Duration,
+    now: SystemTime,
+    options: &VerificationOptions<'_>,
+) -> (Result<TrustedState<C, H, V>, Error>, DecisionTrace)
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    L: TrustThreshold,
+    V: Validator,
+{
+    let trace = RefCell::new(DecisionTrace::new());
+    let result = verify_single_with_options(
+        trusted_state,
+        untrusted_sh,
+        untrusted_vals,
+        untrusted_next_vals,
+        trust_threshold,
+        trusting_period,
+        now,
+        &VerificationOptions {
+            decision_trace: Some(&trace),
+            ..*options
+        },
+    );
+    (result, trace.into_inner())
+}
+
+/// Same as `verify_single`, loading the validator set of the untrusted header only once
+/// it is needed, for verifiers which can't afford to keep it in memory otherwise.
+///
+/// The set is needed to verify the signatures of the commit, which is required in every
+/// case (adjacent or skip), so `load_vals` is called for every header that can be
+/// verified. It is *not* called when the header is rejected by the checks which only
+/// need the trusted state: an expired trusted state, a header which isn't newer than
+/// the trusted one or is from the future, or an adjacent header whose validators hash
+/// isn't the next validators hash of the trusted header. An error of `load_vals` is
+/// returned as is.
+pub fn verify_single_lazy<H, C, L, V, F>(
+    trusted_state: TrustedState<C, H, V>,
+    untrusted_sh: &SignedHeader<C, H>,
+    load_vals: F,
+    untrusted_next_vals: &C::ValidatorSet,
+    trust_threshold: L,
+    trusting_period: Duration,
+    now: SystemTime,
+) -> Result<TrustedState<C, H, V>, Error>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    L: TrustThreshold,
+    V: Validator,
+    F: FnOnce() -> Result<C::ValidatorSet, Error>,
+{
+    let options = VerificationOptions::default();
+    is_within_trust_period(trusted_state.last_header().header(), trusting_period, now)?;
+    check_header_against_trusted(&trusted_state, untrusted_sh.header(), now, &options)?;
+
+    let untrusted_vals = load_vals()?;
+    verify_single_inner(
+        &trusted_state,
+        untrusted_sh,
+        &untrusted_vals,
+        Some(untrusted_next_vals),
+        trust_threshold,
+        now,
+        &options,
     )?;
 
-    // The untrusted header is now trusted;
-    // return to the caller so they can update the store:
     Ok(TrustedState::new(
         untrusted_sh.clone(),
         untrusted_next_vals.clone(),
@@ -66,19 +411,619 @@ where
     C: ProvableCommit<V>,
     V: Validator,
 {
-    let header = untrusted_sh.header();
-    let commit = untrusted_sh.commit();
+    verify_self_contained(
+        untrusted_sh,
+        untrusted_vals,
+        TrustThresholdFraction::default(),
+    )
+}
 
-    validate(header, commit, untrusted_vals, None)?;
+/// Verify a signed header shipped along with its own validator set, without any trusted
+/// state: the commit must be for the header, the validators must hash to the header's
+/// `validators_hash`, and the signers must hold +2/3 of their voting power as well as
+/// more than `trust_threshold` of it. Thresholds up to 2/3 thus add nothing to the +2/3
+/// any commit needs, while 1/1 (`TrustThresholdFraction::all`) requires all of them.
+///
+/// NOTE: this only shows the header is consistent with the given validators,
+/// not that they are the validators of the chain.
+pub fn verify_self_contained<H, C, L, V>(
+    sh: &SignedHeader<C, H>,
+    vals: &C::ValidatorSet,
+    trust_threshold: L,
+) -> Result<(), Error>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    L: TrustThreshold,
+    V: Validator,
+{
+    let header = sh.header();
+    let commit = sh.commit();
+    let options = VerificationOptions::default();
 
-    verify_commit_full(untrusted_vals, header, commit)?;
+    validate(header, commit, vals, None, &options)?;
 
+    let total_power = vals.total_power();
+    let signed_power = verify_commit_full(vals, header, commit, &options)?;
+    let minimum_power = trust_threshold.minimum_power_to_be_trusted(total_power);
+    if signed_power < minimum_power {
+        return Err(Kind::InsufficientSignedVotingPower {
+            total: total_power,
+            signed: signed_power,
+            trust_threshold: format!("{:?}", trust_threshold),
+            power_gap: power_gap(signed_power, minimum_power),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Validate the first header of a chain (height 1) along with its validator set, the same
+/// way as `verify_self_contained`, and ensure it is shaped like the start of a chain:
+/// there is no previous block, so it has neither a last block id nor a last commit hash.
+pub fn verify_genesis_header<V, L>(
+    genesis_sh: &LightSignedHeader,
+    vals: &Set<V>,
+    trust_threshold: L,
+) -> Result<(), Error>
+where
+    V: Validator,
+    L: TrustThreshold,
+{
+    let header = genesis_sh.header();
+    ensure!(
+        header.height.value() == 1,
+        Kind::InvalidGenesisHeader,
+        "height is {}, expected 1",
+        header.height
+    );
+    ensure!(
+        header.last_block_id.is_none(),
+        Kind::InvalidGenesisHeader,
+        "the first block can't refer to a previous block"
+    );
+    ensure!(
+        header.last_commit_hash.is_none(),
+        Kind::InvalidGenesisHeader,
+        "the first block can't carry a last commit"
+    );
+
+    verify_self_contained::<_, _, _, V>(genesis_sh, vals, trust_threshold)
+}
+
+// Ensure the commit is for the header and has a signature for every validator of
+// the lookup, then sum the power of the validators which correctly signed it.
+fn signed_power_with_lookup<V>(
+    header: &LightHeader,
+    commit: &Commit,
+    lookup: &ValidatorLookup<V>,
+) -> Result<u64, Error>
+where
+    V: Validator,
+{
+    // ensure the header matches the commit
+    let header_hash = header.try_hash()?;
+    if header_hash != commit.block_id.hash {
+        return Err(Kind::InvalidCommitValue {
+            header_hash,
+            commit_hash: commit.block_id.hash,
+        }
+        .into());
+    }
+
+    ensure!(
+        commit.signatures.len() == lookup.number_of_validators(),
+        Kind::ImplementationSpecific,
+        "commit signatures count: {} doesn't match validators count: {}",
+        commit.signatures.len(),
+        lookup.number_of_validators()
+    );
+
+    commit.voting_power_in_with(header.chain_id, lookup)
+}
+
+/// Verify many `(header, commit)` pairs of `chain_id` against the same validator set in
+/// parallel, e.g. when backfilling a range of heights where the set doesn't change.
+///
+/// Each header must have `vals` as its validators, and its commit must be signed by at
+/// least `trust_threshold` of their voting power (`TrustThresholdFraction::default()`
+/// for the usual +2/3). The result at index i is the outcome for `pairs[i]`.
+#[cfg(feature = "parallel")]
+pub fn verify_commits_parallel<V, L>(
+    pairs: &[(LightHeader, Commit)],
+    vals: &Set<V>,
+    chain_id: chain::Id,
+    trust_threshold: L,
+) -> Vec<Result<(), Error>>
+where
+    V: Validator + Send + Sync,
+    L: TrustThreshold + Sync,
+{
+    use rayon::prelude::*;
+
+    let vals_hash = vals.hash();
+    let lookup = vals.lookup_index();
+    pairs
+        .par_iter()
+        .map(|(header, commit)| {
+            ensure!(
+                header.chain_id == chain_id,
+                Kind::ImplementationSpecific,
+                "header is for chain {}, expected {}",
+                header.chain_id,
+                chain_id
+            );
+            if header.validators_hash != vals_hash {
+                return Err(Kind::InvalidValidatorSet {
+                    header_val_hash: header.validators_hash,
+                    expected_val_hash: vals_hash,
+                }
+                .into());
+            }
+
+            let total_power = lookup.total_power();
+            let signed_power = signed_power_with_lookup(header, commit, &lookup)?;
+            if !trust_threshold.is_enough_power(signed_power, total_power) {
+                return Err(Kind::InsufficientSignedVotingPower {
+                    total: total_power,
+                    signed: signed_power,
+                    trust_threshold: format!("{:?}", trust_threshold),
+                    power_gap: power_gap(
+                        signed_power,
+                        trust_threshold.minimum_power_to_be_trusted(total_power),
+                    ),
+                }
+                .into());
+            }
+            Ok(())
+        })
+        .collect()
+}
+
+/// Same as `verify_single`, but the untrusted header must also hash to `expected_hash`,
+/// e.g. a block hash obtained from a checkpoint service the client trusts.
+/// The hash is checked first, so a wrong header is rejected before any signature
+/// is verified.
+#[allow(clippy::too_many_arguments)]
+pub fn verify_single_expecting<H, C, L, V>(
+    trusted_state: TrustedState<C, H, V>,
+    untrusted_sh: &SignedHeader<C, H>,
+    untrusted_vals: &C::ValidatorSet,
+    untrusted_next_vals: &C::ValidatorSet,
+    expected_hash: Hash,
+    trust_threshold: L,
+    trusting_period: Duration,
+    now: SystemTime,
+) -> Result<TrustedState<C, H, V>, Error>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    L: TrustThreshold,
+    V: Validator,
+{
+    let header_hash = untrusted_sh.header().try_hash()?;
+    if header_hash != expected_hash {
+        return Err(Kind::UnexpectedHeaderHash {
+            header_hash,
+            expected_hash,
+        }
+        .into());
+    }
+
+    verify_single(
+        trusted_state,
+        untrusted_sh,
+        untrusted_vals,
+        untrusted_next_vals,
+        trust_threshold,
+        trusting_period,
+        now,
+    )
+}
+
+/// Verify a single untrusted header against a trusted state, for sources which don't
+/// provide the next validator set. The header and its commit are checked exactly like in
+/// `verify_single`, except for `next_validators_hash`.
+///
+/// As the next validators are unknown, the header can't be used to verify the ones
+/// after it: no new trusted state is returned, the caller's trusted state stays as is.
+pub fn verify_single_without_next_vals<H, C, L, V>(
+    trusted_state: &TrustedState<C, H, V>,
+    untrusted_sh: &SignedHeader<C, H>,
+    untrusted_vals: &C::ValidatorSet,
+    trust_threshold: L,
+    trusting_period: Duration,
+    now: SystemTime,
+) -> Result<(), Error>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    L: TrustThreshold,
+    V: Validator,
+{
+    is_within_trust_period(trusted_state.last_header().header(), trusting_period, now)?;
+
+    verify_single_inner(
+        trusted_state,
+        untrusted_sh,
+        untrusted_vals,
+        None,
+        trust_threshold,
+        now,
+        &VerificationOptions::default(),
+    )
+}
+
+/// Same as `verify_single` for the concrete light client types, taking the validators
+/// as plain slices (e.g. straight from an RPC response) instead of validator sets.
+/// The sets are built with `Set::new`, which sorts them and drops duplicates.
+pub fn verify_single_from_slices<L, V>(
+    trusted_state: TrustedState<Commit, LightHeader, V>,
+    untrusted_sh: &LightSignedHeader,
+    untrusted_vals: &[V],
+    untrusted_next_vals: &[V],
+    trust_threshold: L,
+    trusting_period: Duration,
+    now: SystemTime,
+) -> Result<TrustedState<Commit, LightHeader, V>, Error>
+where
+    L: TrustThreshold,
+    V: Validator,
+{
+    verify_single(
+        trusted_state,
+        untrusted_sh,
+        &Set::new(untrusted_vals.to_vec()),
+        &Set::new(untrusted_next_vals.to_vec()),
+        trust_threshold,
+        trusting_period,
+        now,
+    )
+}
+
+/// Same as `verify_single`, taking the untrusted header, its validators and next
+/// validators bundled as a light block.
+pub fn verify_single_light_block<H, C, L, V>(
+    trusted_state: TrustedState<C, H, V>,
+    light_block: &LightBlock<C, H, V>,
+    trust_threshold: L,
+    trusting_period: Duration,
+    now: SystemTime,
+) -> Result<TrustedState<C, H, V>, Error>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    L: TrustThreshold,
+    V: Validator,
+{
+    verify_single(
+        trusted_state,
+        light_block.signed_header(),
+        light_block.validators(),
+        light_block.next_validators(),
+        trust_threshold,
+        trusting_period,
+        now,
+    )
+}
+
+/// Check a validator set fetched for the height after the trusted header against the
+/// `next_validators_hash` announced by that header, before relying on it (e.g. as the
+/// validators of the next header to verify).
+pub fn verify_next_validators<H, C, V>(
+    trusted_state: &TrustedState<C, H, V>,
+    fetched_next_vals: &C::ValidatorSet,
+) -> Result<(), Error>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    let header_next_val_hash = trusted_state.last_header().header().next_validators_hash();
+    let expected_next_val_hash = fetched_next_vals.hash();
+    if header_next_val_hash != expected_next_val_hash {
+        return Err(Kind::InvalidNextValidatorSet {
+            header_next_val_hash,
+            expected_next_val_hash,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// The candidate validator set which is the one of the header, i.e. hashes to its
+/// `validators_hash`, e.g. to pick the right one among sets fetched around its height
+/// when unsure which height each is for. None if no candidate matches.
+pub fn match_validator_set<'a, H, V>(header: &H, candidates: &'a [Set<V>]) -> Option<&'a Set<V>>
+where
+    H: Header,
+    V: Validator,
+{
+    let validators_hash = header.validators_hash();
+    candidates
+        .iter()
+        .find(|candidate| candidate.hash() == validators_hash)
+}
+
+/// Whether moving the client from the trusted header to the untrusted one changes its
+/// validators, in which case an update (e.g. an IBC `MsgUpdateClient`) has to carry the
+/// new validator sets: either the untrusted header isn't signed by the next validators
+/// the trusted header announced, or it announces next validators of its own.
+pub fn requires_valset_update<H>(trusted_header: &H, untrusted_header: &H) -> bool
+where
+    H: Header,
+{
+    untrusted_header.validators_hash() != trusted_header.next_validators_hash()
+        || untrusted_header.next_validators_hash() != untrusted_header.validators_hash()
+}
+
+/// Ensure the header was produced under the block protocol version we expect.
+/// A mismatch usually means the chain went through a protocol upgrade and the
+/// client needs to be updated before it can keep following it.
+pub fn verify_version(header: &LightHeader, expected_block_version: u64) -> Result<(), Error> {
+    if header.version.block != expected_block_version {
+        return Err(Kind::UnexpectedBlockVersion {
+            expected: expected_block_version,
+            got: header.version.block,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Ensure the header's `data_hash` is the merkle root of the block's transactions, i.e.
+/// that `txs` are exactly the transactions of the block, in order. As in tendermint, the
+/// leaves are the (SHA256) hashes of the transactions, and a block without transactions
+/// has no data hash.
+pub fn verify_data_hash(header: &LightHeader, txs: &[Vec<u8>]) -> Result<(), Error> {
+    let computed_data_hash = if txs.is_empty() {
+        None
+    } else {
+        let tx_hashes = txs.iter().map(|tx| Sha256::digest(tx).to_vec()).collect();
+        Some(Hash::Sha256(simple_hash_from_byte_vectors(tx_hashes)))
+    };
+    if header.data_hash != computed_data_hash {
+        return Err(Kind::InvalidDataHash {
+            header_data_hash: header.data_hash,
+            computed_data_hash,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Ensure `validator` is part of the validator set committed by `header`, without the
+/// whole set: `proof` is the inclusion proof of the validator (see `Set::inclusion_proof`),
+/// `index` its position in the set (in canonical order) and `total` the number of
+/// validators of the set, all of which determine the shape of the merkle tree.
+pub fn verify_validator_in_set<H>(
+    validator: &Info,
+    proof: Vec<Hash>,
+    index: usize,
+    total: usize,
+    header: &H,
+) -> Result<(), Error>
+where
+    H: Header,
+{
+    let to_bytes = |hash: Hash| match hash {
+        Hash::Sha256(bytes) => bytes,
+    };
+    let aunts: Vec<_> = proof.into_iter().map(to_bytes).collect();
+    let root = compute_hash_from_aunts(index, total, to_bytes(validator.leaf_hash()), &aunts);
+    if root.map(Hash::Sha256) != Some(header.validators_hash()) {
+        return Err(Kind::InvalidValidatorProof { index, total }.into());
+    }
+    Ok(())
+}
+
+/// Verify that +2/3 of the validators in `lookup` correctly signed `commit` for `header`.
+///
+/// This is a lower-level alternative to `verify_single` for callers which pay for every
+/// operation (e.g. IBC handlers metered by gas): the validator lookup is built once with
+/// `Set::lookup_index` and kept around, and neither validator set is hashed again.
+/// Only the commit is checked, so the caller must uphold that:
+/// - `lookup` was built from the validator set whose hash is `header.validators_hash`,
+///   e.g. the next validators of the trusted state when verifying the adjacent header;
+/// - the trusted state has not expired;
+/// - `header` is at a greater height and a later time than the trusted header.
+///
+/// If any of these doesn't hold, `Ok` means nothing.
+pub fn verify_commit_with_lookup<V>(
+    header: &LightHeader,
+    commit: &Commit,
+    lookup: &ValidatorLookup<V>,
+) -> Result<(), Error>
+where
+    V: Validator,
+{
+    let total_power = lookup.total_power();
+    let signed_power = signed_power_with_lookup(header, commit, lookup)?;
+
+    // check the signers account for +2/3 of the voting power
+    if signed_power * 3 <= total_power * 2 {
+        return Err(Kind::InvalidCommit {
+            total: total_power,
+            signed: signed_power,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Additional voting power needed for `signed` to reach `minimum_required`,
+/// 0 if it already does. Bisecting clients can use the gap reported in
+/// `Kind::InsufficientSignedVotingPower` to pick the next intermediate height.
+pub fn power_gap(signed: u64, minimum_required: u64) -> u64 {
+    minimum_required.saturating_sub(signed)
+}
+
+/// Fraction of the total voting power of `vals` which correctly signed the commit,
+/// between 0 and 1. Meant for reporting on the health of a network after verification;
+/// an empty (zero power) set has a fraction of 0.
+pub fn signed_fraction<C, V>(
+    commit: &C,
+    chain_id: chain::Id,
+    vals: &C::ValidatorSet,
+) -> Result<f64, Error>
+where
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    let total_power = vals.total_power();
+    if total_power == 0 {
+        return Ok(0.0);
+    }
+    let signed_power = commit.voting_power_in(chain_id, vals)?;
+    Ok(signed_power as f64 / total_power as f64)
+}
+
+/// Whether a single validator of `vals` holds at least `trust_threshold` of the voting
+/// power, i.e. could make a light client using that threshold trust a header on its own
+/// signature. Purely informational: a centralization risk to keep in mind when choosing
+/// witnesses, such sets are verified like any other.
+pub fn is_power_concentrated<V, L>(vals: &Set<V>, trust_threshold: L) -> bool
+where
+    V: Validator,
+    L: TrustThreshold,
+{
+    let total_power = vals.total_power();
+    vals.validators()
+        .iter()
+        .any(|val| trust_threshold.is_enough_power(val.power(), total_power))
+}
+
+/// Check that each of the given consecutive validator sets keeps at least
+/// `trust_threshold` of the voting power of the previous one, i.e. that the chain never
+/// switched validators faster than a light client skipping over the change could follow.
+/// On failure, returns the index of the first set which doesn't overlap enough with its
+/// predecessor along with the error.
+pub fn verify_valset_continuity<V, L>(
+    sets: &[Set<V>],
+    trust_threshold: L,
+) -> Result<(), (usize, Error)>
+where
+    V: Validator,
+    L: TrustThreshold,
+{
+    for (i, pair) in sets.windows(2).enumerate() {
+        let (previous, next) = (&pair[0], &pair[1]);
+        // the intersection keeps the voting power the validators had in the previous set
+        let overlap = previous.intersect(next).total_power();
+        let total = previous.total_power();
+        if !trust_threshold.is_enough_power(overlap, total) {
+            return Err((
+                i + 1,
+                Kind::InsufficientValidatorSetOverlap {
+                    overlap,
+                    total,
+                    trust_threshold: format!("{:?}", trust_threshold),
+                }
+                .into(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Turnover of the voting power across the given consecutive validator sets, for
+/// monitoring: the power of the validators which left or entered the set at each step,
+/// over the power of both sets, summed over all steps. 0 when the validators never
+/// changed, 1 when every step replaced all of them. Power changes of the validators which
+/// stay in the set aren't churn. Fewer than two sets have no churn.
+pub fn valset_churn<V>(sets: &[Set<V>]) -> f64
+where
+    V: Validator,
+{
+    let mut churned_power = 0u128;
+    let mut total_power = 0u128;
+    for pair in sets.windows(2) {
+        let (churned, total) = power_churned_between::<V, _>(&pair[0], &pair[1]);
+        churned_power += churned;
+        total_power += total;
+    }
+    if total_power == 0 {
+        return 0.0;
+    }
+    churned_power as f64 / total_power as f64
+}
+
+// The power of the validators which left or entered the set between `previous` and
+// `next`, and the power of both sets.
+pub(crate) fn power_churned_between<V, S>(previous: &S, next: &S) -> (u128, u128)
+where
+    V: Validator,
+    S: ValidatorSet<V>,
+{
+    // each intersection keeps the power the validators have in the set it's taken from
+    let left = previous.total_power() - previous.intersect(next).total_power();
+    let entered = next.total_power() - next.intersect(previous).total_power();
+    (
+        u128::from(left) + u128::from(entered),
+        u128::from(previous.total_power()) + u128::from(next.total_power()),
+    )
+}
+
+/// Check that trusted states loaded from an untrusted medium (e.g. the disk) form a
+/// consistent chain before relying on any of them: each validator set is the one
+/// announced by its own header (see `TrustedState::validate_self`), heights and times
+/// strictly increase, and a header directly following the previous one is signed by the
+/// validators that one announced. On failure, returns the index of the first state which
+/// doesn't fit along with the error.
+///
+/// NOTE: this doesn't verify any commit, only that the states agree with each other.
+pub fn verify_trusted_state_chain<H, C, V>(
+    states: &[TrustedState<C, H, V>],
+) -> Result<(), (usize, Error)>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    for (i, state) in states.iter().enumerate() {
+        state.validate_self().map_err(|e| (i, e))?;
+        if i == 0 {
+            continue;
+        }
+        let previous_state = &states[i - 1];
+        let previous = previous_state.last_header().header();
+        let header = state.last_header().header();
+        match previous_state.relationship_to(header.height()) {
+            Relationship::Same | Relationship::Backward => {
+                return Err((
+                    i,
+                    Kind::NonIncreasingHeight {
+                        got: header.height(),
+                        expected: previous.height().saturating_add(1),
+                    }
+                    .into(),
+                ))
+            }
+            Relationship::Adjacent => {
+                if header.validators_hash() != previous.next_validators_hash() {
+                    return Err((
+                        i,
+                        Kind::InvalidValidatorSet {
+                            header_val_hash: header.validators_hash(),
+                            expected_val_hash: previous.next_validators_hash(),
+                        }
+                        .into(),
+                    ));
+                }
+            }
+            Relationship::Skip(_) => {}
+        }
+        if header.bft_time().into() <= previous.bft_time().into() {
+            return Err((i, Kind::NonIncreasingTime.into()));
+        }
+    }
     Ok(())
 }
 
 /// Returns an error if the header has expired according to the given
 /// trusting_period and current time. If so, the verifier must be reset subjectively.
-fn is_within_trust_period<H>(
+pub(crate) fn is_within_trust_period<H>(
     last_header: &H,
     trusting_period: Duration,
     now: SystemTime,
@@ -87,7 +1032,9 @@ where
     H: Header,
 {
     let header_time: SystemTime = last_header.bft_time().into();
-    let expires_at = header_time.add(trusting_period);
+    let expires_at = header_time
+        .checked_add(trusting_period)
+        .ok_or_else(|| Error::from(Kind::OutOfRange))?;
     // Ensure now > expires_at.
     if expires_at <= now {
         return Err(Kind::Expired {
@@ -97,13 +1044,9 @@ where
         .into());
     }
     // Also make sure the header is not after now.
-    ensure!(
-        header_time <= now,
-        Kind::DurationOutOfRange,
-        "header time: ({:?}) > now: ({:?})",
-        header_time,
-        now
-    );
+    if header_time > now {
+        return Err(Kind::TrustedHeaderFromFuture { header_time, now }.into());
+    }
     Ok(())
 }
 
@@ -113,12 +1056,15 @@ where
 // and hence it's possible to use it incorrectly.
 // If trusted_state is not expired and this returns Ok, the
 // untrusted_sh and untrusted_next_vals can be considered trusted.
+// Without untrusted_next_vals only the untrusted_sh is verified.
 fn verify_single_inner<H, C, L, V>(
     trusted_state: &TrustedState<C, H, V>,
     untrusted_sh: &SignedHeader<C, H>,
     untrusted_vals: &C::ValidatorSet,
-    untrusted_next_vals: &C::ValidatorSet,
+    untrusted_next_vals: Option<&C::ValidatorSet>,
     trust_threshold: L,
+    now: SystemTime,
+    options: &VerificationOptions<'_>,
 ) -> Result<(), Error>
 where
     H: Header,
@@ -126,38 +1072,148 @@ where
     L: TrustThreshold,
     V: Validator,
 {
-    // validate the untrusted header against its commit, vals, and next_vals
     let untrusted_header = untrusted_sh.header();
     let untrusted_commit = untrusted_sh.commit();
 
+    // ensure the untrusted header is from the chain we're locked to, if any
+    if let Some(expected) = options.expected_chain_id {
+        if untrusted_header.chain_id() != expected {
+            return Err(Kind::ChainIdMismatch {
+                got: untrusted_header.chain_id(),
+                expected,
+            }
+            .into());
+        }
+        record_decision(options, Decision::ExpectedChainId);
+    }
+
+    // validate the untrusted header against its commit, vals, and next_vals
+
     validate(
         untrusted_sh.header(),
         untrusted_sh.commit(),
         untrusted_vals,
-        Some(untrusted_next_vals),
+        untrusted_next_vals,
+        options,
     )?;
+    record_decision(options, Decision::SignedHeaderAndValsValid);
 
+    // ensure the untrusted header is newer than the trusted one and, if adjacent,
+    // signed by the validators the trusted header announced
+    check_header_against_trusted(trusted_state, untrusted_header, now, options)?;
+
+    // if its >+1, ensure we can skip to it
+    if let Relationship::Skip(_) = trusted_state.relationship_to(untrusted_header.height()) {
+        let trusted_validators = trusted_state.validators();
+        // We need to intersect trusted validators with untrusted validator because
+        // only if our previously trusted validators are part of validator set for this
+        // height, its vote can be considered valid.
+        let common_vals = trusted_validators.intersect(untrusted_vals);
+
+        // Minimum trusted voting power required to consider this header as trusted
+        let minimum_trusted_voting_power_required =
+            trust_threshold.minimum_power_to_be_trusted(trusted_validators.total_power());
+
+        // Sum of voting power of validators who has legitimately signed this header
+        let signed_power = voting_power_in(
+            untrusted_commit,
+            untrusted_header.chain_id(),
+            &common_vals,
+            options,
+        )?;
+
+        // check the signers' total voting powers are greater than or equal to minimum
+        // trusted voting power required.
+        if signed_power < minimum_trusted_voting_power_required {
+            return Err(Kind::InsufficientSignedVotingPower {
+                total: trusted_validators.total_power(),
+                signed: signed_power,
+                trust_threshold: format!("{:?}", trust_threshold),
+                power_gap: power_gap(signed_power, minimum_trusted_voting_power_required),
+            }
+            .into());
+        }
+        record_decision(
+            options,
+            Decision::TrustedOverlap {
+                signed: signed_power,
+                required: minimum_trusted_voting_power_required,
+            },
+        );
+    }
+
+    // All validation passed successfully. Verify the validators correctly committed the block.
+    verify_commit_full(untrusted_vals, untrusted_header, untrusted_commit, options)?;
+    Ok(())
+}
+
+// Checks of the untrusted header against the trusted state which don't need the
+// untrusted validators: its time and height, the validators hash of an adjacent header
+// and the distance of a skip.
+fn check_header_against_trusted<H, C, V>(
+    trusted_state: &TrustedState<C, H, V>,
+    untrusted_header: &H,
+    now: SystemTime,
+    options: &VerificationOptions<'_>,
+) -> Result<(), Error>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    V: Validator,
+{
     // ensure the new height is higher.
     // if its +1, ensure the vals are correct.
-    // if its >+1, ensure we can skip to it
+    // if its >+1, ensure it isn't too far
     let trusted_header = trusted_state.last_header().header();
     let trusted_height = trusted_header.height();
-    let untrusted_height = untrusted_sh.header().height();
+    let untrusted_height = untrusted_header.height();
 
     // ensure the untrusted_header.bft_time() > trusted_header.bft_time()
     if untrusted_header.bft_time().into() <= trusted_header.bft_time().into() {
         return Err(Kind::NonIncreasingTime.into());
     }
 
-    match untrusted_height.cmp(&trusted_height.checked_add(1).expect("height overflow")) {
-        Ordering::Less => {
+    // ensure the untrusted header is not from the future
+    let untrusted_time: SystemTime = untrusted_header.bft_time().into();
+    let latest_time = now
+        .checked_add(options.max_clock_drift)
+        .ok_or_else(|| Error::from(Kind::OutOfRange))?;
+    if untrusted_time > latest_time {
+        return Err(Kind::UntrustedHeaderFromFuture {
+            header_time: untrusted_time,
+            now,
+            max_clock_drift: options.max_clock_drift,
+        }
+        .into());
+    }
+
+    // ensure the untrusted header isn't stale
+    if let Some(max_header_age) = options.max_header_age {
+        let stale = now
+            .duration_since(untrusted_time)
+            .is_ok_and(|age| age > max_header_age);
+        if stale {
+            return Err(Kind::StaleHeader {
+                header_time: untrusted_time,
+                now,
+                max_header_age,
+            }
+            .into());
+        }
+    }
+    record_decision(options, Decision::TimeValid);
+
+    let relationship = trusted_state.relationship_to(untrusted_height);
+    record_decision(options, Decision::Relationship(relationship));
+    match relationship {
+        Relationship::Same | Relationship::Backward => {
             return Err(Kind::NonIncreasingHeight {
                 got: untrusted_height,
-                expected: trusted_height + 1,
+                expected: trusted_height.saturating_add(1),
             }
             .into())
         }
-        Ordering::Equal => {
+        Relationship::Adjacent => {
             let trusted_vals_hash = trusted_header.next_validators_hash();
             let untrusted_vals_hash = untrusted_header.validators_hash();
             if trusted_vals_hash != untrusted_vals_hash {
@@ -167,37 +1223,35 @@ where
                 }
                 .into());
             }
+            // ensure it follows the trusted block, when both name it
+            let trusted_block_id = trusted_state.last_header().commit().certified_block_id();
+            if let (Some(header_last_block_hash), Some(trusted_block_id)) =
+                (untrusted_header.last_block_hash(), trusted_block_id)
+            {
+                if header_last_block_hash != trusted_block_id.hash {
+                    return Err(Kind::InvalidLastBlockId {
+                        header_last_block_hash,
+                        trusted_block_hash: trusted_block_id.hash,
+                    }
+                    .into());
+                }
+            }
+            record_decision(options, Decision::AdjacentValidatorsHash);
         }
-        Ordering::Greater => {
-            let trusted_validators = trusted_state.validators();
-            // We need to intersect trusted validators with untrusted validator because
-            // only if our previously trusted validators are part of validator set for this
-            // height, its vote can be considered valid.
-            let common_vals = trusted_validators.intersect(untrusted_vals);
-
-            // Minimum trusted voting power required to consider this header as trusted
-            let minimum_trusted_voting_power_required =
-                trust_threshold.minimum_power_to_be_trusted(trusted_validators.total_power());
-
-            // Sum of voting power of validators who has legitimately signed this header
-            let signed_power =
-                untrusted_commit.voting_power_in(untrusted_header.chain_id(), &common_vals)?;
-
-            // check the signers' total voting powers are greater than or equal to minimum
-            // trusted voting power required.
-            if signed_power < minimum_trusted_voting_power_required {
-                return Err(Kind::InsufficientSignedVotingPower {
-                    total: trusted_validators.total_power(),
-                    signed: signed_power,
-                    trust_threshold: format!("{:?}", trust_threshold),
+        Relationship::Skip(distance) => {
+            if let Some(max_skip_distance) = options.max_skip_distance {
+                if distance > max_skip_distance {
+                    return Err(Kind::SkipTooFar {
+                        distance,
+                        max_skip_distance,
+                    }
+                    .into());
                 }
-                .into());
             }
+            record_decision(options, Decision::SkipDistance);
         }
     }
-
-    // All validation passed successfully. Verify the validators correctly committed the block.
-    verify_commit_full(untrusted_vals, untrusted_header, untrusted_commit)
+    Ok(())
 }
 
 /// Validate the validators, next validators, against the signed header.
@@ -207,59 +1261,176 @@ fn validate<C, H, V>(
     commit: &C,
     vals: &C::ValidatorSet,
     possible_next_vals: Option<&C::ValidatorSet>,
+    options: &VerificationOptions<'_>,
 ) -> Result<(), Error>
 where
     C: ProvableCommit<V>,
     H: Header,
     V: Validator,
 {
+    // ensure the sets are small enough to be worth hashing
+    if let Some(max_validators) = options.max_validators {
+        for set in std::iter::once(vals).chain(possible_next_vals) {
+            if set.number_of_validators() > max_validators {
+                return Err(Kind::TooManyValidators {
+                    count: set.number_of_validators(),
+                    max_validators,
+                }
+                .into());
+            }
+        }
+    }
+
+    let report_mismatch = |hash, computed, expected| {
+        if let Some(on_hash_mismatch) = options.on_hash_mismatch {
+            on_hash_mismatch(&HashMismatch {
+                hash,
+                computed,
+                expected,
+            });
+        }
+    };
+
     // ensure the header validator hashes match the given validators
-    if header.validators_hash() != vals.hash() {
-        return Err(Kind::InvalidValidatorSet {
+    let vals_hash = vals.hash();
+    if header.validators_hash() != vals_hash {
+        report_mismatch(
+            MismatchedHash::Validators,
+            vals_hash,
+            header.validators_hash(),
+        );
+        let kind = Kind::InvalidValidatorSet {
             header_val_hash: header.validators_hash(),
-            expected_val_hash: vals.hash(),
+            expected_val_hash: vals_hash,
+        };
+        // a set fetched one height too late is the one announced as next validators
+        if vals_hash == header.next_validators_hash() {
+            fail!(
+                kind,
+                "validator set may be from the wrong height (it matches the header's next validators, was it fetched at height {} instead of {}?)",
+                header.height().saturating_add(1),
+                header.height()
+            );
         }
-        .into());
+        if has_unhashable_keys(vals) {
+            fail!(kind, "{}", UNHASHABLE_KEYS);
+        }
+        return Err(kind.into());
     }
 
-    if possible_next_vals.is_some() {
-        let next_vals = possible_next_vals.unwrap();
-        if header.next_validators_hash() != next_vals.hash() {
-            return Err(Kind::InvalidNextValidatorSet {
+    if let Some(next_vals) = possible_next_vals {
+        let next_vals_hash = next_vals.hash();
+        if header.next_validators_hash() != next_vals_hash {
+            report_mismatch(
+                MismatchedHash::NextValidators,
+                next_vals_hash,
+                header.next_validators_hash(),
+            );
+            let kind = Kind::InvalidNextValidatorSet {
                 header_next_val_hash: header.next_validators_hash(),
-                expected_next_val_hash: next_vals.hash(),
+                expected_next_val_hash: next_vals_hash,
+            };
+            // a set fetched one height too early is the one of the header itself
+            if next_vals_hash == header.validators_hash() {
+                fail!(
+                    kind,
+                    "next validator set may be from the wrong height (it matches the header's validators, was it fetched at height {} instead of {}?)",
+                    header.height(),
+                    header.height().saturating_add(1)
+                );
+            }
+            if has_unhashable_keys(next_vals) {
+                fail!(kind, "{}", UNHASHABLE_KEYS);
+            }
+            return Err(kind.into());
+        }
+    }
+
+    if options.strict_consensus_hash {
+        if let Some(consensus_hash) = header.consensus_hash() {
+            if consensus_hash.is_zero() {
+                return Err(Kind::ZeroConsensusHash.into());
             }
-            .into());
         }
     }
 
     // ensure the header matches the commit
-    if header.hash() != commit.header_hash() {
+    let header_hash = header.try_hash()?;
+    if header_hash != commit.header_hash() {
+        report_mismatch(MismatchedHash::Header, header_hash, commit.header_hash());
         return Err(Kind::InvalidCommitValue {
-            header_hash: header.hash(),
+            header_hash,
             commit_hash: commit.header_hash(),
         }
         .into());
     }
 
+    if let (Some(max_round), Some(round)) = (options.max_commit_round, commit.round()) {
+        ensure!(
+            round <= max_round,
+            Kind::OutOfRange,
+            "commit round {} is above {}",
+            round,
+            max_round
+        );
+    }
+
     // additional implementation specific validation:
-    commit.validate(vals)?;
+    commit.validate_with(vals, options.strict_count)?;
 
     Ok(())
 }
 
-/// Verify that +2/3 of the correct validator set signed this commit.
+// NOTE: until the hash of validator sets supports every key type,
+// a mismatch is expected for sets with other keys than ed25519.
+const UNHASHABLE_KEYS: &str = "validator set has non-ed25519 keys, which aren't hashed like tendermint does yet, so the mismatch may come from the key types rather than the validators";
+
+fn has_unhashable_keys<S, V>(vals: &S) -> bool
+where
+    S: ValidatorSet<V>,
+    V: Validator,
+{
+    vals.validators().iter().any(|v| !v.has_hashable_key())
+}
+
+// `commit.voting_power_in`, counting signature verifications if the options ask for it.
+fn voting_power_in<C, V>(
+    commit: &C,
+    chain_id: chain::Id,
+    vals: &C::ValidatorSet,
+    options: &VerificationOptions<'_>,
+) -> Result<u64, Error>
+where
+    C: ProvableCommit<V>,
+    V: Validator,
+{
+    match (options.signature_schemes, options.signatures_verified) {
+        (Some(schemes), signatures_verified) => {
+            commit.voting_power_in_with_schemes(chain_id, vals, schemes, signatures_verified)
+        }
+        (None, Some(count)) => commit.voting_power_in_counting(chain_id, vals, count),
+        (None, None) => commit.voting_power_in(chain_id, vals),
+    }
+}
+
+/// Verify that +2/3 of the correct validator set signed this commit, returning the
+/// power of the signers.
 /// NOTE: These validators are expected to be the correct validators for the commit,
 /// but since we're using voting_power_in, we can't actually detect if there's
 /// votes from validators not in the set.
-fn verify_commit_full<H, C, V>(vals: &C::ValidatorSet, header: &H, commit: &C) -> Result<(), Error>
+fn verify_commit_full<H, C, V>(
+    vals: &C::ValidatorSet,
+    header: &H,
+    commit: &C,
+    options: &VerificationOptions<'_>,
+) -> Result<u64, Error>
 where
     C: ProvableCommit<V>,
     H: Header,
     V: Validator,
 {
     let total_power = vals.total_power();
-    let signed_power = commit.voting_power_in(header.chain_id(), vals)?;
+    let signed_power = voting_power_in(commit, header.chain_id(), vals, options)?;
 
     // check the signers account for +2/3 of the voting power
     if signed_power * 3 <= total_power * 2 {
@@ -269,19 +1440,47 @@ where
         }
         .into());
     }
+    record_decision(
+        options,
+        Decision::CommitFull {
+            signed: signed_power,
+            total: total_power,
+        },
+    );
 
-    Ok(())
+    Ok(signed_power)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::errors::Kind;
+    use crate::types::account;
     use crate::types::block::traits::header::Header;
     use crate::types::hash::{Algorithm, Hash};
-    use crate::types::mocks::{fixed_hash, MockCommit, MockHeader, MockSignedHeader, MockValSet};
+    use crate::types::mocks::{
+        fixed_hash, generate_keypairs, light_header, signed_commit, signed_header_at,
+        trusted_state_at, validator_info, validator_set, MockCommit, MockHeader, MockSignedHeader,
+        MockValSet,
+    };
     use crate::types::traits::validator_set::ValidatorSet;
-    use crate::verification::{is_within_trust_period, verify_single_inner};
-    use crate::{validate_initial_signed_header_and_valset, TrustThresholdFraction, TrustedState};
+    use crate::types::validator::{Info, Set};
+    use crate::verification::{
+        is_power_concentrated, is_within_trust_period, match_validator_set, power_gap,
+        requires_valset_update, signed_fraction, valset_churn, verify_commit_with_lookup,
+        verify_data_hash, verify_genesis_header, verify_next_validators, verify_self_contained,
+        verify_single_expecting, verify_single_from_slices, verify_single_inner,
+        verify_single_lazy, verify_single_with_options, verify_single_with_output,
+        verify_single_with_trace, verify_single_without_next_vals, verify_trusted_state_chain,
+        verify_validator_in_set, verify_valset_continuity, verify_version, Decision, HashMismatch,
+        MismatchedHash, VerificationOptions, DEFAULT_MAX_CLOCK_DRIFT, DEFAULT_MAX_COMMIT_ROUND,
+    };
+    use crate::{
+        validate_initial_signed_header_and_valset, verify_single, LightHeader, LightSignedHeader,
+        LightTrustedState, LightValidator, Relationship, SignatureSchemeRegistry, Time,
+        TrustThresholdFraction, TrustedState, ED25519_KEY_TYPE,
+    };
     use rand::Rng;
+    use std::cell::{Cell, RefCell};
     use std::time::{Duration, SystemTime};
 
     type MockState = TrustedState<MockCommit<usize>, MockHeader, usize>;
@@ -346,8 +1545,10 @@ mod tests {
             ts,
             &un_sh,
             &un_vals,
-            &un_next_vals,
+            Some(&un_next_vals),
             TrustThresholdFraction::default(),
+            init_time() + Duration::new(10, 0),
+            &VerificationOptions::default(),
         );
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().to_string(), err_str);
@@ -360,8 +1561,10 @@ mod tests {
             ts,
             &un_sh,
             &un_vals,
-            &un_next_vals,
-            TrustThresholdFraction::default()
+            Some(&un_next_vals),
+            TrustThresholdFraction::default(),
+            init_time() + Duration::new(10, 0),
+            &VerificationOptions::default(),
         )
         .is_ok());
     }
@@ -648,6 +1851,1652 @@ mod tests {
         let now = SystemTime::UNIX_EPOCH;
         let later_than_now = now + Duration::new(60, 0);
         let future_header = MockHeader::new(4, later_than_now, fixed_hash(), fixed_hash());
-        assert!(is_within_trust_period(&future_header, period, now).is_err());
+        let res = is_within_trust_period(&future_header, period, now);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            format!(
+                "trusted header time {:?} is after now {:?}",
+                later_than_now, now
+            )
+        );
+    }
+
+    #[test]
+    fn test_is_within_trust_period_leap_second() {
+        // a header at a leap second is at the same fraction of the next second
+        let leap = Time::parse_from_rfc3339("2016-12-31T23:59:60.5Z").unwrap();
+        let header = light_header(4, leap, fixed_hash(), fixed_hash());
+        let header_time = SystemTime::UNIX_EPOCH + Duration::new(1_483_228_800, 500_000_000);
+        let period = Duration::new(100, 0);
+
+        assert!(is_within_trust_period(&header, period, header_time).is_ok());
+        assert!(is_within_trust_period(&header, period, header_time + period).is_err());
+        let res = is_within_trust_period(&header, period, header_time - Duration::new(0, 1));
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::TrustedHeaderFromFuture { .. }
+        ));
+    }
+
+    #[test]
+    fn test_untrusted_header_from_future() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let now = SystemTime::UNIX_EPOCH + Duration::new(20, 0);
+        let verify = |seconds, max_clock_drift| {
+            let time = Time::unix_epoch() + Duration::new(seconds, 0);
+            verify_single_with_options(
+                trusted_state.clone(),
+                &signed_header_at(2, time, &keypairs, &vals, &all_signers),
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                now,
+                &VerificationOptions {
+                    max_clock_drift,
+                    ..VerificationOptions::default()
+                },
+            )
+        };
+
+        // within the default drift of 10s
+        assert!(verify(25, DEFAULT_MAX_CLOCK_DRIFT).is_ok());
+        assert!(verify(30, DEFAULT_MAX_CLOCK_DRIFT).is_ok());
+
+        let res = verify(31, DEFAULT_MAX_CLOCK_DRIFT);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            format!(
+                "untrusted header time {:?} is after now {:?} + max clock drift {:?}",
+                SystemTime::UNIX_EPOCH + Duration::new(31, 0),
+                now,
+                DEFAULT_MAX_CLOCK_DRIFT
+            )
+        );
+
+        // no drift allowed
+        assert!(verify(20, Duration::new(0, 0)).is_ok());
+        assert!(verify(21, Duration::new(0, 0)).is_err());
+
+        // a drift beyond the range of SystemTime
+        let res = verify(25, Duration::new(u64::MAX, 0));
+        assert!(matches!(res.err().unwrap().kind(), Kind::OutOfRange));
+    }
+
+    #[test]
+    fn test_verify_trusted_state_chain() {
+        let vals = MockValSet::new(vec![0, 1, 2]);
+        let next_vals = MockValSet::new(vec![1, 2, 3]);
+        let state = |height: u64, vals: &MockValSet<usize>, next_vals: &MockValSet<usize>| {
+            let time = init_time() + Duration::new(height, 0);
+            let header = MockHeader::new(height, time, vals.hash(), next_vals.hash());
+            let commit = MockCommit::new(header.hash(), vec![0, 1, 2]);
+            MockState::new(MockSignedHeader::new(commit, header), next_vals.clone())
+        };
+
+        // h1 hands over to next_vals, which sign h2 and are still there at h5
+        let chain = vec![
+            state(1, &vals, &next_vals),
+            state(2, &next_vals, &next_vals),
+            state(5, &next_vals, &next_vals),
+        ];
+        assert!(verify_trusted_state_chain(&chain).is_ok());
+        assert!(verify_trusted_state_chain::<MockHeader, MockCommit<usize>, usize>(&[]).is_ok());
+
+        // h2 isn't signed by the validators h1 announced
+        let broken = vec![
+            chain[0].clone(),
+            state(2, &vals, &next_vals),
+            chain[2].clone(),
+        ];
+        let (index, err) = verify_trusted_state_chain(&broken).err().unwrap();
+        assert_eq!(index, 1);
+        assert!(matches!(err.kind(), Kind::InvalidValidatorSet { .. }));
+
+        // the states must be in increasing order
+        let unordered = vec![chain[0].clone(), chain[2].clone(), chain[1].clone()];
+        let (index, err) = verify_trusted_state_chain(&unordered).err().unwrap();
+        assert_eq!(index, 2);
+        assert!(matches!(
+            err.kind(),
+            Kind::NonIncreasingHeight {
+                got: 2,
+                expected: 6
+            }
+        ));
+
+        // each validator set must be the one announced by its header
+        let tampered = vec![
+            chain[0].clone(),
+            MockState::new(chain[1].last_header().clone(), vals.clone()),
+        ];
+        let (index, err) = verify_trusted_state_chain(&tampered).err().unwrap();
+        assert_eq!(index, 1);
+        assert!(matches!(err.kind(), Kind::InvalidNextValidatorSet { .. }));
+    }
+
+    #[test]
+    fn test_verify_next_validators() {
+        let vals = MockValSet::new(vec![0, 1, 2]);
+        let next_vals = MockValSet::new(vec![1, 2, 3]);
+        let header = MockHeader::new(1, init_time(), vals.hash(), next_vals.hash());
+        let commit = MockCommit::new(header.hash(), vec![0, 1, 2]);
+        let ts = &MockState::new(MockSignedHeader::new(commit, header), next_vals.clone());
+
+        assert!(verify_next_validators(ts, &next_vals).is_ok());
+
+        let res = verify_next_validators(ts, &vals);
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InvalidNextValidatorSet { .. }
+        ));
+    }
+
+    #[test]
+    fn test_match_validator_set() {
+        let keypairs = generate_keypairs(6);
+        let vals = validator_set(&keypairs[..4], 1);
+        let next_vals = validator_set(&keypairs[1..5], 1);
+        let header = light_header(4, Time::unix_epoch(), vals.hash(), next_vals.hash());
+
+        // the sets of the heights around the header's, fetched in any order
+        let candidates = vec![
+            validator_set(&keypairs[2..6], 1),
+            next_vals.clone(),
+            vals.clone(),
+            validator_set(&keypairs[..4], 2),
+        ];
+        assert_eq!(match_validator_set(&header, &candidates), Some(&vals));
+        assert_eq!(match_validator_set(&header, &candidates[..2]), None);
+        assert_eq!(match_validator_set::<_, Info>(&header, &[]), None);
+    }
+
+    #[test]
+    fn test_requires_valset_update() {
+        let vals = MockValSet::new(vec![0, 1, 2]);
+        let next_vals = MockValSet::new(vec![1, 2, 3]);
+        let trusted_header = MockHeader::new(1, init_time(), vals.hash(), vals.hash());
+
+        // same validators all along
+        let header = MockHeader::new(2, init_time(), vals.hash(), vals.hash());
+        assert!(!requires_valset_update(&trusted_header, &header));
+
+        // the untrusted header announces new validators
+        let header = MockHeader::new(2, init_time(), vals.hash(), next_vals.hash());
+        assert!(requires_valset_update(&trusted_header, &header));
+
+        // the validators changed between the trusted and the untrusted header
+        let header = MockHeader::new(5, init_time(), next_vals.hash(), next_vals.hash());
+        assert!(requires_valset_update(&trusted_header, &header));
+    }
+
+    #[test]
+    fn test_verify_version() {
+        let header = light_header(4, Time::unix_epoch(), fixed_hash(), fixed_hash());
+
+        // header was produced with block version 10
+        assert!(verify_version(&header, 10).is_ok());
+
+        let res = verify_version(&header, 11);
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "unexpected block version (expected: 11, got: 10)"
+        );
+    }
+
+    #[test]
+    fn test_verify_data_hash() {
+        use sha2::{Digest, Sha256};
+
+        let mut header = light_header(4, Time::unix_epoch(), fixed_hash(), fixed_hash());
+        let txs = vec![b"tx1".to_vec(), b"tx2".to_vec()];
+
+        // root of a two leaf tree over the transaction hashes, computed by hand
+        let leaf = |tx: &[u8]| Sha256::digest(&[&[0], Sha256::digest(tx).as_slice()].concat());
+        let root =
+            Sha256::digest(&[&[1], leaf(b"tx1").as_slice(), leaf(b"tx2").as_slice()].concat());
+        header.data_hash = Some(Hash::new(Algorithm::Sha256, &root).unwrap());
+        assert!(verify_data_hash(&header, &txs).is_ok());
+
+        // order matters, and every transaction has to be there
+        let reordered = vec![b"tx2".to_vec(), b"tx1".to_vec()];
+        let res = verify_data_hash(&header, &reordered);
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InvalidDataHash { .. }
+        ));
+        assert!(verify_data_hash(&header, &txs[..1]).is_err());
+        assert!(verify_data_hash(&header, &[]).is_err());
+
+        // a block without transactions has no data hash
+        header.data_hash = None;
+        assert!(verify_data_hash(&header, &[]).is_ok());
+        assert!(verify_data_hash(&header, &txs).is_err());
+    }
+
+    #[test]
+    fn test_verify_validator_in_set() {
+        let keypairs = generate_keypairs(7);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(4, Time::unix_epoch(), vals.hash(), vals.hash());
+
+        let validator = vals.validators()[5];
+        let proof = vals.inclusion_proof(5).unwrap();
+        assert!(verify_validator_in_set(&validator, proof.clone(), 5, 7, &header).is_ok());
+
+        // the proof only holds for this validator, at this position of a set this size
+        let other = vals.validators()[4];
+        assert!(verify_validator_in_set(&other, proof.clone(), 5, 7, &header).is_err());
+        assert!(verify_validator_in_set(&validator, proof.clone(), 4, 7, &header).is_err());
+        assert!(verify_validator_in_set(&validator, proof.clone(), 5, 6, &header).is_err());
+        let res = verify_validator_in_set(&validator, proof[1..].to_vec(), 5, 7, &header);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "invalid inclusion proof for validator 5 of 7"
+        );
+
+        // nor for another set
+        let other_vals = validator_set(&generate_keypairs(7), 1);
+        let other_header = light_header(4, Time::unix_epoch(), other_vals.hash(), vals.hash());
+        assert!(verify_validator_in_set(&validator, proof, 5, 7, &other_header).is_err());
+    }
+
+    #[test]
+    fn test_validate_unhashable_keys() {
+        use crate::types::pubkey::PublicKey;
+        use crate::types::validator::{Info, Set};
+        use crate::types::vote::power::Power;
+        use subtle_encoding::hex;
+
+        let keypairs = generate_keypairs(3);
+        let mut infos: Vec<Info> = keypairs.iter().map(|kp| validator_info(kp, 1)).collect();
+        let secp256k1 = PublicKey::from_raw_secp256k1(
+            &hex::decode_upper(
+                "02A1633CAFCC01EBFB6D78E39F687A1F0995C62FC95F51EAD10A02EE0BE551B5DC",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        infos.push(Info::new(secp256k1, Power::new(1)));
+        let vals = Set::new(infos);
+
+        // the hash tendermint would have computed for this set isn't the one computed here
+        let header = light_header(2, Time::unix_epoch(), fixed_hash(), fixed_hash());
+        let commit = signed_commit(&header, &keypairs, &validator_set(&keypairs, 1), &[0, 1, 2]);
+        let res = validate_initial_signed_header_and_valset(
+            &LightSignedHeader::new(commit, header),
+            &vals,
+        );
+        let err = res.err().unwrap();
+        assert!(matches!(err.kind(), Kind::InvalidValidatorSet { .. }));
+        assert!(err.to_string().contains("non-ed25519 keys"));
+
+        // ed25519 only sets keep the usual message
+        let ed25519_vals = validator_set(&keypairs, 1);
+        let header = light_header(2, Time::unix_epoch(), fixed_hash(), fixed_hash());
+        let commit = signed_commit(&header, &keypairs, &ed25519_vals, &[0, 1, 2]);
+        let res = validate_initial_signed_header_and_valset(
+            &LightSignedHeader::new(commit, header),
+            &ed25519_vals,
+        );
+        assert!(!res.err().unwrap().to_string().contains("non-ed25519 keys"));
+    }
+
+    #[test]
+    fn test_verify_self_contained() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let sign =
+            |signers: &[usize]| signed_header_at(5, Time::unix_epoch(), &keypairs, &vals, signers);
+        let two_thirds = TrustThresholdFraction::default();
+        let all = TrustThresholdFraction::all();
+
+        // a unanimous commit is enough for 1/1
+        let sh = sign(&[0, 1, 2, 3]);
+        assert!(verify_self_contained(&sh, &vals, two_thirds).is_ok());
+        assert!(verify_self_contained(&sh, &vals, all).is_ok());
+
+        let sh = sign(&[0, 1, 2]);
+        assert!(verify_self_contained(&sh, &vals, two_thirds).is_ok());
+        let res = verify_self_contained(&sh, &vals, all);
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InsufficientSignedVotingPower {
+                total: 4,
+                signed: 3,
+                power_gap: 1,
+                ..
+            }
+        ));
+
+        let sh = sign(&[0, 1]);
+        let res = verify_self_contained(&sh, &vals, two_thirds);
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InvalidCommit {
+                total: 4,
+                signed: 2
+            }
+        ));
+
+        // the validators must be the ones of the header
+        let other_vals = validator_set(&generate_keypairs(4), 1);
+        let res = verify_self_contained(&sign(&[0, 1, 2, 3]), &other_vals, two_thirds);
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InvalidValidatorSet { .. }
+        ));
+    }
+
+    #[test]
+    fn test_verify_genesis_header() {
+        use crate::types::block::id::Id as BlockId;
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let genesis = light_header(1, Time::unix_epoch(), vals.hash(), vals.hash());
+        let sign = |header: &LightHeader, signers: &[usize]| {
+            let commit = signed_commit(header, &keypairs, &vals, signers);
+            LightSignedHeader::new(commit, header.clone())
+        };
+
+        let sh = sign(&genesis, &[0, 1, 2, 3]);
+        assert!(verify_genesis_header(&sh, &vals, TrustThresholdFraction::default()).is_ok());
+
+        // 3 of 4 is enough for +2/3, not for a threshold of 100%
+        let sh = sign(&genesis, &[0, 1, 2]);
+        assert!(verify_genesis_header(&sh, &vals, TrustThresholdFraction::default()).is_ok());
+        let res = verify_genesis_header(&sh, &vals, TrustThresholdFraction::all());
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InsufficientSignedVotingPower { .. }
+        ));
+        let sh = sign(&genesis, &[0, 1]);
+        let res = verify_genesis_header(&sh, &vals, TrustThresholdFraction::default());
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InvalidCommit { .. }
+        ));
+
+        // a later block isn't a genesis block, nor is a height 1 block with a past
+        let later = light_header(2, Time::unix_epoch(), vals.hash(), vals.hash());
+        let res = verify_genesis_header(
+            &sign(&later, &[0, 1, 2, 3]),
+            &vals,
+            TrustThresholdFraction::default(),
+        );
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "invalid genesis header: height is 2, expected 1"
+        );
+        let mut with_previous = genesis.clone();
+        with_previous.last_block_id = Some(BlockId::new(fixed_hash(), None));
+        let res = verify_genesis_header(
+            &sign(&with_previous, &[0, 1, 2, 3]),
+            &vals,
+            TrustThresholdFraction::default(),
+        );
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InvalidGenesisHeader
+        ));
+        let mut with_last_commit = genesis;
+        with_last_commit.last_commit_hash = Some(fixed_hash());
+        let res = verify_genesis_header(
+            &sign(&with_last_commit, &[0, 1, 2, 3]),
+            &vals,
+            TrustThresholdFraction::default(),
+        );
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InvalidGenesisHeader
+        ));
+    }
+
+    #[test]
+    fn test_is_power_concentrated() {
+        use crate::types::mocks::validator_info;
+        use crate::types::traits::validator::Validator;
+        use crate::LightValidatorSet;
+
+        let keypairs = generate_keypairs(4);
+        let distributed = validator_set(&keypairs, 10);
+        assert!(!is_power_concentrated(
+            &distributed,
+            TrustThresholdFraction::default()
+        ));
+        assert!(!is_power_concentrated(
+            &distributed,
+            TrustThresholdFraction::new(1, 3).unwrap()
+        ));
+
+        // 70 of 100, so more than 2/3
+        let powers = [70, 10, 10, 10];
+        let concentrated = LightValidatorSet::new(
+            keypairs
+                .iter()
+                .zip(powers.iter())
+                .map(|(kp, power)| validator_info(kp, *power))
+                .collect(),
+        );
+        assert!(is_power_concentrated(
+            &concentrated,
+            TrustThresholdFraction::default()
+        ));
+
+        // the dominant validator commits blocks on its own
+        let dominant = concentrated
+            .validators()
+            .iter()
+            .position(|val| val.power() == 70)
+            .unwrap();
+        let header = light_header(
+            4,
+            Time::unix_epoch(),
+            concentrated.hash(),
+            concentrated.hash(),
+        );
+        let commit = signed_commit(&header, &keypairs, &concentrated, &[dominant]);
+        let sh = LightSignedHeader::new(commit, header);
+        assert!(validate_initial_signed_header_and_valset(&sh, &concentrated).is_ok());
+
+        // no validator holds more than 1/3 of 100
+        let powers = [30, 30, 30, 10];
+        let below_a_third = LightValidatorSet::new(
+            keypairs
+                .iter()
+                .zip(powers.iter())
+                .map(|(kp, power)| validator_info(kp, *power))
+                .collect(),
+        );
+        assert!(!is_power_concentrated(
+            &below_a_third,
+            TrustThresholdFraction::new(1, 3).unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_validate_unencodable_header() {
+        let keypairs = generate_keypairs(2);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(4, Time::unix_epoch(), vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1]);
+
+        // a header from before the unix epoch can't be amino encoded,
+        // this has to be reported as an error rather than a panic
+        let mut pre_epoch_header = header;
+        pre_epoch_header.time = Time::parse_from_rfc3339("1969-12-31T23:59:59Z").unwrap();
+        let sh = LightSignedHeader::new(commit, pre_epoch_header);
+        let res = validate_initial_signed_header_and_valset(&sh, &vals);
+        assert!(res.is_err());
+        assert_eq!(
+            res.err().unwrap().kind().to_string(),
+            "Implementation specific error"
+        );
+    }
+
+    #[test]
+    fn test_verify_commit_with_lookup() {
+        let keypairs = generate_keypairs(10);
+        let vals = validator_set(&keypairs, 1);
+        let lookup = vals.lookup_index();
+        let all_signers: Vec<usize> = (0..10).collect();
+
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let header = light_header(2, time, vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &all_signers);
+
+        let res = verify_commit_with_lookup(&header, &commit, &lookup);
+        assert!(res.is_ok());
+
+        // not enough voting power
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2, 3, 4, 5]);
+        let res = verify_commit_with_lookup(&header, &commit, &lookup);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "signed voting power (6) do not account for +2/3 of the total voting power: (10)"
+        );
+
+        // commit for another header
+        let other_header = light_header(3, time, vals.hash(), vals.hash());
+        let commit = signed_commit(&other_header, &keypairs, &vals, &all_signers);
+        assert!(verify_commit_with_lookup(&header, &commit, &lookup).is_err());
+
+        // lookup of another validator set
+        let other_lookup = validator_set(&keypairs[..5], 1).lookup_index();
+        let commit = signed_commit(&header, &keypairs, &vals, &all_signers);
+        assert!(verify_commit_with_lookup(&header, &commit, &other_lookup).is_err());
+    }
+
+    #[test]
+    fn test_on_hash_mismatch() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let other_vals = validator_set(&keypairs[..3], 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let sh = signed_header_at(2, time, &keypairs, &vals, &all_signers);
+
+        let reported = RefCell::new(vec![]);
+        let on_hash_mismatch = |mismatch: &HashMismatch| reported.borrow_mut().push(*mismatch);
+        let options = VerificationOptions {
+            on_hash_mismatch: Some(&on_hash_mismatch),
+            ..VerificationOptions::default()
+        };
+        let verify = |vals, next_vals| {
+            verify_single_with_options(
+                trusted_state.clone(),
+                &sh,
+                vals,
+                next_vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+                &options,
+            )
+        };
+
+        // nothing is reported on success
+        assert!(verify(&vals, &vals).is_ok());
+        assert!(reported.borrow().is_empty());
+
+        // the error is unchanged, the hook gets the hashes
+        let res = verify(&other_vals, &vals);
+        assert_eq!(
+            res.err().unwrap().kind().to_string(),
+            format!(
+                "header's validator hash does not match actual validator hash ({:?}!={:?})",
+                vals.hash(),
+                other_vals.hash()
+            )
+        );
+        assert_eq!(
+            reported.borrow_mut().pop(),
+            Some(HashMismatch {
+                hash: MismatchedHash::Validators,
+                computed: other_vals.hash(),
+                expected: vals.hash(),
+            })
+        );
+
+        let res = verify(&vals, &other_vals);
+        assert!(res.is_err());
+        assert_eq!(
+            reported.borrow_mut().pop(),
+            Some(HashMismatch {
+                hash: MismatchedHash::NextValidators,
+                computed: other_vals.hash(),
+                expected: vals.hash(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_verify_single_without_next_vals() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let unknown_next_vals = validator_set(&keypairs[1..], 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+        let trusting_period = Duration::new(100, 0);
+        let now = SystemTime::UNIX_EPOCH + Duration::new(20, 0);
+
+        // skip to a header whose next validators we don't have
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let header = light_header(5, time, vals.hash(), unknown_next_vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &all_signers);
+        let sh = LightSignedHeader::new(commit, header.clone());
+
+        let verify = |sh, threshold| {
+            verify_single_without_next_vals(
+                &trusted_state,
+                sh,
+                &vals,
+                threshold,
+                trusting_period,
+                now,
+            )
+        };
+        assert!(verify(&sh, TrustThresholdFraction::default()).is_ok());
+
+        // the commit is still checked, as is the trust threshold
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2]);
+        let sh = LightSignedHeader::new(commit, header.clone());
+        assert!(verify(&sh, TrustThresholdFraction::default()).is_ok());
+        assert!(verify(&sh, TrustThresholdFraction::new(4, 5).unwrap()).is_err());
+
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1]);
+        let sh = LightSignedHeader::new(commit, header);
+        assert!(verify(&sh, TrustThresholdFraction::new(1, 3).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_count_signatures_verified() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let signatures_verified = Cell::new(0);
+        let options = VerificationOptions {
+            signatures_verified: Some(&signatures_verified),
+            ..VerificationOptions::default()
+        };
+        let verify = |height| {
+            let time = Time::unix_epoch() + Duration::new(10, 0);
+            verify_single_with_options(
+                trusted_state.clone(),
+                &signed_header_at(height, time, &keypairs, &vals, &[0, 1, 3]),
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+                &options,
+            )
+        };
+
+        // adjacent header: each of the 3 signatures is verified once
+        assert!(verify(2).is_ok());
+        assert_eq!(signatures_verified.get(), 3);
+
+        // skipping: verified against the trusted validators, then the full set
+        signatures_verified.set(0);
+        assert!(verify(5).is_ok());
+        assert_eq!(signatures_verified.get(), 6);
+    }
+
+    #[test]
+    fn test_verify_single_expecting() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+        let trusted_header = trusted_state.last_header().header().clone();
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let sh = signed_header_at(2, time, &keypairs, &vals, &[0, 1, 2, 3]);
+        let header = sh.header().clone();
+
+        let verify = |expected_hash| {
+            verify_single_expecting(
+                trusted_state.clone(),
+                &sh,
+                &vals,
+                &vals,
+                expected_hash,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+            )
+        };
+
+        let new_state = verify(header.hash()).unwrap();
+        assert_eq!(new_state.last_header(), &sh);
+
+        let res = verify(trusted_header.hash());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            format!(
+                "header hash does not match the expected hash ({:?}!={:?})",
+                header.hash(),
+                trusted_header.hash()
+            )
+        );
+    }
+
+    #[test]
+    fn test_power_gap() {
+        assert_eq!(power_gap(1, 3), 2);
+        assert_eq!(power_gap(3, 3), 0);
+        assert_eq!(power_gap(4, 3), 0);
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        // skipping with 1 of 4 signers, 3 are needed to trust +2/3
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let header = light_header(5, time, vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &[2]);
+        let res = verify_single(
+            trusted_state,
+            &LightSignedHeader::new(commit, header),
+            &vals,
+            &vals,
+            TrustThresholdFraction::default(),
+            Duration::new(100, 0),
+            SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+        );
+        match res.err().unwrap().kind() {
+            Kind::InsufficientSignedVotingPower {
+                signed, power_gap, ..
+            } => {
+                assert_eq!(*signed, 1);
+                assert_eq!(*power_gap, 2);
+            }
+            kind => panic!("unexpected error: {}", kind),
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_verify_commits_parallel() {
+        use crate::verification::verify_commits_parallel;
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let mut pairs: Vec<_> = (1..=8)
+            .map(|height| {
+                let time = Time::unix_epoch() + Duration::new(height, 0);
+                let header = light_header(height, time, vals.hash(), vals.hash());
+                let commit = signed_commit(&header, &keypairs, &vals, &all_signers);
+                (header, commit)
+            })
+            .collect();
+        // height 3 is only signed by half of the validators
+        pairs[2].1 = signed_commit(&pairs[2].0, &keypairs, &vals, &[0, 1]);
+        // height 6 carries the commit of height 5
+        pairs[5].1 = pairs[4].1.clone();
+
+        let chain_id = pairs[0].0.chain_id;
+        let results =
+            verify_commits_parallel(&pairs, &vals, chain_id, TrustThresholdFraction::default());
+        assert_eq!(results.len(), pairs.len());
+        for (i, result) in results.iter().enumerate() {
+            match i {
+                2 => assert!(matches!(
+                    result.as_ref().unwrap_err().kind(),
+                    Kind::InsufficientSignedVotingPower { .. }
+                )),
+                5 => assert!(matches!(
+                    result.as_ref().unwrap_err().kind(),
+                    Kind::InvalidCommitValue { .. }
+                )),
+                _ => assert!(result.is_ok(), "height {}: {:?}", i + 1, result),
+            }
+        }
+
+        // validators of another height
+        let other_vals = validator_set(&keypairs[..3], 1);
+        let results = verify_commits_parallel(
+            &pairs[..1],
+            &other_vals,
+            chain_id,
+            TrustThresholdFraction::default(),
+        );
+        assert!(results[0].is_err());
+    }
+
+    #[test]
+    fn test_strict_consensus_hash() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let mut header = light_header(2, time, vals.hash(), vals.hash());
+        header.consensus_hash = Hash::new(Algorithm::Sha256, &[0; 32]).unwrap();
+        let commit = signed_commit(&header, &keypairs, &vals, &all_signers);
+        let sh = LightSignedHeader::new(commit, header);
+
+        let verify = |strict_consensus_hash| {
+            verify_single_with_options(
+                trusted_state.clone(),
+                &sh,
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+                &VerificationOptions {
+                    strict_consensus_hash,
+                    ..VerificationOptions::default()
+                },
+            )
+        };
+
+        // allowed by default
+        assert!(verify(false).is_ok());
+
+        let res = verify(true);
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "header's consensus hash is zero"
+        );
+    }
+
+    #[test]
+    fn test_validator_set_from_wrong_height() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let next_vals = validator_set(&keypairs[..3], 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let header = light_header(2, time, vals.hash(), next_vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &all_signers);
+        let sh = LightSignedHeader::new(commit, header);
+
+        let verify = |vals, next_vals| {
+            verify_single(
+                trusted_state.clone(),
+                &sh,
+                vals,
+                next_vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+            )
+        };
+
+        assert!(verify(&vals, &next_vals).is_ok());
+
+        // validators fetched at h+1
+        let res = verify(&next_vals, &next_vals);
+        let err = res.err().unwrap();
+        assert!(matches!(err.kind(), Kind::InvalidValidatorSet { .. }));
+        assert!(err.to_string().ends_with(
+            "validator set may be from the wrong height (it matches the header's next validators, was it fetched at height 3 instead of 2?)"
+        ));
+
+        // next validators fetched at h
+        let res = verify(&vals, &vals);
+        let err = res.err().unwrap();
+        assert!(matches!(err.kind(), Kind::InvalidNextValidatorSet { .. }));
+        assert!(err.to_string().ends_with(
+            "next validator set may be from the wrong height (it matches the header's validators, was it fetched at height 2 instead of 3?)"
+        ));
+
+        // unrelated sets get no hint
+        let other_vals = validator_set(&generate_keypairs(2), 1);
+        let res = verify(&other_vals, &next_vals);
+        let err = res.err().unwrap();
+        assert!(matches!(err.kind(), Kind::InvalidValidatorSet { .. }));
+        assert!(!err.to_string().contains("wrong height"));
+    }
+
+    #[test]
+    fn test_relaxed_signatures_count() {
+        use crate::types::block::commit::CommitSigs;
+        use crate::types::block::commit_sigs::CommitSig;
+
+        let keypairs = generate_keypairs(5);
+        let vals = validator_set(&keypairs[..4], 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let header = light_header(2, time, vals.hash(), vals.hash());
+        let commit = signed_commit(&header, &keypairs, &vals, &all_signers);
+        let with_signatures = |signatures: Vec<CommitSig>| {
+            let mut commit = commit.clone();
+            commit.signatures = CommitSigs::new(signatures);
+            LightSignedHeader::new(commit, header.clone())
+        };
+        let verify = |sh: &LightSignedHeader, strict_count| {
+            verify_single_with_options(
+                trusted_state.clone(),
+                sh,
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+                &VerificationOptions {
+                    strict_count,
+                    ..VerificationOptions::default()
+                },
+            )
+        };
+
+        // one signature more than validators
+        let mut signatures = commit.signatures.clone().into_vec();
+        signatures.push(CommitSig::BlockIDFlagAbsent);
+        let sh = with_signatures(signatures);
+        let res = verify(&sh, true);
+        assert!(res
+            .err()
+            .unwrap()
+            .to_string()
+            .ends_with("commit signatures count: 5 doesn't match validators count: 4"));
+        assert!(verify(&sh, false).is_ok());
+
+        // fewer signatures than validators are still rejected
+        let signatures = commit.signatures.clone().into_vec()[..3].to_vec();
+        let sh = with_signatures(signatures);
+        let res = verify(&sh, false);
+        assert!(res
+            .err()
+            .unwrap()
+            .to_string()
+            .ends_with("commit signatures count: 3 is less than validators count: 4"));
+
+        // and so are extra signers which aren't validators
+        let outsider_vals = validator_set(&keypairs, 1);
+        let outsider_commit = signed_commit(&header, &keypairs, &outsider_vals, &[0, 1, 2, 3, 4]);
+        let outsider = outsider_commit
+            .signatures
+            .iter()
+            .find(|sig| match sig {
+                CommitSig::BlockIDFlagCommit {
+                    validator_address, ..
+                } => vals.validator(*validator_address).is_none(),
+                _ => false,
+            })
+            .cloned()
+            .unwrap();
+        let mut signatures = commit.signatures.clone().into_vec();
+        signatures.push(outsider);
+        let sh = with_signatures(signatures);
+        let res = verify(&sh, false);
+        assert!(res
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("not present in the validator set"));
+    }
+
+    #[test]
+    fn test_verify_single_from_slices() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let sh = signed_header_at(2, time, &keypairs, &vals, &all_signers);
+
+        // out of canonical order, as an RPC response might list them
+        let mut raw_vals = vals.validators().to_vec();
+        raw_vals.reverse();
+
+        let new_state = verify_single_from_slices(
+            trusted_state.clone(),
+            &sh,
+            &raw_vals,
+            &raw_vals,
+            TrustThresholdFraction::default(),
+            Duration::new(100, 0),
+            SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+        )
+        .unwrap();
+        assert_eq!(new_state.last_header(), &sh);
+        assert_eq!(new_state.validators(), &vals);
+
+        let res = verify_single_from_slices(
+            trusted_state,
+            &sh,
+            &raw_vals[1..],
+            &raw_vals,
+            TrustThresholdFraction::default(),
+            Duration::new(100, 0),
+            SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+        );
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InvalidValidatorSet { .. }
+        ));
+    }
+
+    #[test]
+    fn test_verify_single_lazy() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let other_vals = validator_set(&generate_keypairs(4), 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let sh = signed_header_at(2, time, &keypairs, &vals, &all_signers);
+
+        let loads = Cell::new(0);
+        let load_vals = || {
+            loads.set(loads.get() + 1);
+            Ok(vals.clone())
+        };
+        let verify = |sh: &LightSignedHeader, now: u64| {
+            verify_single_lazy(
+                trusted_state.clone(),
+                sh,
+                load_vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(now, 0),
+            )
+        };
+
+        // the set is needed to verify the signatures
+        let new_state = verify(&sh, 20).unwrap();
+        assert_eq!(new_state.last_header(), &sh);
+        assert_eq!(loads.get(), 1);
+
+        // but not to reject an expired trusted state
+        assert!(matches!(
+            verify(&sh, 200).err().unwrap().kind(),
+            Kind::Expired { .. }
+        ));
+        // or an adjacent header signed by other validators than the trusted ones announced
+        let mut other_header = sh.header().clone();
+        other_header.validators_hash = other_vals.hash();
+        let other_sh = LightSignedHeader::new(sh.commit().clone(), other_header);
+        assert!(matches!(
+            verify(&other_sh, 20).err().unwrap().kind(),
+            Kind::InvalidValidatorSet { .. }
+        ));
+        assert_eq!(loads.get(), 1);
+
+        // failing to load the set fails the verification
+        let res = verify_single_lazy(
+            trusted_state,
+            &sh,
+            || Err(Kind::ImplementationSpecific.into()),
+            &vals,
+            TrustThresholdFraction::default(),
+            Duration::new(100, 0),
+            SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+        );
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::ImplementationSpecific
+        ));
+    }
+
+    #[test]
+    fn test_expected_chain_id() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let mut header = light_header(2, time, vals.hash(), vals.hash());
+        header.chain_id = "other-chain".parse().unwrap();
+        let commit = signed_commit(&header, &keypairs, &vals, &all_signers);
+        let sh = LightSignedHeader::new(commit, header);
+
+        let verify = |expected_chain_id| {
+            verify_single_with_options(
+                trusted_state.clone(),
+                &sh,
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+                &VerificationOptions {
+                    expected_chain_id,
+                    ..VerificationOptions::default()
+                },
+            )
+        };
+
+        // not checked by default, nor when locked to the header's chain
+        assert!(verify(None).is_ok());
+        assert!(verify(Some("other-chain".parse().unwrap())).is_ok());
+
+        let res = verify(Some("test-chain".parse().unwrap()));
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "header's chain id does not match the expected chain id (other-chain!=test-chain)"
+        );
+    }
+
+    #[test]
+    fn test_max_skip_distance() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let verify = |height, max_skip_distance| {
+            let time = Time::unix_epoch() + Duration::new(10, 0);
+            verify_single_with_options(
+                trusted_state.clone(),
+                &signed_header_at(height, time, &keypairs, &vals, &all_signers),
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+                &VerificationOptions {
+                    max_skip_distance,
+                    ..VerificationOptions::default()
+                },
+            )
+        };
+
+        // unlimited by default
+        assert!(verify(1001, None).is_ok());
+        // up to the limit
+        assert!(verify(6, Some(5)).is_ok());
+        // adjacent headers are never skips
+        assert!(verify(2, Some(0)).is_ok());
+
+        let res = verify(7, Some(5));
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "header is 6 heights ahead of the trusted header (max skip distance: 5)"
+        );
+    }
+
+    #[test]
+    fn test_max_validators() {
+        let keypairs = generate_keypairs(200);
+        let vals = validator_set(&keypairs[..4], 1);
+        let oversized_vals = validator_set(&keypairs, 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs[..4], &vals);
+        // announces a set of 200 validators for the next height
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let header = light_header(2, time, vals.hash(), oversized_vals.hash());
+        let commit = signed_commit(&header, &keypairs[..4], &vals, &all_signers);
+        let sh = LightSignedHeader::new(commit, header);
+
+        let verify = |max_validators| {
+            verify_single_with_options(
+                trusted_state.clone(),
+                &sh,
+                &vals,
+                &oversized_vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+                &VerificationOptions {
+                    max_validators,
+                    ..VerificationOptions::default()
+                },
+            )
+        };
+
+        // unlimited by default
+        assert!(verify(None).is_ok());
+        // up to the limit
+        assert!(verify(Some(200)).is_ok());
+
+        let res = verify(Some(175));
+        assert!(matches!(
+            res.as_ref().err().unwrap().kind(),
+            Kind::TooManyValidators {
+                count: 200,
+                max_validators: 175
+            }
+        ));
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "validator set has 200 validators (max: 175)"
+        );
+    }
+
+    #[test]
+    fn test_max_header_age() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+        // the header is 50s old, well within the trusting period
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let sh = signed_header_at(2, time, &keypairs, &vals, &all_signers);
+        let now = SystemTime::UNIX_EPOCH + Duration::new(60, 0);
+
+        let verify = |max_header_age| {
+            verify_single_with_options(
+                trusted_state.clone(),
+                &sh,
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                now,
+                &VerificationOptions {
+                    max_header_age,
+                    ..VerificationOptions::default()
+                },
+            )
+        };
+
+        assert!(verify(None).is_ok());
+        assert!(verify(Some(Duration::new(50, 0))).is_ok());
+        // ages beyond what a time can represent don't overflow
+        assert!(verify(Some(Duration::new(u64::MAX, 0))).is_ok());
+        let res = verify(Some(Duration::new(30, 0)));
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::StaleHeader {
+                max_header_age,
+                ..
+            } if *max_header_age == Duration::new(30, 0)
+        ));
+    }
+
+    #[test]
+    fn test_max_commit_round() {
+        use crate::types::mocks::signed_commit_at_round;
+
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+
+        let verify = |round, max_commit_round| {
+            let time = Time::unix_epoch() + Duration::new(10, 0);
+            let header = light_header(2, time, vals.hash(), vals.hash());
+            let commit = signed_commit_at_round(&header, &keypairs, &vals, &all_signers, round);
+            verify_single_with_options(
+                trusted_state.clone(),
+                &LightSignedHeader::new(commit, header),
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+                &VerificationOptions {
+                    max_commit_round,
+                    ..VerificationOptions::default()
+                },
+            )
+        };
+
+        let default = VerificationOptions::default().max_commit_round;
+        assert!(verify(3, default).is_ok());
+        assert!(verify(DEFAULT_MAX_COMMIT_ROUND, default).is_ok());
+
+        let res = verify(u64::MAX - 1, default);
+        let err = res.err().unwrap();
+        assert!(matches!(err.kind(), Kind::OutOfRange));
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "value out of range: commit round {} is above {}",
+                u64::MAX - 1,
+                DEFAULT_MAX_COMMIT_ROUND
+            )
+        );
+        assert!(verify(4, Some(3)).is_err());
+
+        // the bound can be lifted
+        assert!(verify(u64::MAX - 1, None).is_ok());
+    }
+
+    #[test]
+    fn test_full_validator_rotation() {
+        // every epoch hands over to a disjoint set of validators
+        let epochs: Vec<_> = (0..3).map(|_| generate_keypairs(4)).collect();
+        let sets: Vec<_> = epochs.iter().map(|kps| validator_set(kps, 1)).collect();
+        let all_signers: Vec<usize> = (0..4).collect();
+        let now = SystemTime::UNIX_EPOCH + Duration::new(100, 0);
+        // the header at `height` is signed by the set of `epoch`, handing over to `next`
+        let signed_header = |height: u64, epoch: usize, next: usize| {
+            let time = Time::unix_epoch() + Duration::new(height, 0);
+            let header = light_header(height, time, sets[epoch].hash(), sets[next].hash());
+            let commit = signed_commit(&header, &epochs[epoch], &sets[epoch], &all_signers);
+            LightSignedHeader::new(commit, header)
+        };
+        assert_eq!(sets[0].intersect(&sets[1]).number_of_validators(), 0);
+
+        let first = signed_header(1, 0, 1);
+        let mut trusted_state = TrustedState::new(first, sets[1].clone());
+        for (height, epoch, next) in [(2, 1, 1), (3, 1, 2), (4, 2, 2)].iter() {
+            trusted_state = verify_single(
+                trusted_state,
+                &signed_header(*height, *epoch, *next),
+                &sets[*epoch],
+                &sets[*next],
+                TrustThresholdFraction::default(),
+                Duration::new(1000, 0),
+                now,
+            )
+            .unwrap();
+        }
+        assert_eq!(trusted_state.last_header().header().height(), 4);
+        assert_eq!(trusted_state.validators(), &sets[2]);
+
+        // adjacent headers must still be signed by the announced set
+        let first = signed_header(1, 0, 1);
+        let res = verify_single(
+            TrustedState::new(first.clone(), sets[1].clone()),
+            &signed_header(2, 2, 2),
+            &sets[2],
+            &sets[2],
+            TrustThresholdFraction::default(),
+            Duration::new(1000, 0),
+            now,
+        );
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InvalidValidatorSet { .. }
+        ));
+
+        // while skipping over a full rotation can't be trusted
+        let res = verify_single(
+            TrustedState::new(first, sets[1].clone()),
+            &signed_header(4, 2, 2),
+            &sets[2],
+            &sets[2],
+            TrustThresholdFraction::default(),
+            Duration::new(1000, 0),
+            now,
+        );
+        assert!(matches!(
+            res.err().unwrap().kind(),
+            Kind::InsufficientSignedVotingPower { signed: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn test_signed_fraction() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let header = light_header(2, Time::unix_epoch(), vals.hash(), vals.hash());
+
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 3]);
+        let fraction = signed_fraction(&commit, header.chain_id, &vals).unwrap();
+        assert!((fraction - 0.75).abs() < f64::EPSILON);
+
+        let commit = signed_commit(&header, &keypairs, &vals, &[0, 1, 2, 3]);
+        let fraction = signed_fraction(&commit, header.chain_id, &vals).unwrap();
+        assert!((fraction - 1.0).abs() < f64::EPSILON);
+
+        let empty_vals = validator_set(&[], 1);
+        let fraction = signed_fraction(&commit, header.chain_id, &empty_vals).unwrap();
+        assert!(fraction.abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_verify_valset_continuity() {
+        let keypairs = generate_keypairs(8);
+        let one_third = TrustThresholdFraction::new(1, 3).unwrap();
+
+        // one validator rotated out at each step
+        let benign: Vec<_> = (0..4)
+            .map(|i| validator_set(&keypairs[i..i + 4], 1))
+            .collect();
+        assert!(verify_valset_continuity(&benign, one_third).is_ok());
+        assert!(verify_valset_continuity(&benign[..1], one_third).is_ok());
+        assert!(verify_valset_continuity::<LightValidator, _>(&[], one_third).is_ok());
+
+        // the whole set swapped at once
+        let mut takeover = benign[..2].to_vec();
+        takeover.push(validator_set(&keypairs[6..8], 1));
+        let (index, err) = verify_valset_continuity(&takeover, one_third)
+            .err()
+            .unwrap();
+        assert_eq!(index, 2);
+        assert!(matches!(
+            err.kind(),
+            Kind::InsufficientValidatorSetOverlap {
+                overlap: 0,
+                total: 4,
+                ..
+            }
+        ));
+
+        // keeping half of the power is enough for 1/3, but not for 2/3
+        let half = vec![
+            validator_set(&keypairs[0..4], 1),
+            validator_set(&keypairs[2..6], 1),
+        ];
+        assert!(verify_valset_continuity(&half, one_third).is_ok());
+        let (index, _) = verify_valset_continuity(&half, TrustThresholdFraction::default())
+            .err()
+            .unwrap();
+        assert_eq!(index, 1);
+    }
+
+    #[test]
+    fn test_verify_single_unanimous() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+        let time = Time::unix_epoch() + Duration::new(5, 0);
+        let skip_with = |signers: &[usize]| {
+            verify_single(
+                trusted_state.clone(),
+                &signed_header_at(5, time, &keypairs, &vals, signers),
+                &vals,
+                &vals,
+                TrustThresholdFraction::all(),
+                Duration::new(1000, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(100, 0),
+            )
+        };
+
+        assert!(skip_with(&[0, 1, 2, 3]).is_ok());
+        assert!(matches!(
+            skip_with(&[0, 1, 2]).err().unwrap().kind(),
+            Kind::InsufficientSignedVotingPower {
+                total: 4,
+                signed: 3,
+                power_gap: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_valset_churn() {
+        let keypairs = generate_keypairs(8);
+        let sets = vec![
+            validator_set(&keypairs[0..4], 1),
+            // one of the 4 validators replaced: 2 of 8
+            validator_set(&keypairs[1..5], 1),
+            // no change
+            validator_set(&keypairs[1..5], 1),
+            // all of them replaced by 3 others: 7 of 7
+            validator_set(&keypairs[5..8], 1),
+        ];
+        assert_eq!(valset_churn(&sets[..3]), 2.0 / 16.0);
+        assert_eq!(valset_churn(&sets), 9.0 / 23.0);
+        assert_eq!(valset_churn(&sets[2..]), 1.0);
+        assert_eq!(valset_churn(&sets[..1]), 0.0);
+        assert_eq!(valset_churn::<LightValidator>(&[]), 0.0);
+
+        // churn is weighted by voting power
+        let weighted = vec![
+            validator_set(&keypairs[0..4], 1),
+            Set::new(vec![
+                validator_info(&keypairs[0], 1),
+                validator_info(&keypairs[4], 2),
+            ]),
+        ];
+        assert_eq!(valset_churn(&weighted), 5.0 / 7.0);
+    }
+
+    // A trusted state at height 1 on the first 4 of 6 validators, and the set of the last
+    // 4, which shares 2 validators with the trusted one.
+    struct OverlappingSets {
+        keypairs: Vec<ed25519_dalek::Keypair>,
+        trusted_vals: Set<Info>,
+        untrusted_vals: Set<Info>,
+        trusted_state: LightTrustedState,
+    }
+
+    impl OverlappingSets {
+        fn new() -> Self {
+            let keypairs = generate_keypairs(6);
+            let trusted_vals = validator_set(&keypairs[..4], 1);
+            let untrusted_vals = validator_set(&keypairs[2..], 1);
+            let trusted_state = trusted_state_at(1, &keypairs, &trusted_vals);
+            OverlappingSets {
+                keypairs,
+                trusted_vals,
+                untrusted_vals,
+                trusted_state,
+            }
+        }
+
+        // a header at the given height, `height` seconds after the epoch, signed by all
+        // the validators of `vals`.
+        fn signed_header(&self, height: u64, vals: &Set<Info>) -> LightSignedHeader {
+            let time = Time::unix_epoch() + Duration::new(height, 0);
+            signed_header_at(height, time, &self.keypairs, vals, &[0, 1, 2, 3])
+        }
+    }
+
+    #[test]
+    fn test_verify_single_with_output() {
+        let sets = OverlappingSets::new();
+        let addresses = |kps: &[ed25519_dalek::Keypair]| -> Vec<account::Id> {
+            kps.iter().map(|kp| account::Id::from(kp.public)).collect()
+        };
+        let verify = |height: u64, vals: &Set<Info>| {
+            verify_single_with_output(
+                sets.trusted_state.clone(),
+                &sets.signed_header(height, vals),
+                vals,
+                vals,
+                TrustThresholdFraction::new(1, 3).unwrap(),
+                Duration::new(1000, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(100, 0),
+                &VerificationOptions::default(),
+            )
+        };
+
+        // adjacent: trusted on the whole untrusted set
+        let output = verify(2, &sets.trusted_vals).unwrap();
+        assert_eq!(output.checked_validators, addresses(&sets.keypairs[..4]));
+        assert_eq!(output.trusted_state.last_header().header().height(), 2);
+
+        // skip: trusted on the validators which are still part of the set only
+        let output = verify(5, &sets.untrusted_vals).unwrap();
+        assert_eq!(output.checked_validators, addresses(&sets.keypairs[2..4]));
+        assert_eq!(output.trusted_state.validators(), &sets.untrusted_vals);
+    }
+
+    #[test]
+    fn test_verify_single_with_trace() {
+        let sets = OverlappingSets::new();
+        let verify = |height: u64, vals: &Set<Info>| {
+            verify_single_with_trace(
+                sets.trusted_state.clone(),
+                &sets.signed_header(height, vals),
+                vals,
+                vals,
+                TrustThresholdFraction::new(1, 3).unwrap(),
+                Duration::new(1000, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(100, 0),
+                &VerificationOptions::default(),
+            )
+        };
+
+        // skip: 2 of the 4 trusted validators signed, enough for 1/3
+        let (result, trace) = verify(5, &sets.untrusted_vals);
+        assert!(result.is_ok());
+        assert_eq!(
+            trace.decisions(),
+            &[
+                Decision::WithinTrustingPeriod,
+                Decision::SignedHeaderAndValsValid,
+                Decision::TimeValid,
+                Decision::Relationship(Relationship::Skip(4)),
+                Decision::SkipDistance,
+                Decision::TrustedOverlap {
+                    signed: 2,
+                    required: 2
+                },
+                Decision::CommitFull {
+                    signed: 4,
+                    total: 4
+                },
+            ][..]
+        );
+
+        // adjacent: no overlap check
+        let (result, trace) = verify(2, &sets.trusted_vals);
+        assert!(result.is_ok());
+        assert!(trace.contains(&Decision::AdjacentValidatorsHash));
+        assert!(!trace
+            .decisions()
+            .iter()
+            .any(|decision| matches!(decision, Decision::TrustedOverlap { .. })));
+
+        // rejected: the trace stops before the failed check
+        let (result, trace) = verify(2, &sets.untrusted_vals);
+        assert!(matches!(
+            result.err().unwrap().kind(),
+            Kind::InvalidValidatorSet { .. }
+        ));
+        assert_eq!(
+            trace.decisions().last(),
+            Some(&Decision::Relationship(Relationship::Adjacent))
+        );
+    }
+
+    #[test]
+    fn test_signature_schemes() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let all_signers: Vec<usize> = (0..4).collect();
+
+        let trusted_state = trusted_state_at(1, &keypairs, &vals);
+        let time = Time::unix_epoch() + Duration::new(10, 0);
+        let sh = signed_header_at(5, time, &keypairs, &vals, &all_signers);
+        let signatures_verified = Cell::new(0);
+        let verify = |schemes: &SignatureSchemeRegistry| {
+            verify_single_with_options(
+                trusted_state.clone(),
+                &sh,
+                &vals,
+                &vals,
+                TrustThresholdFraction::default(),
+                Duration::new(100, 0),
+                SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+                &VerificationOptions {
+                    signature_schemes: Some(schemes),
+                    signatures_verified: Some(&signatures_verified),
+                    ..VerificationOptions::default()
+                },
+            )
+        };
+
+        // the default schemes verify the ed25519 signatures, once for the trusted
+        // validators and once for the untrusted ones
+        assert!(verify(&SignatureSchemeRegistry::default()).is_ok());
+        assert_eq!(signatures_verified.get(), 8);
+        assert!(verify(&SignatureSchemeRegistry::no_alloc()).is_ok());
+        assert_eq!(signatures_verified.get(), 16);
+
+        // the registered verifier is the one used
+        fn reject_all(_public_key: &[u8], _sign_bytes: &[u8], _signature: &[u8]) -> bool {
+            false
+        }
+        let mut rejecting = SignatureSchemeRegistry::default();
+        rejecting.register(ED25519_KEY_TYPE, reject_all);
+        assert!(verify(&rejecting)
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("Couldn't verify signature"));
+
+        let res = verify(&SignatureSchemeRegistry::empty());
+        assert_eq!(
+            res.err().unwrap().to_string(),
+            "invalid key: no signature scheme registered for key type tendermint/PubKeyEd25519"
+        );
     }
 }
@@ -0,0 +1,215 @@
+//! Non-fatal anomalies of verified headers, for operators to notice risky transitions
+
+use std::time::{Duration, SystemTime};
+
+use crate::errors::Error;
+use crate::types::account;
+use crate::types::block::commit::SignedHeader;
+use crate::types::block::traits::commit::ProvableCommit;
+use crate::types::block::traits::header::Header;
+use crate::types::traits::trusted::TrustThreshold;
+use crate::types::traits::validator::Validator;
+use crate::types::traits::validator_set::ValidatorSet;
+use crate::types::trusted::TrustedState;
+use crate::verification::{power_churned_between, verify_single};
+
+/// Churn (see `valset_churn`) between the trusted validators and the validators of the
+/// verified header above which `Warning::LargeValidatorSetChange` is reported.
+pub const LARGE_VALIDATOR_SET_CHANGE: f64 = 1.0 / 3.0;
+
+/// A risky but valid aspect of a header verified with `verify_single_with_warnings`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    /// The trusted header the new one was verified from was more than 2/3 through its
+    /// trusting period: the client is close to having to be reset subjectively.
+    OldTrustedHeader {
+        age: Duration,
+        trusting_period: Duration,
+    },
+    /// A single validator of the new trusted validator set holds enough power to make the
+    /// client trust a header on its own signature (see `is_power_concentrated`).
+    ConcentratedPower {
+        address: account::Id,
+        power: u64,
+        total_power: u64,
+    },
+    /// The validators of the verified header differ from the trusted ones by more than
+    /// `LARGE_VALIDATOR_SET_CHANGE` (see `valset_churn`).
+    LargeValidatorSetChange { churn: f64 },
+}
+
+/// Same as `verify_single`, also returning the warnings about the verified header, in the
+/// order of the `Warning` variants. Warnings are only produced for verified headers.
+#[allow(clippy::type_complexity)]
+pub fn verify_single_with_warnings<H, C, L, V>(
+    trusted_state: TrustedState<C, H, V>,
+    untrusted_sh: &SignedHeader<C, H>,
+    untrusted_vals: &C::ValidatorSet,
+    untrusted_next_vals: &C::ValidatorSet,
+    trust_threshold: L,
+    trusting_period: Duration,
+    now: SystemTime,
+) -> Result<(TrustedState<C, H, V>, Vec<Warning>), Error>
+where
+    H: Header,
+    C: ProvableCommit<V>,
+    L: TrustThreshold,
+    V: Validator,
+{
+    let age = trusted_state.age(now);
+    let (churned_power, total_power) =
+        power_churned_between::<V, _>(trusted_state.validators(), untrusted_vals);
+
+    let new_state = verify_single(
+        trusted_state,
+        untrusted_sh,
+        untrusted_vals,
+        untrusted_next_vals,
+        trust_threshold,
+        trusting_period,
+        now,
+    )?;
+
+    let mut warnings = vec![];
+    // the trusted header was within its trusting period, so it isn't from the future
+    let age = age?;
+    if age > trusting_period - trusting_period / 3 {
+        warnings.push(Warning::OldTrustedHeader {
+            age,
+            trusting_period,
+        });
+    }
+    let total_next_power = untrusted_next_vals.total_power();
+    if let Some(val) = untrusted_next_vals
+        .validators()
+        .iter()
+        .find(|val| trust_threshold.is_enough_power(val.power(), total_next_power))
+    {
+        warnings.push(Warning::ConcentratedPower {
+            address: val.address(),
+            power: val.power(),
+            total_power: total_next_power,
+        });
+    }
+    if total_power > 0 {
+        let churn = churned_power as f64 / total_power as f64;
+        if churn > LARGE_VALIDATOR_SET_CHANGE {
+            warnings.push(Warning::LargeValidatorSetChange { churn });
+        }
+    }
+    Ok((new_state, warnings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::mocks::{generate_keypairs, signed_header_at, validator_info, validator_set};
+    use crate::types::time::Time;
+    use crate::types::validator::{Info, Set};
+    use crate::TrustThresholdFraction;
+    use ed25519_dalek::Keypair;
+
+    const TRUSTING_PERIOD: Duration = Duration::from_secs(90);
+
+    // verify a header at `height`, signed by all of the `untrusted` validators, against a
+    // state trusting the `trusted` ones at height 1, `elapsed` seconds after its header
+    fn verify_with_warnings(
+        trusted: (&[Keypair], &Set<Info>),
+        untrusted: (&[Keypair], &Set<Info>),
+        height: u64,
+        elapsed: u64,
+    ) -> Vec<Warning> {
+        let sign = |(keypairs, vals): (&[Keypair], &Set<Info>), height: u64| {
+            let time = Time::unix_epoch() + Duration::new(height, 0);
+            let signers: Vec<usize> = (0..vals.number_of_validators()).collect();
+            signed_header_at(height, time, keypairs, vals, &signers)
+        };
+        let trusted_state = TrustedState::new(sign(trusted, 1), trusted.1.clone());
+
+        let (new_state, warnings) = verify_single_with_warnings(
+            trusted_state,
+            &sign(untrusted, height),
+            untrusted.1,
+            untrusted.1,
+            TrustThresholdFraction::new(1, 3).unwrap(),
+            TRUSTING_PERIOD,
+            SystemTime::UNIX_EPOCH + Duration::new(1 + elapsed, 0),
+        )
+        .unwrap();
+        assert_eq!(new_state.validators(), untrusted.1);
+        warnings
+    }
+
+    #[test]
+    fn test_no_warnings() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let warnings = verify_with_warnings((&keypairs, &vals), (&keypairs, &vals), 2, 10);
+        assert_eq!(warnings, vec![]);
+    }
+
+    #[test]
+    fn test_old_trusted_header_warning() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        let warnings = verify_with_warnings((&keypairs, &vals), (&keypairs, &vals), 2, 61);
+        assert_eq!(
+            warnings,
+            vec![Warning::OldTrustedHeader {
+                age: Duration::from_secs(61),
+                trusting_period: TRUSTING_PERIOD,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_concentrated_power_warning() {
+        let keypairs = generate_keypairs(4);
+        let vals = validator_set(&keypairs, 1);
+        // the first validator has 2 of 5, more than the 1/3 trust threshold
+        let concentrated = Set::new(
+            keypairs
+                .iter()
+                .enumerate()
+                .map(|(i, kp)| validator_info(kp, if i == 0 { 2 } else { 1 }))
+                .collect(),
+        );
+        let warnings = verify_with_warnings((&keypairs, &vals), (&keypairs, &concentrated), 5, 10);
+        assert_eq!(
+            warnings,
+            vec![Warning::ConcentratedPower {
+                address: validator_info(&keypairs[0], 2).address(),
+                power: 2,
+                total_power: 5,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_large_validator_set_change_warning() {
+        let keypairs = generate_keypairs(6);
+        let trusted_vals = validator_set(&keypairs[..4], 1);
+        // half of the validators replaced: 4 of 8
+        let untrusted_vals = validator_set(&keypairs[2..], 1);
+        let warnings = verify_with_warnings(
+            (&keypairs[..4], &trusted_vals),
+            (&keypairs[2..], &untrusted_vals),
+            5,
+            10,
+        );
+        assert_eq!(
+            warnings,
+            vec![Warning::LargeValidatorSetChange { churn: 0.5 }]
+        );
+
+        // one of 4 replaced is fine: 2 of 8
+        let untrusted_vals = validator_set(&keypairs[1..5], 1);
+        let warnings = verify_with_warnings(
+            (&keypairs[..4], &trusted_vals),
+            (&keypairs[1..5], &untrusted_vals),
+            5,
+            10,
+        );
+        assert_eq!(warnings, vec![]);
+    }
+}
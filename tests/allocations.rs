@@ -0,0 +1,119 @@
+//! Allocation counts of the verification paths, as a stand-in for the gas they spend.
+//!
+//! The counting allocator replaces the global allocator of the whole binary, which is
+//! why these tests live in their own integration test instead of the unit tests.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::time::{Duration, SystemTime};
+
+use ed25519_dalek::{Keypair, SecretKey, Signer};
+use tendermint_light_client::traits::Validator;
+use tendermint_light_client::{
+    verify_commit_with_lookup, verify_single, LightValidator, PublicKey, SignatureSchemeRegistry,
+    TrustThresholdFraction, TrustedState, VotePower, ED25519_KEY_TYPE,
+};
+
+// Counts the allocations made by the current thread.
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocations<F: FnOnce() -> R, R>(f: F) -> (R, usize) {
+    let before = ALLOCATIONS.with(|count| count.get());
+    let result = f();
+    (result, ALLOCATIONS.with(|count| count.get()) - before)
+}
+
+fn keypair(seed: u8) -> Keypair {
+    let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+    let public = (&secret).into();
+    Keypair { secret, public }
+}
+
+#[test]
+fn test_lookup_path_allocates_less() {
+    let trusted_state = TrustedState::mock(1);
+    let vals = trusted_state.validators().clone();
+    let lookup = vals.lookup_index();
+    let sh = trusted_state.mock_next();
+
+    let (res, standard_allocations) = count_allocations(|| {
+        verify_single(
+            trusted_state,
+            &sh,
+            &vals,
+            &vals,
+            TrustThresholdFraction::default(),
+            Duration::new(100, 0),
+            SystemTime::UNIX_EPOCH + Duration::new(20, 0),
+        )
+    });
+    assert!(res.is_ok());
+
+    let (res, lookup_allocations) =
+        count_allocations(|| verify_commit_with_lookup(sh.header(), sh.commit(), &lookup));
+    assert!(res.is_ok());
+    assert!(
+        lookup_allocations < standard_allocations,
+        "lookup path: {} allocations, standard path: {}",
+        lookup_allocations,
+        standard_allocations
+    );
+}
+
+#[test]
+fn test_no_alloc_ed25519_verifier() {
+    let keypairs = [keypair(1), keypair(2)];
+    let info = LightValidator::new(PublicKey::Ed25519(keypairs[0].public), VotePower::new(1));
+    let public_key = keypairs[0].public.to_bytes();
+    let registry = SignatureSchemeRegistry::no_alloc();
+    let signature = keypairs[0].sign(b"sign bytes").to_bytes();
+    let mut tampered = signature;
+    tampered[0] ^= 1;
+    let other_signer = keypairs[1].sign(b"sign bytes").to_bytes();
+
+    let cases: [(&[u8], &[u8], bool); 5] = [
+        (b"sign bytes", &signature, true),
+        (b"other bytes", &signature, false),
+        (b"sign bytes", &tampered, false),
+        (b"sign bytes", &other_signer, false),
+        (b"sign bytes", &signature[..63], false),
+    ];
+    for (sign_bytes, signature, expected) in cases.iter() {
+        let (default, default_allocations) =
+            count_allocations(|| info.verify_signature(sign_bytes, signature));
+        let (no_alloc, no_alloc_allocations) = count_allocations(|| {
+            registry
+                .verify(ED25519_KEY_TYPE, &public_key, sign_bytes, signature)
+                .unwrap()
+        });
+        let (with_key, with_key_allocations) = count_allocations(|| {
+            info.verify_signature_with(&registry, sign_bytes, signature)
+                .unwrap()
+        });
+        assert_eq!(default, *expected);
+        assert_eq!(no_alloc, *expected);
+        assert_eq!(with_key, *expected);
+        // ed25519-dalek allocates the error of a rejected signature
+        assert!(default_allocations <= if *expected { 0 } else { 1 });
+        assert_eq!(no_alloc_allocations, 0);
+        assert_eq!(with_key_allocations, 0);
+    }
+}